@@ -0,0 +1,213 @@
+use crate::{
+    percentile,
+    runtime::{self, Instant},
+    AtollResult, Cluster, ParameterlessMethod, RpcRequest, Unconfigured,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Runs the same workload against several endpoints and compares their latency,
+/// error rate and how far behind the most up to date endpoint they are.
+/// Each round sends `getSlot` to every endpoint concurrently.
+#[derive(Debug)]
+pub struct EndpointBenchmark {
+    endpoints: Vec<RpcRequest<Unconfigured>>,
+    rounds: usize,
+    interval: Duration,
+}
+
+impl EndpointBenchmark {
+    /// Benchmark the given endpoints with 10 rounds and 500ms between rounds
+    pub fn new(endpoints: Vec<Cluster>) -> Self {
+        EndpointBenchmark::with_requests(
+            endpoints
+                .into_iter()
+                .map(|cluster| RpcRequest::new().change_cluster(cluster))
+                .collect(),
+        )
+    }
+
+    /// Benchmark the endpoints of the configured requests, sending `getSlot` through
+    /// the transport and with the headers of each request
+    pub fn with_requests(endpoints: Vec<RpcRequest<Unconfigured>>) -> Self {
+        EndpointBenchmark {
+            endpoints,
+            rounds: 10,
+            interval: Duration::from_millis(500),
+        }
+    }
+
+    /// Change the number of rounds
+    pub fn change_rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds.max(1);
+
+        self
+    }
+
+    /// Change the pause between rounds
+    pub fn change_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+
+        self
+    }
+
+    /// Run the benchmark and build the comparative report
+    pub async fn run(&self) -> BenchmarkReport {
+        let mut samples = vec![Vec::<Sample>::new(); self.endpoints.len()];
+
+        for round in 0..self.rounds {
+            if round > 0 {
+                runtime::sleep(self.interval).await;
+            }
+
+            let tasks = self
+                .endpoints
+                .iter()
+                .map(|endpoint| runtime::spawn(Self::sample(endpoint.template())))
+                .collect::<Vec<_>>();
+
+            let mut round_samples = Vec::with_capacity(tasks.len());
+            for task in tasks {
+                round_samples.push(task.await);
+            }
+
+            let highest_slot = round_samples
+                .iter()
+                .filter_map(|sample| sample.slot)
+                .max()
+                .unwrap_or_default();
+
+            for (index, mut sample) in round_samples.into_iter().enumerate() {
+                sample.slot_lag = sample.slot.map(|slot| highest_slot.saturating_sub(slot));
+                samples[index].push(sample);
+            }
+        }
+
+        BenchmarkReport {
+            rounds: self.rounds as u64,
+            endpoints: self
+                .endpoints
+                .iter()
+                .zip(samples)
+                .map(|(endpoint, samples)| {
+                    EndpointReport::from_samples(endpoint.cluster().url(), &samples)
+                })
+                .collect(),
+        }
+    }
+
+    async fn sample(endpoint: RpcRequest<Unconfigured>) -> Sample {
+        let started = Instant::now();
+        let slot = Self::get_slot(endpoint).await.ok();
+
+        Sample {
+            latency: started.elapsed(),
+            slot,
+            slot_lag: None,
+        }
+    }
+
+    async fn get_slot(endpoint: RpcRequest<Unconfigured>) -> AtollResult<u64> {
+        endpoint
+            .add_parameterless_method(ParameterlessMethod::GetSlot)
+            .request::<u64>()
+            .await?
+            .into_result()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Sample {
+    latency: Duration,
+    slot: Option<u64>,
+    slot_lag: Option<u64>,
+}
+
+/// The results of an [EndpointBenchmark]
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub rounds: u64,
+    pub endpoints: Vec<EndpointReport>,
+}
+
+impl BenchmarkReport {
+    /// The endpoints ordered from the lowest to the highest median latency,
+    /// endpoints that never responded last
+    pub fn ranked(&self) -> Vec<&EndpointReport> {
+        let mut ranked = self.endpoints.iter().collect::<Vec<&EndpointReport>>();
+        ranked.sort_by_key(|report| (report.successes == 0, report.latency_p50_micros));
+
+        ranked
+    }
+}
+
+/// The benchmark results of a single endpoint. Latencies are in microseconds
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointReport {
+    pub url: String,
+    pub requests: u64,
+    pub successes: u64,
+    pub errors: u64,
+    /// The share of failed requests from `0.0` to `1.0`
+    pub error_rate: f64,
+    pub latency_min_micros: u64,
+    pub latency_p50_micros: u64,
+    pub latency_p90_micros: u64,
+    pub latency_p99_micros: u64,
+    pub latency_max_micros: u64,
+    /// The average number of slots behind the most up to date endpoint of each round
+    pub average_slot_lag: f64,
+    pub max_slot_lag: u64,
+}
+
+impl EndpointReport {
+    fn from_samples(url: &str, samples: &[Sample]) -> Self {
+        let requests = samples.len() as u64;
+        let successes = samples
+            .iter()
+            .filter(|sample| sample.slot.is_some())
+            .count() as u64;
+
+        let mut latencies = samples
+            .iter()
+            .filter(|sample| sample.slot.is_some())
+            .map(|sample| sample.latency.as_micros() as u64)
+            .collect::<Vec<u64>>();
+        latencies.sort_unstable();
+
+        let slot_lags = samples
+            .iter()
+            .filter_map(|sample| sample.slot_lag)
+            .collect::<Vec<u64>>();
+
+        EndpointReport {
+            url: url.to_owned(),
+            requests,
+            successes,
+            errors: requests - successes,
+            error_rate: if requests == 0 {
+                0.0
+            } else {
+                (requests - successes) as f64 / requests as f64
+            },
+            latency_min_micros: latencies.first().copied().unwrap_or_default(),
+            latency_p50_micros: percentile(&latencies, 50),
+            latency_p90_micros: percentile(&latencies, 90),
+            latency_p99_micros: percentile(&latencies, 99),
+            latency_max_micros: latencies.last().copied().unwrap_or_default(),
+            average_slot_lag: if slot_lags.is_empty() {
+                0.0
+            } else {
+                slot_lags.iter().sum::<u64>() as f64 / slot_lags.len() as f64
+            },
+            max_slot_lag: slot_lags.iter().copied().max().unwrap_or_default(),
+        }
+    }
+}
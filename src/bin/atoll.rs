@@ -0,0 +1,259 @@
+//! A lightweight command line client for the operations supported by Atoll.
+//! Build it with `cargo build --features cli`.
+
+use atoll::{
+    AtollError, Block, Cluster, EndpointBenchmark, GetAccountInfo, RpcMethod, RpcRequest,
+    RpcResult, LAMPORTS,
+};
+use std::{env, process::ExitCode};
+
+const USAGE: &str = "Usage: atoll [--cluster <localnet|devnet|testnet|mainnet|url>] <command>
+
+Commands:
+    balance <pubkey>            Get the balance of an account in SOL
+    account <pubkey>            Get the account information of an account
+    block <slot>                Get a confirmed block
+    send <base64 transaction>   Send a signed wire encoded transaction
+    airdrop <pubkey> <sol>      Request an airdrop of SOL (not available on mainnet)
+    confirm <signature>         Get the confirmation status of a transaction
+    bench <rounds> <cluster>... Compare the latency, error rate and slot lag of clusters";
+
+type CliResult<T> = Result<T, CliError>;
+
+/// The errors of the command line client
+#[derive(Debug)]
+enum CliError {
+    /// An argument could not be parsed
+    Argument(String),
+    Atoll(AtollError),
+}
+
+impl From<AtollError> for CliError {
+    fn from(error: AtollError) -> Self {
+        CliError::Atoll(error)
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(error: serde_json::Error) -> Self {
+        CliError::Atoll(error.into())
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1).collect::<Vec<String>>();
+
+    let mut cluster = Cluster::DevNet;
+    if let Some(position) = args.iter().position(|arg| arg == "--cluster") {
+        let Some(value) = args.get(position + 1) else {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        };
+
+        cluster = match parse_cluster(value) {
+            Some(cluster) => cluster,
+            None => {
+                eprintln!("Unknown cluster `{}`\n\n{}", value, USAGE);
+                return ExitCode::FAILURE;
+            }
+        };
+        args.drain(position..=position + 1);
+    }
+
+    let args = args.iter().map(String::as_str).collect::<Vec<&str>>();
+
+    let outcome = smol::block_on(async {
+        match args.as_slice() {
+            ["balance", pubkey] => balance(cluster, pubkey).await,
+            ["account", pubkey] => account(cluster, pubkey).await,
+            ["block", slot] => block(cluster, slot).await,
+            ["send", transaction] => send(cluster, transaction).await,
+            ["airdrop", pubkey, sol] => airdrop(cluster, pubkey, sol).await,
+            ["confirm", signature] => confirm(cluster, signature).await,
+            ["bench", rounds, clusters @ ..] if !clusters.is_empty() => {
+                bench(rounds, clusters).await
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return Ok(false);
+            }
+        }
+        .map(|_| true)
+    });
+
+    match outcome {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(CliError::Argument(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            ExitCode::FAILURE
+        }
+        Err(CliError::Atoll(error)) => {
+            eprintln!("Error: {:?}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn balance(cluster: Cluster, pubkey: &str) -> CliResult<()> {
+    let lamports = RpcRequest::new()
+        .change_cluster(cluster)
+        .get_balance(pubkey)
+        .request::<RpcResult<u64>>()
+        .await?
+        .into_result()?
+        .value
+        .unwrap_or_default();
+
+    println!("{} SOL", lamports as f64 / LAMPORTS as f64);
+
+    Ok(())
+}
+
+async fn account(cluster: Cluster, pubkey: &str) -> CliResult<()> {
+    let account = RpcRequest::new()
+        .change_cluster(cluster)
+        .get_account_info(pubkey)
+        .add_extra("encoding", "base64".into())
+        .request::<RpcResult<GetAccountInfo>>()
+        .await?
+        .into_result()?
+        .value;
+
+    match account {
+        Some(account) => println!("{:#?}", account),
+        None => println!("Account {} does not exist", pubkey),
+    }
+
+    Ok(())
+}
+
+async fn block(cluster: Cluster, slot: &str) -> CliResult<()> {
+    let slot = parse_number::<u64>(slot, "slot")?;
+
+    let block = RpcRequest::new()
+        .change_cluster(cluster)
+        .get_block(slot)
+        .add_extra("encoding", "base64".into())
+        .add_extra("maxSupportedTransactionVersion", 0.into())
+        .request::<Block>()
+        .await?
+        .into_result()?;
+
+    println!("{}", serde_json::to_string_pretty(&block)?);
+
+    Ok(())
+}
+
+async fn send(cluster: Cluster, transaction: &str) -> CliResult<()> {
+    let signature = RpcRequest::new()
+        .change_cluster(cluster)
+        .add_method(RpcMethod::SendTransaction)
+        .add_value(transaction.into())
+        .add_extra("encoding", "base64".into())
+        .request::<String>()
+        .await?
+        .into_result()?;
+
+    println!("{}", signature);
+
+    Ok(())
+}
+
+async fn airdrop(cluster: Cluster, pubkey: &str, sol: &str) -> CliResult<()> {
+    let amount = parse_number::<f64>(sol, "SOL amount")?;
+    if !(amount.is_finite() && amount > 0.0) {
+        return Err(CliError::Argument(format!(
+            "Invalid SOL amount `{}`, expected a positive number",
+            sol
+        )));
+    }
+    let lamports = (amount * LAMPORTS as f64) as u64;
+
+    let signature = RpcRequest::new()
+        .change_cluster(cluster)
+        .add_method(RpcMethod::RequestAirdrop)
+        .add_value(pubkey.into())
+        .add_param(lamports.into())
+        .request::<String>()
+        .await?
+        .into_result()?;
+
+    println!("{}", signature);
+
+    Ok(())
+}
+
+async fn confirm(cluster: Cluster, signature: &str) -> CliResult<()> {
+    let statuses = RpcRequest::new()
+        .change_cluster(cluster)
+        .add_method(RpcMethod::GetSignatureStatuses)
+        .add_value(json::array![signature])
+        .add_extra("searchTransactionHistory", true.into())
+        .request::<RpcResult<Vec<Option<serde_json::Value>>>>()
+        .await?
+        .into_result()?;
+
+    match statuses
+        .value
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .flatten()
+    {
+        Some(status) => println!("{}", serde_json::to_string_pretty(&status)?),
+        None => println!("Transaction {} was not found", signature),
+    }
+
+    Ok(())
+}
+
+async fn bench(rounds: &str, clusters: &[&str]) -> CliResult<()> {
+    let rounds = parse_number::<usize>(rounds, "number of rounds")?;
+    let clusters = clusters
+        .iter()
+        .map(|cluster| {
+            parse_cluster(cluster)
+                .ok_or_else(|| CliError::Argument(format!("Unknown cluster `{}`", cluster)))
+        })
+        .collect::<CliResult<Vec<Cluster>>>()?;
+
+    let report = EndpointBenchmark::new(clusters)
+        .change_rounds(rounds)
+        .run()
+        .await;
+
+    println!(
+        "{:<45} {:>8} {:>10} {:>10} {:>10} {:>10}",
+        "endpoint", "errors", "p50 ms", "p90 ms", "avg lag", "max lag"
+    );
+    for endpoint in report.ranked() {
+        println!(
+            "{:<45} {:>8} {:>10.1} {:>10.1} {:>10.1} {:>10}",
+            endpoint.url,
+            endpoint.errors,
+            endpoint.latency_p50_micros as f64 / 1000.0,
+            endpoint.latency_p90_micros as f64 / 1000.0,
+            endpoint.average_slot_lag,
+            endpoint.max_slot_lag
+        );
+    }
+
+    Ok(())
+}
+
+fn parse_cluster(value: &str) -> Option<Cluster> {
+    match value {
+        "localnet" => Some(Cluster::LocalNet),
+        "devnet" => Some(Cluster::DevNet),
+        "testnet" => Some(Cluster::TestNet),
+        "mainnet" | "mainnet-beta" => Some(Cluster::MainNetBeta),
+        url => Cluster::custom(url).ok(),
+    }
+}
+
+fn parse_number<T: core::str::FromStr>(value: &str, name: &str) -> CliResult<T> {
+    value
+        .parse()
+        .map_err(|_| CliError::Argument(format!("Invalid {} `{}`", name, value)))
+}
@@ -1,7 +1,177 @@
+use crate::{AtollError, AtollResult};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use core::{fmt, str::FromStr};
+#[allow(deprecated)]
 use generic_array::{typenum::U64, GenericArray};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use web3utilities::{ed25519_dalek::PublicKey, Utilities};
 
 pub const LAMPORTS: u64 = 1_000_000_000;
 
+/// The most seeds a program address is derived from
+pub const MAX_SEEDS: usize = 16;
+/// The most bytes of one seed of a program address
+pub const MAX_SEED_LEN: usize = 32;
+
+/// Appended to the seeds of a program address so it cannot collide with other hashes
+const PDA_MARKER: &[u8] = b"ProgramDerivedAddress";
+
 /// The byte representation of an Ed25519 Signature. Stored as a `GenericArray`
 /// since Rust doesn't yet support `u256` primitive due to limitations in LLVM compiler.
+#[allow(deprecated)]
 pub type SignatureGenericArray = GenericArray<u8, U64>;
+
+/// An Ed25519 public key or a program derived address.
+/// It is displayed, serialized and parsed as a base58 string
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct Pubkey(pub [u8; 32]);
+
+impl Pubkey {
+    /// Create a public key from its bytes
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        Pubkey(bytes)
+    }
+
+    /// Parse a base58 encoded public key
+    pub fn from_base58(value: &str) -> AtollResult<Self> {
+        let bytes = Utilities::base58_to_bytes(value).map_err(AtollError::Utilities)?;
+
+        Ok(Pubkey(
+            Utilities::to_32byte_array(&bytes).map_err(AtollError::Utilities)?,
+        ))
+    }
+
+    /// The bytes of the public key
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// The base58 encoded public key
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.0).into_string()
+    }
+
+    /// Whether the bytes are a point on the Ed25519 curve, which program
+    /// derived addresses never are so that no private key can sign for them
+    pub fn is_on_curve(&self) -> bool {
+        PublicKey::from_bytes(&self.0).is_ok()
+    }
+
+    /// The program derived address of the seeds, which must include a bump seed
+    /// that pushes the address off the curve. Fails with [AtollError::InvalidSeeds]
+    /// if the address is on the curve, use [Pubkey::find_program_address] to find the bump
+    pub fn create_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> AtollResult<Pubkey> {
+        check_seeds(seeds, MAX_SEEDS)?;
+
+        let mut hasher = Sha256::new();
+        seeds.iter().for_each(|seed| hasher.update(seed));
+        hasher.update(program_id.0);
+        hasher.update(PDA_MARKER);
+        let address = Pubkey(hasher.finalize().into());
+
+        match address.is_on_curve() {
+            true => Err(AtollError::InvalidSeeds(
+                "The seeds derive an address on the Ed25519 curve".to_owned(),
+            )),
+            false => Ok(address),
+        }
+    }
+
+    /// The program derived address of the seeds and the bump seed appended to them,
+    /// trying bumps from `255` down until the address is off the curve. Programs
+    /// expect this canonical bump
+    pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> AtollResult<(Pubkey, u8)> {
+        // The bump seed takes the last place
+        check_seeds(seeds, MAX_SEEDS - 1)?;
+
+        for bump in (0..=u8::MAX).rev() {
+            let bump_seed = [bump];
+            let mut seeds_with_bump = seeds.to_vec();
+            seeds_with_bump.push(&bump_seed);
+
+            if let Ok(address) = Pubkey::create_program_address(&seeds_with_bump, program_id) {
+                return Ok((address, bump));
+            }
+        }
+
+        Err(AtollError::InvalidSeeds(
+            "No bump seed derives an address off the Ed25519 curve".to_owned(),
+        ))
+    }
+}
+
+fn check_seeds(seeds: &[&[u8]], max_seeds: usize) -> AtollResult<()> {
+    if seeds.len() > max_seeds {
+        return Err(AtollError::InvalidSeeds(format!(
+            "{} seeds given, at most {max_seeds} are allowed",
+            seeds.len()
+        )));
+    }
+
+    match seeds.iter().find(|seed| seed.len() > MAX_SEED_LEN) {
+        Some(seed) => Err(AtollError::InvalidSeeds(format!(
+            "A seed is {} bytes long, at most {MAX_SEED_LEN} are allowed",
+            seed.len()
+        ))),
+        None => Ok(()),
+    }
+}
+
+impl From<[u8; 32]> for Pubkey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Pubkey(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Pubkey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl FromStr for Pubkey {
+    type Err = AtollError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Pubkey::from_base58(value)
+    }
+}
+
+impl fmt::Display for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_base58())
+    }
+}
+
+impl fmt::Debug for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Pubkey").field(&self.to_base58()).finish()
+    }
+}
+
+impl Serialize for Pubkey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_base58())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pubkey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <String as Deserialize>::deserialize(deserializer)?;
+
+        Pubkey::from_base58(&value).map_err(|_| de::Error::custom("Invalid base58 public key"))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::TransactionError;
 use web3utilities::UtilitiesError;
 
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -13,6 +14,100 @@ pub enum AtollError {
     /// Http Errors from the `minreq` crate used for HTTP requests
     Http(Minreq),
     SerdeJsonDeser(String),
+    /// The encoding of a transaction or account data is not supported
+    UnsupportedEncoding(String),
+    /// Decoding base64 data failed
+    Base64Decode(String),
+    /// The bytes of a wire encoded transaction could not be decoded
+    MalformedTransaction(String),
+    /// A transaction could not be built or signed
+    InvalidTransaction(String),
+    /// Signing was refused because the message violates the configured signing policy
+    PolicyViolation(String),
+    /// The RPC node responded with a JSON-RPC error. `data` is the JSON text of
+    /// the `data` field of the error, if the node sent one
+    Rpc {
+        code: i16,
+        message: String,
+        data: Option<String>,
+    },
+    /// The simulated balance changes exceed the configured outflow limits
+    OutflowLimitExceeded(String),
+    /// Signing was refused because the blockhash is likely to expire before the transaction lands
+    BlockhashRejected(String),
+    /// The bytes do not form a valid Ed25519 keypair
+    InvalidKeypair(String),
+    /// Reading or writing a file failed
+    Io(String),
+    /// The seeds cannot derive a program address, because there are too many or
+    /// one is too long, or the address is an Ed25519 public key
+    InvalidSeeds(String),
+    /// An Ed25519 signature could not be verified
+    InvalidSignature(String),
+    /// The requested account or item does not exist
+    NotFound(String),
+    /// The node is too old or too new to serve the method or option requested
+    UnsupportedByNode(String),
+    /// The request was cancelled through its cancellation token
+    Cancelled,
+    /// A notification buffer with the given capacity was full
+    BufferOverflow(usize),
+    /// The receiving half of a notification channel was dropped
+    ChannelClosed,
+    /// The URL of a custom RPC endpoint is not valid
+    InvalidUrl(String),
+    /// The `id` of a response does not match the `id` of the request it answers
+    ResponseIdMismatch {
+        expected: u64,
+        received: u64,
+    },
+    /// The node kept answering with HTTP 429 until the rate limit budget of the
+    /// retry policy was spent. `retry_after` is the last `Retry-After` in seconds
+    RateLimited {
+        retry_after: Option<u64>,
+    },
+    /// The deadline of the request passed before it completed
+    DeadlineExceeded,
+    /// An HTTP transport other than `minreq` failed to send the request
+    Transport(String),
+    /// A request names more accounts or signatures than the method accepts
+    TooManyAccounts {
+        max: usize,
+        received: usize,
+    },
+    /// A serialized transaction does not fit in a packet. `size` is split into
+    /// the bytes of the signatures and of the message
+    TransactionTooLarge {
+        size: usize,
+        limit: usize,
+        signatures: usize,
+        message: usize,
+    },
+    /// The blockhash of the transaction expired before the cluster processed it,
+    /// the transaction can no longer land
+    TransactionExpired {
+        signature: String,
+        last_valid_block_height: u64,
+    },
+    /// The cluster processed the transaction but it failed
+    TransactionFailed {
+        signature: String,
+        err: TransactionError,
+    },
+    /// The simulation the node runs before forwarding a transaction failed, with
+    /// the program logs up to the failure
+    PreflightFailure {
+        err: TransactionError,
+        logs: Vec<String>,
+        units_consumed: Option<u64>,
+    },
+    /// The genesis hash of the endpoint is not the one of the selected cluster,
+    /// for example a mainnet cluster pointed at a devnet endpoint
+    WrongCluster {
+        url: String,
+        expected: String,
+        received: String,
+    },
 }
 
 /// Errors from the minreq crate
@@ -80,6 +175,7 @@ pub enum Minreq {
     Other(String),
 }
 
+#[cfg(feature = "minreq")]
 impl From<minreq::Error> for AtollError {
     fn from(minreq_error: minreq::Error) -> Self {
         match minreq_error {
@@ -131,3 +227,9 @@ impl From<serde_json::Error> for AtollError {
         AtollError::SerdeJsonDeser(error.to_string())
     }
 }
+
+impl From<base64::DecodeError> for AtollError {
+    fn from(error: base64::DecodeError) -> Self {
+        AtollError::Base64Decode(error.to_string())
+    }
+}
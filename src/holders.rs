@@ -0,0 +1,279 @@
+use crate::{
+    AtollResult, GetProgramAccountsRequest, KeyedAccount, ProgramAccountsFilter, RpcRequest,
+    Unconfigured, TOKEN_2022_PROGRAM, TOKEN_PROGRAM,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The size of an SPL Token account without Token-2022 extensions
+const TOKEN_ACCOUNT_SIZE: usize = 165;
+
+/// The balance of a single owner of a mint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenHolder {
+    pub owner: String,
+    /// The sum of the balances of all token accounts of the owner in base units
+    pub amount: u64,
+    pub token_accounts: u32,
+}
+
+/// All holders of a mint with their aggregated balances, suitable for airdrops or governance checks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenHolderSnapshot {
+    pub mint: String,
+    /// Holders sorted by amount, largest first
+    pub holders: Vec<TokenHolder>,
+    /// The sum of all balances in base units
+    pub total_amount: u128,
+    /// The number of token accounts found for the mint, including empty ones
+    pub token_accounts: u64,
+}
+
+/// Builds a [TokenHolderSnapshot] by enumerating all token accounts of a mint
+/// owned by the SPL Token and Token-2022 programs
+#[derive(Debug)]
+pub struct TokenHolderSnapshotBuilder {
+    mint: String,
+    client: RpcRequest<Unconfigured>,
+    include_empty: bool,
+}
+
+impl TokenHolderSnapshotBuilder {
+    /// Snapshot the holders of a base58 encoded mint
+    pub fn new(mint: &str) -> Self {
+        TokenHolderSnapshotBuilder {
+            mint: mint.to_owned(),
+            client: RpcRequest::new(),
+            include_empty: false,
+        }
+    }
+
+    /// Fetch the token accounts through the configured request, see [RpcRequest::template]
+    pub fn change_client(mut self, client: &RpcRequest<Unconfigured>) -> Self {
+        self.client = client.template();
+
+        self
+    }
+
+    /// Include owners whose token accounts are all empty
+    pub fn change_include_empty(mut self, include_empty: bool) -> Self {
+        self.include_empty = include_empty;
+
+        self
+    }
+
+    /// Fetch the token accounts and aggregate them by owner
+    pub async fn build(self) -> AtollResult<TokenHolderSnapshot> {
+        let token_program = bs58::encode(TOKEN_PROGRAM).into_string();
+        let token_2022_program = bs58::encode(TOKEN_2022_PROGRAM).into_string();
+
+        let mint_filter = ProgramAccountsFilter::memcmp_base58(0, &self.mint);
+        let size_filter = ProgramAccountsFilter::data_size(TOKEN_ACCOUNT_SIZE as u64);
+        // Token-2022 accounts with extensions store the account type `Account` (2) after the base account
+        let extension_filter = ProgramAccountsFilter::memcmp(TOKEN_ACCOUNT_SIZE, &[2u8]);
+
+        let mut accounts = self
+            .fetch(&token_program, [size_filter.clone(), mint_filter.clone()])
+            .await?;
+        accounts.extend(
+            self.fetch(&token_2022_program, [size_filter, mint_filter.clone()])
+                .await?,
+        );
+        accounts.extend(
+            self.fetch(&token_2022_program, [extension_filter, mint_filter])
+                .await?,
+        );
+
+        let mut holders = HashMap::<String, TokenHolder>::new();
+        let mut total_amount = 0u128;
+        let token_accounts = accounts.len() as u64;
+
+        for keyed_account in accounts {
+            let Some(data) = keyed_account.account.data.0.as_ref() else {
+                continue;
+            };
+            let data = BASE64.decode(data)?;
+
+            // The data is sliced to the owner followed by the amount
+            if data.len() < 40 {
+                continue;
+            }
+
+            let owner = bs58::encode(&data[..32]).into_string();
+            let amount = u64::from_le_bytes(data[32..40].try_into().unwrap()); // Never fails since the slice is 8 bytes
+
+            let holder = holders.entry(owner.clone()).or_insert(TokenHolder {
+                owner,
+                amount: 0,
+                token_accounts: 0,
+            });
+            holder.amount = holder.amount.saturating_add(amount);
+            holder.token_accounts += 1;
+            total_amount += amount as u128;
+        }
+
+        let mut holders = holders
+            .into_values()
+            .filter(|holder| self.include_empty || holder.amount > 0)
+            .collect::<Vec<TokenHolder>>();
+        holders.sort_by(|a, b| b.amount.cmp(&a.amount).then_with(|| a.owner.cmp(&b.owner)));
+
+        Ok(TokenHolderSnapshot {
+            mint: self.mint,
+            holders,
+            total_amount,
+            token_accounts,
+        })
+    }
+
+    async fn fetch(
+        &self,
+        program_id: &str,
+        filters: [ProgramAccountsFilter; 2],
+    ) -> AtollResult<Vec<KeyedAccount>> {
+        let typed = filters.into_iter().fold(
+            GetProgramAccountsRequest::new(program_id).change_data_slice(32, 40),
+            GetProgramAccountsRequest::add_filter,
+        );
+
+        self.client
+            .template()
+            .add_typed(typed)
+            .request()
+            .await?
+            .into_result()
+    }
+}
+
+/// The share of the supply held by the largest holders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopConcentration {
+    /// The number of largest holders
+    pub top: u64,
+    pub amount: u128,
+    /// The share of the total amount held from `0.0` to `1.0`
+    pub share: f64,
+}
+
+/// The holders whose share of the total amount falls within `[min_share, max_share)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HolderBucket {
+    pub min_share: f64,
+    pub max_share: f64,
+    pub holders: u64,
+    pub amount: u128,
+}
+
+/// Distribution statistics of the holders of a mint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HolderDistribution {
+    pub mint: String,
+    pub holders: u64,
+    pub total_amount: u128,
+    pub top_concentration: Vec<TopConcentration>,
+    /// Holder counts bucketed by their share of the total amount, smallest share first
+    pub buckets: Vec<HolderBucket>,
+    /// The Gini coefficient of the balances, `0.0` when all holders hold the same
+    /// amount and approaching `1.0` when a single holder holds everything
+    pub gini: f64,
+}
+
+impl TokenHolderSnapshot {
+    /// Compute the distribution statistics with the concentration of the top 1, 10, 50 and 100 holders
+    pub fn distribution(&self) -> HolderDistribution {
+        self.distribution_with_top(&[1, 10, 50, 100])
+    }
+
+    /// Compute the distribution statistics with the concentration of custom top holder counts
+    pub fn distribution_with_top(&self, top: &[usize]) -> HolderDistribution {
+        let share = |amount: u128| {
+            if self.total_amount == 0 {
+                0.0
+            } else {
+                amount as f64 / self.total_amount as f64
+            }
+        };
+
+        // `holders` is sorted by amount, largest first
+        let top_concentration = top
+            .iter()
+            .map(|top| {
+                let amount = self
+                    .holders
+                    .iter()
+                    .take(*top)
+                    .map(|holder| holder.amount as u128)
+                    .sum::<u128>();
+
+                TopConcentration {
+                    top: *top as u64,
+                    amount,
+                    share: share(amount),
+                }
+            })
+            .collect();
+
+        let bounds = [0.0, 0.00001, 0.0001, 0.001, 0.01, 0.1, f64::INFINITY];
+        let mut buckets = bounds
+            .windows(2)
+            .map(|bounds| HolderBucket {
+                min_share: bounds[0],
+                max_share: bounds[1],
+                holders: 0,
+                amount: 0,
+            })
+            .collect::<Vec<HolderBucket>>();
+
+        for holder in &self.holders {
+            let holder_share = share(holder.amount as u128);
+
+            if let Some(bucket) = buckets
+                .iter_mut()
+                .find(|bucket| holder_share >= bucket.min_share && holder_share < bucket.max_share)
+            {
+                bucket.holders += 1;
+                bucket.amount += holder.amount as u128;
+            }
+        }
+
+        HolderDistribution {
+            mint: self.mint.clone(),
+            holders: self.holders.len() as u64,
+            total_amount: self.total_amount,
+            top_concentration,
+            buckets,
+            gini: self.gini(),
+        }
+    }
+
+    fn gini(&self) -> f64 {
+        let count = self.holders.len() as f64;
+        let total = self
+            .holders
+            .iter()
+            .map(|holder| holder.amount as f64)
+            .sum::<f64>();
+
+        if count == 0.0 || total == 0.0 {
+            return 0.0;
+        }
+
+        // Rank the balances in ascending order starting from 1
+        let weighted = self
+            .holders
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(index, holder)| (index + 1) as f64 * holder.amount as f64)
+            .sum::<f64>();
+
+        (2.0 * weighted) / (count * total) - (count + 1.0) / count
+    }
+}
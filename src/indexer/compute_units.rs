@@ -0,0 +1,145 @@
+use crate::{Block, ParsedLogs, TxMetadata};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Aggregates the compute units consumed per program across a stream of blocks or transactions.
+/// The compute units of nested invocations are attributed to the invoked program and not the caller.
+#[derive(Debug, Clone, Default)]
+pub struct ComputeUnitAggregator {
+    programs: HashMap<String, ProgramComputeUnits>,
+    transactions: u64,
+    compute_units_consumed: u64,
+    unattributed_compute_units: u64,
+}
+
+impl ComputeUnitAggregator {
+    /// Create a new empty aggregator
+    pub fn new() -> Self {
+        ComputeUnitAggregator::default()
+    }
+
+    /// Add the compute units of all transactions in a block
+    pub fn ingest_block(&mut self, block: &Block) {
+        block
+            .transactions
+            .iter()
+            .for_each(|transaction| self.ingest_transaction(&transaction.meta));
+    }
+
+    /// Add the compute units of a single transaction
+    pub fn ingest_transaction(&mut self, meta: &TxMetadata) {
+        let parsed = ParsedLogs::parse(&meta.log_messages);
+
+        let mut attributed = 0u64;
+        let mut seen_in_transaction = Vec::<&str>::new();
+
+        for (index, invocation) in parsed.invocations.iter().enumerate() {
+            let program = self
+                .programs
+                .entry(invocation.program_id.clone())
+                .or_insert_with(|| ProgramComputeUnits {
+                    program_id: invocation.program_id.clone(),
+                    ..Default::default()
+                });
+
+            program.invocations += 1;
+
+            if !seen_in_transaction.contains(&invocation.program_id.as_str()) {
+                program.transactions += 1;
+                seen_in_transaction.push(&invocation.program_id);
+            }
+
+            if let Some(exclusive) = parsed.exclusive_compute_units(index) {
+                program.compute_units += exclusive;
+                program.max_invocation_compute_units =
+                    program.max_invocation_compute_units.max(exclusive);
+                attributed += exclusive;
+            }
+        }
+
+        let total = meta.compute_units_consumed.unwrap_or(attributed);
+
+        self.transactions += 1;
+        self.compute_units_consumed += total;
+        self.unattributed_compute_units += total.saturating_sub(attributed);
+    }
+
+    /// Get the aggregated compute units, programs sorted by the compute units they consumed
+    pub fn report(&self) -> ComputeUnitReport {
+        let mut programs = self.programs.values().cloned().collect::<Vec<_>>();
+        programs.sort_by(|a, b| {
+            b.compute_units
+                .cmp(&a.compute_units)
+                .then_with(|| a.program_id.cmp(&b.program_id))
+        });
+
+        ComputeUnitReport {
+            transactions: self.transactions,
+            compute_units_consumed: self.compute_units_consumed,
+            unattributed_compute_units: self.unattributed_compute_units,
+            programs,
+        }
+    }
+
+    /// Forget all aggregated data
+    pub fn reset(&mut self) {
+        *self = ComputeUnitAggregator::default();
+    }
+}
+
+/// The compute units consumed by a single program
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramComputeUnits {
+    pub program_id: String,
+    /// The number of times the program was invoked, including CPI
+    pub invocations: u64,
+    /// The number of transactions that invoked the program
+    pub transactions: u64,
+    /// The compute units consumed by the program itself
+    pub compute_units: u64,
+    /// The largest number of compute units consumed by a single invocation
+    pub max_invocation_compute_units: u64,
+}
+
+/// The compute units consumed per program as aggregated by a [ComputeUnitAggregator]
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputeUnitReport {
+    pub transactions: u64,
+    /// The total from `computeUnitsConsumed` of each transaction
+    pub compute_units_consumed: u64,
+    /// Compute units that could not be attributed to a program, usually due to truncated logs
+    pub unattributed_compute_units: u64,
+    /// Programs sorted by the compute units they consumed, highest first
+    pub programs: Vec<ProgramComputeUnits>,
+}
+
+impl ComputeUnitReport {
+    /// The `count` programs that consumed the most compute units
+    pub fn top(&self, count: usize) -> &[ProgramComputeUnits] {
+        &self.programs[..count.min(self.programs.len())]
+    }
+
+    /// The share of all attributed compute units consumed by a program, from `0.0` to `1.0`
+    pub fn share(&self, program_id: &str) -> f64 {
+        let attributed = self
+            .compute_units_consumed
+            .saturating_sub(self.unattributed_compute_units);
+
+        if attributed == 0 {
+            return 0.0;
+        }
+
+        self.programs
+            .iter()
+            .find(|program| program.program_id == program_id)
+            .map(|program| program.compute_units as f64 / attributed as f64)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{AtollError, AtollResult};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// The default number of signatures remembered by a [SignatureDedup]
+pub const DEFAULT_DEDUP_CAPACITY: usize = 100_000;
+
+/// A bounded set of transaction signatures that have already been emitted downstream.
+/// Once the capacity is reached the oldest signature is evicted first.
+/// The set can optionally be persisted to disk so that restarting an indexer
+/// or feeding it from overlapping sources does not emit the same transaction twice.
+#[derive(Debug)]
+pub struct SignatureDedup {
+    capacity: usize,
+    seen: HashSet<String>,
+    order: VecDeque<String>,
+    path: Option<PathBuf>,
+}
+
+impl Default for SignatureDedup {
+    fn default() -> Self {
+        SignatureDedup::new(DEFAULT_DEDUP_CAPACITY)
+    }
+}
+
+impl SignatureDedup {
+    /// Create an in-memory dedup set that remembers at most `capacity` signatures
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        SignatureDedup {
+            capacity,
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            path: None,
+        }
+    }
+
+    /// Create a dedup set backed by a file. If the file exists, the signatures
+    /// it contains are loaded, otherwise the file is created on the first call to [Self::persist]
+    pub fn with_persistence(capacity: usize, path: impl AsRef<Path>) -> AtollResult<Self> {
+        let mut dedup = SignatureDedup::new(capacity);
+        let path = path.as_ref().to_path_buf();
+
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let signatures = Vec::<String>::try_from_slice(&bytes)
+                    .map_err(|error| AtollError::Utilities(error.into()))?;
+                signatures.iter().for_each(|signature| {
+                    dedup.insert(signature);
+                });
+            }
+            Err(io_error) if io_error.kind() == ErrorKind::NotFound => (),
+            Err(io_error) => {
+                return Err(AtollError::Io(format!("{}: {}", path.display(), io_error)))
+            }
+        }
+
+        dedup.path = Some(path);
+
+        Ok(dedup)
+    }
+
+    /// Record a signature. Returns `true` if the signature had not been seen before
+    /// and the transaction should be emitted downstream
+    pub fn insert(&mut self, signature: &str) -> bool {
+        if self.seen.contains(signature) {
+            return false;
+        }
+
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        self.seen.insert(signature.to_owned());
+        self.order.push_back(signature.to_owned());
+
+        true
+    }
+
+    /// Check whether a signature has already been recorded
+    pub fn contains(&self, signature: &str) -> bool {
+        self.seen.contains(signature)
+    }
+
+    /// Keep only the items whose signature has not been seen before, recording them in the process
+    pub fn retain_new<T>(&mut self, items: Vec<T>, signature: impl Fn(&T) -> &str) -> Vec<T> {
+        items
+            .into_iter()
+            .filter(|item| self.insert(signature(item)))
+            .collect()
+    }
+
+    /// Write the remembered signatures to the backing file, oldest first.
+    /// The signatures are written to a temporary file next to it which then
+    /// replaces the backing file, so a crash never leaves a truncated file behind.
+    /// This is a no-op for in-memory dedup sets
+    pub fn persist(&self) -> AtollResult<()> {
+        if let Some(path) = self.path.as_ref() {
+            let signatures = self.order.iter().cloned().collect::<Vec<String>>();
+            let bytes = signatures
+                .try_to_vec()
+                .map_err(|error| AtollError::Utilities(error.into()))?;
+
+            let mut temp_path = path.clone().into_os_string();
+            temp_path.push(".tmp");
+
+            fs::write(&temp_path, bytes)
+                .and_then(|_| fs::rename(&temp_path, path))
+                .map_err(|error| AtollError::Io(format!("{}: {}", path.display(), error)))?;
+        }
+
+        Ok(())
+    }
+
+    /// The maximum number of signatures remembered
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of signatures currently remembered
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Check if no signatures have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Forget all recorded signatures
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+    }
+}
@@ -0,0 +1,1136 @@
+use crate::{
+    AtollError, AtollResult, TokenBalances, TxWithMeta, ASSOCIATED_TOKEN_PROGRAM,
+    SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM, TOKEN_PROGRAM,
+};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The position of an instruction within a transaction
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionPosition {
+    /// The index of the top-level instruction
+    pub index: u8,
+    /// The index within the inner instructions of the top-level instruction,
+    /// `None` for the top-level instruction itself
+    pub inner_index: Option<u16>,
+}
+
+/// An instruction whose program id and accounts have been resolved to base58
+/// addresses and whose data has been decoded
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ResolvedInstruction {
+    pub position: InstructionPosition,
+    pub program_id: String,
+    pub accounts: Vec<String>,
+    pub data: Vec<u8>,
+}
+
+impl ResolvedInstruction {
+    fn account(&self, index: usize) -> AtollResult<String> {
+        self.accounts
+            .get(index)
+            .cloned()
+            .ok_or(AtollError::MalformedTransaction(format!(
+                "Instruction at {:?} is missing account {}",
+                self.position, index
+            )))
+    }
+
+    fn read_u32(&self, offset: usize) -> Option<u32> {
+        self.data
+            .get(offset..offset.checked_add(4)?)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap())) // Never fails since the slice is 4 bytes
+    }
+
+    fn read_u64(&self, offset: usize) -> Option<u64> {
+        self.data
+            .get(offset..offset.checked_add(8)?)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())) // Never fails since the slice is 8 bytes
+    }
+
+    /// Offset of the lamports in `CreateAccountWithSeed`, which follow the base pubkey
+    /// (32 bytes) and a length prefixed seed. `None` if the seed length is truncated or
+    /// overflows the offset
+    fn seed_lamports_offset(&self) -> Option<usize> {
+        let seed_len = usize::try_from(self.read_u64(36)?).ok()?;
+
+        44usize.checked_add(seed_len)
+    }
+}
+
+/// How value was moved by a [TransferEvent]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub enum TransferKind {
+    /// SPL Token `Transfer`
+    SplTransfer,
+    /// SPL Token `TransferChecked`
+    SplTransferChecked,
+    /// System program `Transfer`
+    SystemTransfer,
+    /// System program `TransferWithSeed`
+    SystemTransferWithSeed,
+    /// System program `CreateAccount` funding the new account
+    CreateAccount,
+    /// System program `CreateAccountWithSeed` funding the new account
+    CreateAccountWithSeed,
+}
+
+/// A normalized movement of tokens from one account to another
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferEvent {
+    /// The first signature of the transaction
+    pub signature: String,
+    pub position: InstructionPosition,
+    pub program_id: String,
+    pub kind: TransferKind,
+    /// The mint of the tokens. Recovered from the token balances of the transaction
+    /// for `Transfer` instructions which do not reference the mint.
+    /// This is `None` for native SOL transfers
+    pub mint: Option<String>,
+    pub source: String,
+    pub destination: String,
+    /// The owner or delegate that authorized the transfer
+    pub owner: Option<String>,
+    /// The amount in base units, lamports for native SOL transfers
+    pub amount: u64,
+    pub decimals: Option<u8>,
+}
+
+/// How the supply of a mint was changed by a [SupplyChangeEvent]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub enum SupplyChangeKind {
+    /// SPL Token `MintTo`
+    MintTo,
+    /// SPL Token `MintToChecked`
+    MintToChecked,
+    /// SPL Token `Burn`
+    Burn,
+    /// SPL Token `BurnChecked`
+    BurnChecked,
+}
+
+/// Tokens minted to or burned from a token account, changing the supply of the mint
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SupplyChangeEvent {
+    /// The first signature of the transaction
+    pub signature: String,
+    pub position: InstructionPosition,
+    pub program_id: String,
+    pub kind: SupplyChangeKind,
+    pub mint: String,
+    /// The token account that received the minted tokens or whose tokens were burned
+    pub token_account: String,
+    /// The mint authority for mints and the owner or delegate for burns
+    pub authority: Option<String>,
+    /// The amount in base units
+    pub amount: u64,
+    pub decimals: Option<u8>,
+}
+
+impl SupplyChangeEvent {
+    /// The signed change of the supply of the mint
+    pub fn supply_delta(&self) -> i128 {
+        match self.kind {
+            SupplyChangeKind::MintTo | SupplyChangeKind::MintToChecked => self.amount as i128,
+            SupplyChangeKind::Burn | SupplyChangeKind::BurnChecked => -(self.amount as i128),
+        }
+    }
+}
+
+/// What happened to an account in an [AccountLifecycleEvent]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub enum AccountLifecycleKind {
+    /// Created by the System program `CreateAccount`
+    Created,
+    /// Created by the System program `CreateAccountWithSeed`
+    CreatedWithSeed,
+    /// An associated token account was created by `Create` or `CreateIdempotent`
+    AssociatedTokenAccountCreated,
+    /// A token account was closed by SPL Token `CloseAccount` and its rent reclaimed
+    TokenAccountClosed,
+}
+
+/// The creation or closure of an account
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountLifecycleEvent {
+    /// The first signature of the transaction
+    pub signature: String,
+    pub position: InstructionPosition,
+    pub program_id: String,
+    pub kind: AccountLifecycleKind,
+    /// The address of the account that was created or closed
+    pub address: String,
+    /// The program that owns the created account
+    pub owner_program: Option<String>,
+    /// The account that funded the creation or received the reclaimed rent
+    pub counterparty: String,
+    /// The lamports used to fund the account or the rent reclaimed on closure
+    pub lamports: Option<u64>,
+    /// The data size of a created account
+    pub space: Option<u64>,
+}
+
+/// Decodes a transaction once and extracts normalized events from its
+/// top-level and inner instructions
+#[derive(Debug, Clone)]
+pub struct EventExtractor<'a> {
+    transaction: &'a TxWithMeta,
+    signature: String,
+    account_keys: Vec<String>,
+    instructions: Vec<ResolvedInstruction>,
+}
+
+impl<'a> EventExtractor<'a> {
+    /// Decode a transaction and resolve all its instructions in execution order
+    pub fn new(transaction: &'a TxWithMeta) -> AtollResult<Self> {
+        let signature = transaction.signatures()?.into_iter().next().ok_or(
+            AtollError::MalformedTransaction("The transaction has no signatures".to_owned()),
+        )?;
+        let account_keys = transaction.all_account_keys()?;
+
+        let resolve = |position: InstructionPosition,
+                       program_id_index: u8,
+                       accounts: &[u8],
+                       data: &str|
+         -> AtollResult<ResolvedInstruction> {
+            let key = |index: u8| {
+                account_keys
+                    .get(index as usize)
+                    .cloned()
+                    .ok_or(AtollError::MalformedTransaction(format!(
+                        "Account index {} is out of bounds",
+                        index
+                    )))
+            };
+
+            Ok(ResolvedInstruction {
+                position,
+                program_id: key(program_id_index)?,
+                accounts: accounts
+                    .iter()
+                    .map(|index| key(*index))
+                    .collect::<AtollResult<Vec<String>>>()?,
+                data: bs58::decode(data)
+                    .into_vec()
+                    .map_err(|error| AtollError::MalformedTransaction(error.to_string()))?,
+            })
+        };
+
+        let mut instructions = Vec::new();
+
+        for (index, instruction) in transaction.instructions()?.iter().enumerate() {
+            let index = index as u8;
+
+            instructions.push(resolve(
+                InstructionPosition {
+                    index,
+                    inner_index: None,
+                },
+                instruction.program_id_index,
+                &instruction.accounts,
+                &instruction.data,
+            )?);
+
+            for inner in transaction
+                .meta
+                .inner_instructions
+                .iter()
+                .filter(|inner| inner.index == index)
+            {
+                for (inner_index, instruction) in inner.instructions.iter().enumerate() {
+                    instructions.push(resolve(
+                        InstructionPosition {
+                            index,
+                            inner_index: Some(inner_index as u16),
+                        },
+                        instruction.program_id_index,
+                        &instruction.accounts,
+                        &instruction.data,
+                    )?);
+                }
+            }
+        }
+
+        Ok(EventExtractor {
+            transaction,
+            signature,
+            account_keys,
+            instructions,
+        })
+    }
+
+    /// The first signature of the transaction
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// All top-level and inner instructions in execution order
+    pub fn instructions(&self) -> &[ResolvedInstruction] {
+        &self.instructions
+    }
+
+    /// Whether the transaction failed, in which case none of its instructions took
+    /// effect and no events are extracted from it
+    pub fn is_failed(&self) -> bool {
+        self.transaction.meta.err.is_some()
+    }
+
+    /// Extract SPL Token and Token-2022 `Transfer` and `TransferChecked` events,
+    /// none if the transaction failed
+    pub fn token_transfers(&self) -> AtollResult<Vec<TransferEvent>> {
+        let mut events = Vec::new();
+
+        if self.is_failed() {
+            return Ok(events);
+        }
+
+        for instruction in self.token_instructions() {
+            let event = match instruction.data.first() {
+                Some(3) => {
+                    let Some(amount) = instruction.read_u64(1) else {
+                        continue;
+                    };
+                    let source = instruction.account(0)?;
+                    let balance = self.token_balance_of(&source);
+
+                    TransferEvent {
+                        signature: self.signature.clone(),
+                        position: instruction.position,
+                        program_id: instruction.program_id.clone(),
+                        kind: TransferKind::SplTransfer,
+                        mint: balance.map(|balance| balance.mint.clone()),
+                        source,
+                        destination: instruction.account(1)?,
+                        owner: instruction.accounts.get(2).cloned(),
+                        amount,
+                        decimals: balance.map(|balance| balance.ui_token_amount.decimals),
+                    }
+                }
+                Some(12) => {
+                    let (Some(amount), Some(decimals)) =
+                        (instruction.read_u64(1), instruction.data.get(9))
+                    else {
+                        continue;
+                    };
+
+                    TransferEvent {
+                        signature: self.signature.clone(),
+                        position: instruction.position,
+                        program_id: instruction.program_id.clone(),
+                        kind: TransferKind::SplTransferChecked,
+                        source: instruction.account(0)?,
+                        mint: Some(instruction.account(1)?),
+                        destination: instruction.account(2)?,
+                        owner: instruction.accounts.get(3).cloned(),
+                        amount,
+                        decimals: Some(*decimals),
+                    }
+                }
+                _ => continue,
+            };
+
+            events.push(event);
+        }
+
+        Ok(events)
+    }
+
+    /// Extract SPL Token and Token-2022 `MintTo`, `Burn` and their checked variants,
+    /// none if the transaction failed
+    pub fn supply_changes(&self) -> AtollResult<Vec<SupplyChangeEvent>> {
+        let mut events = Vec::new();
+
+        if self.is_failed() {
+            return Ok(events);
+        }
+
+        for instruction in self.token_instructions() {
+            let (kind, checked) = match instruction.data.first() {
+                Some(7) => (SupplyChangeKind::MintTo, false),
+                Some(8) => (SupplyChangeKind::Burn, false),
+                Some(14) => (SupplyChangeKind::MintToChecked, true),
+                Some(15) => (SupplyChangeKind::BurnChecked, true),
+                _ => continue,
+            };
+
+            let Some(amount) = instruction.read_u64(1) else {
+                continue;
+            };
+
+            let (mint, token_account) = match kind {
+                SupplyChangeKind::MintTo | SupplyChangeKind::MintToChecked => {
+                    (instruction.account(0)?, instruction.account(1)?)
+                }
+                SupplyChangeKind::Burn | SupplyChangeKind::BurnChecked => {
+                    (instruction.account(1)?, instruction.account(0)?)
+                }
+            };
+
+            let decimals = if checked {
+                instruction.data.get(9).copied()
+            } else {
+                self.token_balance_of(&token_account)
+                    .map(|balance| balance.ui_token_amount.decimals)
+            };
+
+            events.push(SupplyChangeEvent {
+                signature: self.signature.clone(),
+                position: instruction.position,
+                program_id: instruction.program_id.clone(),
+                kind,
+                mint,
+                token_account,
+                authority: instruction.accounts.get(2).cloned(),
+                amount,
+                decimals,
+            });
+        }
+
+        Ok(events)
+    }
+
+    /// Detect accounts created by the System program or the Associated Token Account program
+    /// and token accounts closed by SPL Token `CloseAccount`, none if the transaction failed
+    pub fn account_lifecycle(&self) -> AtollResult<Vec<AccountLifecycleEvent>> {
+        let associated_token_program = bs58::encode(ASSOCIATED_TOKEN_PROGRAM).into_string();
+        let mut events = Vec::new();
+
+        if self.is_failed() {
+            return Ok(events);
+        }
+
+        for instruction in self.system_instructions() {
+            let (kind, lamports_offset) = match instruction.read_u32(0) {
+                Some(0) => (AccountLifecycleKind::Created, 4usize),
+                Some(3) => match instruction.seed_lamports_offset() {
+                    Some(offset) => (AccountLifecycleKind::CreatedWithSeed, offset),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let owner_program = lamports_offset
+                .checked_add(16)
+                .zip(lamports_offset.checked_add(48))
+                .and_then(|(start, end)| instruction.data.get(start..end))
+                .map(|owner| bs58::encode(owner).into_string());
+
+            events.push(AccountLifecycleEvent {
+                signature: self.signature.clone(),
+                position: instruction.position,
+                program_id: instruction.program_id.clone(),
+                kind,
+                address: instruction.account(1)?,
+                owner_program,
+                counterparty: instruction.account(0)?,
+                lamports: instruction.read_u64(lamports_offset),
+                space: lamports_offset
+                    .checked_add(8)
+                    .and_then(|offset| instruction.read_u64(offset)),
+            });
+        }
+
+        for instruction in self
+            .instructions
+            .iter()
+            .filter(|instruction| instruction.program_id == associated_token_program)
+        {
+            let idempotent = match instruction.data.first() {
+                None | Some(0) => false,
+                Some(1) => true,
+                _ => continue,
+            };
+
+            let address = instruction.account(1)?;
+
+            // `CreateIdempotent` succeeds without creating anything if the account already exists
+            if idempotent && self.pre_balance_of(&address).unwrap_or_default() > 0 {
+                continue;
+            }
+
+            events.push(AccountLifecycleEvent {
+                signature: self.signature.clone(),
+                position: instruction.position,
+                program_id: instruction.program_id.clone(),
+                kind: AccountLifecycleKind::AssociatedTokenAccountCreated,
+                owner_program: instruction.accounts.get(5).cloned(),
+                lamports: self.post_balance_of(&address),
+                address,
+                counterparty: instruction.account(0)?,
+                space: None,
+            });
+        }
+
+        for instruction in self.token_instructions() {
+            if instruction.data.first() != Some(&9) {
+                continue;
+            }
+
+            let address = instruction.account(0)?;
+
+            events.push(AccountLifecycleEvent {
+                signature: self.signature.clone(),
+                position: instruction.position,
+                program_id: instruction.program_id.clone(),
+                kind: AccountLifecycleKind::TokenAccountClosed,
+                owner_program: None,
+                lamports: self.pre_balance_of(&address),
+                address,
+                counterparty: instruction.account(1)?,
+                space: None,
+            });
+        }
+
+        events.sort_by_key(|event| event.position);
+
+        Ok(events)
+    }
+
+    /// The account lifecycle events of the transaction grouped by the address of the account
+    pub fn account_lifecycle_by_address(
+        &self,
+    ) -> AtollResult<BTreeMap<String, Vec<AccountLifecycleEvent>>> {
+        let mut by_address = BTreeMap::<String, Vec<AccountLifecycleEvent>>::new();
+
+        self.account_lifecycle()?.into_iter().for_each(|event| {
+            by_address
+                .entry(event.address.clone())
+                .or_default()
+                .push(event)
+        });
+
+        Ok(by_address)
+    }
+
+    /// Extract native SOL movements by the System program, including the lamports
+    /// used to fund accounts created with `CreateAccount` and `CreateAccountWithSeed`,
+    /// none if the transaction failed
+    pub fn system_transfers(&self) -> AtollResult<Vec<TransferEvent>> {
+        let mut events = Vec::new();
+
+        if self.is_failed() {
+            return Ok(events);
+        }
+
+        for instruction in self.system_instructions() {
+            let (kind, amount, source, destination, owner) = match instruction.read_u32(0) {
+                Some(0) => (
+                    TransferKind::CreateAccount,
+                    instruction.read_u64(4),
+                    instruction.account(0)?,
+                    instruction.account(1)?,
+                    instruction.account(0)?,
+                ),
+                Some(2) => (
+                    TransferKind::SystemTransfer,
+                    instruction.read_u64(4),
+                    instruction.account(0)?,
+                    instruction.account(1)?,
+                    instruction.account(0)?,
+                ),
+                Some(3) => {
+                    let Some(lamports_offset) = instruction.seed_lamports_offset() else {
+                        continue;
+                    };
+
+                    (
+                        TransferKind::CreateAccountWithSeed,
+                        instruction.read_u64(lamports_offset),
+                        instruction.account(0)?,
+                        instruction.account(1)?,
+                        instruction.account(0)?,
+                    )
+                }
+                Some(11) => (
+                    TransferKind::SystemTransferWithSeed,
+                    instruction.read_u64(4),
+                    instruction.account(0)?,
+                    instruction.account(2)?,
+                    instruction.account(1)?,
+                ),
+                _ => continue,
+            };
+
+            let Some(amount) = amount else {
+                continue;
+            };
+
+            events.push(TransferEvent {
+                signature: self.signature.clone(),
+                position: instruction.position,
+                program_id: instruction.program_id.clone(),
+                kind,
+                mint: None,
+                source,
+                destination,
+                owner: Some(owner),
+                amount,
+                decimals: Some(9),
+            });
+        }
+
+        Ok(events)
+    }
+
+    /// Extract both token and native SOL transfers in execution order
+    pub fn transfers(&self) -> AtollResult<Vec<TransferEvent>> {
+        let mut events = self.token_transfers()?;
+        events.extend(self.system_transfers()?);
+        events.sort_by_key(|event| event.position);
+
+        Ok(events)
+    }
+
+    fn system_instructions(&self) -> impl Iterator<Item = &ResolvedInstruction> {
+        let system_program = bs58::encode(SYSTEM_PROGRAM_ID).into_string();
+
+        self.instructions
+            .iter()
+            .filter(move |instruction| instruction.program_id == system_program)
+    }
+
+    fn token_instructions(&self) -> impl Iterator<Item = &ResolvedInstruction> {
+        let token_program = bs58::encode(TOKEN_PROGRAM).into_string();
+        let token_2022_program = bs58::encode(TOKEN_2022_PROGRAM).into_string();
+
+        self.instructions.iter().filter(move |instruction| {
+            instruction.program_id == token_program || instruction.program_id == token_2022_program
+        })
+    }
+
+    fn pre_balance_of(&self, address: &str) -> Option<u64> {
+        let account_index = self.account_keys.iter().position(|key| key == address)?;
+
+        self.transaction
+            .meta
+            .pre_balances
+            .get(account_index)
+            .copied()
+    }
+
+    fn post_balance_of(&self, address: &str) -> Option<u64> {
+        let account_index = self.account_keys.iter().position(|key| key == address)?;
+
+        self.transaction
+            .meta
+            .post_balances
+            .get(account_index)
+            .copied()
+    }
+
+    /// Find the pre or post token balance of a token account by its address
+    fn token_balance_of(&self, address: &str) -> Option<&TokenBalances> {
+        let account_index = self.account_keys.iter().position(|key| key == address)? as u8;
+
+        self.transaction
+            .meta
+            .pre_token_balances
+            .iter()
+            .chain(self.transaction.meta.post_token_balances.iter())
+            .find(|balance| balance.account_index == account_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tx::{AccountMeta, Instruction, Message, Transaction},
+        InnerInstructions, InstructionError, Pubkey, TokenAmount, TransactionError, TxMetadata,
+    };
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+    const PAYER: Pubkey = Pubkey::new([1; 32]);
+    const SOURCE: Pubkey = Pubkey::new([2; 32]);
+    const DESTINATION: Pubkey = Pubkey::new([3; 32]);
+    const MINT: Pubkey = Pubkey::new([4; 32]);
+
+    fn token_instruction(data: &[u8], accounts: &[Pubkey], signer: &Pubkey) -> Instruction {
+        accounts.iter().fold(
+            Instruction::new(Pubkey::new(TOKEN_PROGRAM)).change_data(data),
+            |instruction, account| match account == signer {
+                true => instruction.add_account(AccountMeta::new_readonly(*account, true)),
+                false => instruction.add_account(AccountMeta::new(*account, false)),
+            },
+        )
+    }
+
+    /// The instruction data of an amount instruction like `Transfer`, followed by
+    /// the decimals for the checked variants
+    fn amount_data(tag: u8, amount: u64, decimals: Option<u8>) -> Vec<u8> {
+        let mut data = vec![tag];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend(decimals);
+
+        data
+    }
+
+    fn transaction(instructions: &[Instruction], err: Option<TransactionError>) -> TxWithMeta {
+        let message = Message::new(instructions, &PAYER).unwrap();
+        let account_count = message.account_keys.len();
+
+        TxWithMeta {
+            meta: TxMetadata {
+                status: err.clone().map_or(Ok(()), Err),
+                err,
+                fee: 5000,
+                pre_balances: vec![2_039_280; account_count],
+                post_balances: vec![2_039_280; account_count],
+                inner_instructions: Vec::new(),
+                log_messages: Vec::new(),
+                pre_token_balances: Vec::new(),
+                post_token_balances: Vec::new(),
+                rewards: Vec::new(),
+                loaded_addresses: None,
+                return_data: None,
+                compute_units_consumed: None,
+            },
+            transaction: (
+                BASE64.encode(Transaction::new(message).serialize()),
+                "base64".to_owned(),
+            ),
+        }
+    }
+
+    fn system_instruction(data: &[u8], accounts: &[Pubkey]) -> Instruction {
+        accounts.iter().fold(
+            Instruction::new(Pubkey::new(SYSTEM_PROGRAM_ID)).change_data(data),
+            |instruction, account| instruction.add_account(AccountMeta::new(*account, true)),
+        )
+    }
+
+    /// The instruction data of a System program instruction followed by the lamports
+    fn lamports_data(tag: u32, lamports: u64) -> Vec<u8> {
+        let mut data = tag.to_le_bytes().to_vec();
+        data.extend_from_slice(&lamports.to_le_bytes());
+
+        data
+    }
+
+    /// The instruction data of `CreateAccount`
+    fn create_account_data(lamports: u64, space: u64, owner: &Pubkey) -> Vec<u8> {
+        let mut data = lamports_data(0, lamports);
+        data.extend_from_slice(&space.to_le_bytes());
+        data.extend_from_slice(&owner.to_bytes());
+
+        data
+    }
+
+    fn failed() -> Option<TransactionError> {
+        Some(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(1),
+        ))
+    }
+
+    fn token_balance(transaction: &TxWithMeta, account: &Pubkey, decimals: u8) -> TokenBalances {
+        let account_index = transaction
+            .all_account_keys()
+            .unwrap()
+            .iter()
+            .position(|key| *key == account.to_base58())
+            .unwrap() as u8;
+
+        TokenBalances {
+            account_index,
+            mint: MINT.to_base58(),
+            owner: PAYER.to_base58(),
+            ui_token_amount: TokenAmount {
+                amount: "0".to_owned(),
+                decimals,
+                ui_amount: 0.0,
+                ui_amount_string: "0".to_owned(),
+            },
+        }
+    }
+
+    #[test]
+    fn extracts_token_transfers() {
+        let mut transaction = transaction(
+            &[token_instruction(
+                &amount_data(3, 250, None),
+                &[SOURCE, DESTINATION, PAYER],
+                &PAYER,
+            )],
+            None,
+        );
+        let balance = token_balance(&transaction, &SOURCE, 6);
+        transaction.meta.pre_token_balances.push(balance);
+
+        let events = EventExtractor::new(&transaction)
+            .unwrap()
+            .token_transfers()
+            .unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, TransferKind::SplTransfer);
+        assert_eq!(events[0].source, SOURCE.to_base58());
+        assert_eq!(events[0].destination, DESTINATION.to_base58());
+        assert_eq!(events[0].owner, Some(PAYER.to_base58()));
+        assert_eq!(events[0].mint, Some(MINT.to_base58()));
+        assert_eq!(events[0].amount, 250);
+        assert_eq!(events[0].decimals, Some(6));
+    }
+
+    #[test]
+    fn extracts_checked_token_transfers() {
+        let transaction = transaction(
+            &[token_instruction(
+                &amount_data(12, 1_000_000, Some(6)),
+                &[SOURCE, MINT, DESTINATION, PAYER],
+                &PAYER,
+            )],
+            None,
+        );
+
+        let events = EventExtractor::new(&transaction)
+            .unwrap()
+            .token_transfers()
+            .unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, TransferKind::SplTransferChecked);
+        assert_eq!(events[0].source, SOURCE.to_base58());
+        assert_eq!(events[0].destination, DESTINATION.to_base58());
+        assert_eq!(events[0].owner, Some(PAYER.to_base58()));
+        assert_eq!(events[0].mint, Some(MINT.to_base58()));
+        assert_eq!(events[0].amount, 1_000_000);
+        assert_eq!(events[0].decimals, Some(6));
+    }
+
+    #[test]
+    fn extracts_inner_token_transfers() {
+        let mut transaction = transaction(
+            &[token_instruction(
+                &amount_data(12, 1_000_000, Some(6)),
+                &[SOURCE, MINT, DESTINATION, PAYER],
+                &PAYER,
+            )],
+            None,
+        );
+        let top_level = transaction.instructions().unwrap().remove(0);
+        transaction.meta.inner_instructions.push(InnerInstructions {
+            index: 0,
+            instructions: vec![top_level],
+        });
+
+        let events = EventExtractor::new(&transaction)
+            .unwrap()
+            .token_transfers()
+            .unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[1].position,
+            InstructionPosition {
+                index: 0,
+                inner_index: Some(0),
+            }
+        );
+    }
+
+    #[test]
+    fn skips_token_transfers_of_failed_transactions() {
+        let transaction = transaction(
+            &[token_instruction(
+                &amount_data(12, 1_000_000, Some(6)),
+                &[SOURCE, MINT, DESTINATION, PAYER],
+                &PAYER,
+            )],
+            failed(),
+        );
+        let extractor = EventExtractor::new(&transaction).unwrap();
+
+        assert!(extractor.is_failed());
+        assert_eq!(extractor.instructions().len(), 1);
+        assert!(extractor.token_transfers().unwrap().is_empty());
+    }
+
+    #[test]
+    fn extracts_system_transfers() {
+        let transaction = transaction(
+            &[
+                system_instruction(&lamports_data(2, 1_500_000_000), &[PAYER, DESTINATION]),
+                system_instruction(
+                    &create_account_data(2_039_280, 165, &Pubkey::new(TOKEN_PROGRAM)),
+                    &[PAYER, SOURCE],
+                ),
+            ],
+            None,
+        );
+
+        let events = EventExtractor::new(&transaction)
+            .unwrap()
+            .system_transfers()
+            .unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, TransferKind::SystemTransfer);
+        assert_eq!(events[0].source, PAYER.to_base58());
+        assert_eq!(events[0].destination, DESTINATION.to_base58());
+        assert_eq!(events[0].amount, 1_500_000_000);
+        assert_eq!(events[0].mint, None);
+        assert_eq!(events[1].kind, TransferKind::CreateAccount);
+        assert_eq!(events[1].destination, SOURCE.to_base58());
+        assert_eq!(events[1].amount, 2_039_280);
+    }
+
+    #[test]
+    fn skips_system_transfers_of_failed_transactions() {
+        let transaction = transaction(
+            &[
+                system_instruction(&lamports_data(2, 1_500_000_000), &[PAYER, DESTINATION]),
+                token_instruction(
+                    &amount_data(3, 250, None),
+                    &[SOURCE, DESTINATION, PAYER],
+                    &PAYER,
+                ),
+            ],
+            failed(),
+        );
+        let extractor = EventExtractor::new(&transaction).unwrap();
+
+        assert!(extractor.system_transfers().unwrap().is_empty());
+        assert!(extractor.transfers().unwrap().is_empty());
+    }
+
+    #[test]
+    fn extracts_supply_changes() {
+        let mut transaction = transaction(
+            &[
+                token_instruction(
+                    &amount_data(7, 500, None),
+                    &[MINT, DESTINATION, PAYER],
+                    &PAYER,
+                ),
+                token_instruction(
+                    &amount_data(14, 700, Some(6)),
+                    &[MINT, DESTINATION, PAYER],
+                    &PAYER,
+                ),
+                token_instruction(&amount_data(8, 200, None), &[SOURCE, MINT, PAYER], &PAYER),
+                token_instruction(
+                    &amount_data(15, 300, Some(6)),
+                    &[SOURCE, MINT, PAYER],
+                    &PAYER,
+                ),
+            ],
+            None,
+        );
+        let balance = token_balance(&transaction, &DESTINATION, 6);
+        transaction.meta.post_token_balances.push(balance);
+
+        let events = EventExtractor::new(&transaction)
+            .unwrap()
+            .supply_changes()
+            .unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| (event.kind, event.supply_delta(), event.decimals))
+                .collect::<Vec<_>>(),
+            vec![
+                (SupplyChangeKind::MintTo, 500, Some(6)),
+                (SupplyChangeKind::MintToChecked, 700, Some(6)),
+                (SupplyChangeKind::Burn, -200, None),
+                (SupplyChangeKind::BurnChecked, -300, Some(6)),
+            ]
+        );
+        assert!(events.iter().all(|event| event.mint == MINT.to_base58()));
+        assert_eq!(events[0].token_account, DESTINATION.to_base58());
+        assert_eq!(events[2].token_account, SOURCE.to_base58());
+        assert_eq!(events[3].authority, Some(PAYER.to_base58()));
+    }
+
+    #[test]
+    fn skips_supply_changes_of_failed_transactions() {
+        let transaction = transaction(
+            &[token_instruction(
+                &amount_data(14, 700, Some(6)),
+                &[MINT, DESTINATION, PAYER],
+                &PAYER,
+            )],
+            failed(),
+        );
+
+        assert!(EventExtractor::new(&transaction)
+            .unwrap()
+            .supply_changes()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn extracts_account_lifecycle() {
+        let associated_token_account = Pubkey::new([5; 32]);
+        let create_associated_token_account =
+            Instruction::new(Pubkey::new(ASSOCIATED_TOKEN_PROGRAM))
+                .add_account(AccountMeta::new(PAYER, true))
+                .add_account(AccountMeta::new(associated_token_account, false))
+                .add_account(AccountMeta::new_readonly(DESTINATION, false))
+                .add_account(AccountMeta::new_readonly(MINT, false))
+                .add_account(AccountMeta::new_readonly(
+                    Pubkey::new(SYSTEM_PROGRAM_ID),
+                    false,
+                ))
+                .add_account(AccountMeta::new_readonly(Pubkey::new(TOKEN_PROGRAM), false));
+        let transaction = transaction(
+            &[
+                system_instruction(
+                    &create_account_data(1_461_600, 82, &Pubkey::new(TOKEN_PROGRAM)),
+                    &[PAYER, MINT],
+                ),
+                create_associated_token_account,
+                token_instruction(&[9], &[SOURCE, PAYER, PAYER], &PAYER),
+            ],
+            None,
+        );
+
+        let events = EventExtractor::new(&transaction)
+            .unwrap()
+            .account_lifecycle()
+            .unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| (
+                    event.kind,
+                    event.address.clone(),
+                    event.counterparty.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    AccountLifecycleKind::Created,
+                    MINT.to_base58(),
+                    PAYER.to_base58()
+                ),
+                (
+                    AccountLifecycleKind::AssociatedTokenAccountCreated,
+                    associated_token_account.to_base58(),
+                    PAYER.to_base58()
+                ),
+                (
+                    AccountLifecycleKind::TokenAccountClosed,
+                    SOURCE.to_base58(),
+                    PAYER.to_base58()
+                ),
+            ]
+        );
+        assert_eq!(events[0].lamports, Some(1_461_600));
+        assert_eq!(events[0].space, Some(82));
+        assert_eq!(
+            events[0].owner_program,
+            Some(bs58::encode(TOKEN_PROGRAM).into_string())
+        );
+        assert_eq!(events[2].lamports, Some(2_039_280));
+    }
+
+    #[test]
+    fn skips_account_lifecycle_of_failed_transactions() {
+        let transaction = transaction(
+            &[
+                system_instruction(
+                    &create_account_data(1_461_600, 82, &Pubkey::new(TOKEN_PROGRAM)),
+                    &[PAYER, MINT],
+                ),
+                token_instruction(&[9], &[SOURCE, PAYER, PAYER], &PAYER),
+            ],
+            failed(),
+        );
+        let extractor = EventExtractor::new(&transaction).unwrap();
+
+        assert!(extractor.account_lifecycle().unwrap().is_empty());
+        assert!(extractor.account_lifecycle_by_address().unwrap().is_empty());
+    }
+}
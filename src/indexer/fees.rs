@@ -0,0 +1,241 @@
+use crate::{AtollResult, Block};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// The number of lamports charged per signature for the base fee
+pub const DEFAULT_LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// The number of most recent transactions used to compute the rolling fee percentiles
+pub const DEFAULT_FEE_WINDOW: usize = 10_000;
+
+/// The number of most recent slots whose fee totals are kept
+pub const DEFAULT_SLOT_HISTORY: usize = 150;
+
+/// Aggregates the fees paid by transactions in a stream of blocks, separating the
+/// base fee (charged per signature) from the priority fee (everything above the base fee).
+/// Call [FeeAggregator::snapshot] at any time to get a view for dashboards.
+#[derive(Debug, Clone)]
+pub struct FeeAggregator {
+    lamports_per_signature: u64,
+    window: usize,
+    slot_history: usize,
+    recent_fees: VecDeque<u64>,
+    recent_priority_fees: VecDeque<u64>,
+    slots: BTreeMap<u64, SlotFees>,
+    totals: FeeTotals,
+}
+
+impl Default for FeeAggregator {
+    fn default() -> Self {
+        FeeAggregator::new()
+    }
+}
+
+impl FeeAggregator {
+    /// Create a new aggregator with the default configuration
+    pub fn new() -> Self {
+        FeeAggregator {
+            lamports_per_signature: DEFAULT_LAMPORTS_PER_SIGNATURE,
+            window: DEFAULT_FEE_WINDOW,
+            slot_history: DEFAULT_SLOT_HISTORY,
+            recent_fees: VecDeque::default(),
+            recent_priority_fees: VecDeque::default(),
+            slots: BTreeMap::default(),
+            totals: FeeTotals::default(),
+        }
+    }
+
+    /// Change the lamports charged per signature used to compute the base fee
+    pub fn change_lamports_per_signature(mut self, lamports: u64) -> Self {
+        self.lamports_per_signature = lamports;
+
+        self
+    }
+
+    /// Change the number of recent transactions used to compute percentiles
+    pub fn change_window(mut self, transactions: usize) -> Self {
+        self.window = transactions.max(1);
+
+        self
+    }
+
+    /// Change the number of recent slots whose fee totals are kept
+    pub fn change_slot_history(mut self, slots: usize) -> Self {
+        self.slot_history = slots.max(1);
+
+        self
+    }
+
+    /// Add the fees of all transactions in a block. The slot is required since
+    /// the `getBlock` response does not contain the slot of the block itself.
+    pub fn ingest_block(&mut self, slot: u64, block: &Block) -> AtollResult<()> {
+        let mut slot_fees = SlotFees {
+            slot,
+            ..Default::default()
+        };
+
+        for transaction in &block.transactions {
+            let base_fee = (transaction.signature_count()? as u64)
+                .saturating_mul(self.lamports_per_signature)
+                .min(transaction.meta.fee);
+            let priority_fee = transaction.meta.fee - base_fee;
+
+            slot_fees.transactions += 1;
+            slot_fees.base_fees += base_fee;
+            slot_fees.priority_fees += priority_fee;
+
+            Self::push_bounded(&mut self.recent_fees, transaction.meta.fee, self.window);
+            Self::push_bounded(&mut self.recent_priority_fees, priority_fee, self.window);
+        }
+
+        self.totals.blocks += 1;
+        self.totals.transactions += slot_fees.transactions;
+        self.totals.base_fees += slot_fees.base_fees;
+        self.totals.priority_fees += slot_fees.priority_fees;
+
+        self.slots.insert(slot, slot_fees);
+        while self.slots.len() > self.slot_history {
+            self.slots.pop_first();
+        }
+
+        Ok(())
+    }
+
+    /// Get the current view of the aggregated fees
+    pub fn snapshot(&self) -> FeeSnapshot {
+        FeeSnapshot {
+            totals: self.totals,
+            slots: self.slots.values().copied().collect(),
+            fee_percentiles: FeePercentiles::from_values(self.recent_fees.iter().copied()),
+            priority_fee_percentiles: FeePercentiles::from_values(
+                self.recent_priority_fees.iter().copied(),
+            ),
+        }
+    }
+
+    /// Forget all aggregated data while keeping the configuration
+    pub fn reset(&mut self) {
+        self.recent_fees.clear();
+        self.recent_priority_fees.clear();
+        self.slots.clear();
+        self.totals = FeeTotals::default();
+    }
+
+    fn push_bounded(values: &mut VecDeque<u64>, value: u64, bound: usize) {
+        if values.len() == bound {
+            values.pop_front();
+        }
+
+        values.push_back(value);
+    }
+}
+
+/// A point in time view of a [FeeAggregator]
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeSnapshot {
+    pub totals: FeeTotals,
+    pub slots: Vec<SlotFees>,
+    pub fee_percentiles: FeePercentiles,
+    pub priority_fee_percentiles: FeePercentiles,
+}
+
+/// Fee totals since the aggregator was created or reset
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeTotals {
+    pub blocks: u64,
+    pub transactions: u64,
+    pub base_fees: u64,
+    pub priority_fees: u64,
+}
+
+/// The fee totals of a single slot
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotFees {
+    pub slot: u64,
+    pub transactions: u64,
+    pub base_fees: u64,
+    pub priority_fees: u64,
+}
+
+/// Percentiles (nearest rank) of fees in lamports
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FeePercentiles {
+    pub samples: u64,
+    pub min: u64,
+    pub p50: u64,
+    pub p75: u64,
+    pub p90: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl FeePercentiles {
+    /// Compute the percentiles of some values
+    pub fn from_values(values: impl Iterator<Item = u64>) -> Self {
+        let mut sorted = values.collect::<Vec<u64>>();
+        sorted.sort_unstable();
+
+        FeePercentiles {
+            samples: sorted.len() as u64,
+            min: sorted.first().copied().unwrap_or_default(),
+            p50: percentile(&sorted, 50),
+            p75: percentile(&sorted, 75),
+            p90: percentile(&sorted, 90),
+            p95: percentile(&sorted, 95),
+            p99: percentile(&sorted, 99),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Nearest rank percentile of already sorted values
+pub(crate) fn percentile(sorted: &[u64], percentile: u8) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    let rank = (percentile.min(100) as usize * sorted.len()).div_ceil(100);
+
+    sorted[rank.saturating_sub(1)]
+}
@@ -0,0 +1,14 @@
+mod dedup;
+pub use dedup::*;
+
+mod fees;
+pub use fees::*;
+
+mod compute_units;
+pub use compute_units::*;
+
+mod program_stats;
+pub use program_stats::*;
+
+mod events;
+pub use events::*;
@@ -0,0 +1,186 @@
+use crate::{AtollResult, Block, InvocationOutcome, ParsedLogs};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// The default number of slots covered by a single [ProgramUsageReport], about 10 minutes
+pub const DEFAULT_STATS_WINDOW_SLOTS: u64 = 1_500;
+
+/// Collects per program invocation counts, success and failure rates and unique users
+/// from a stream of blocks. Blocks are grouped into windows of a configurable number of
+/// slots and a [ProgramUsageReport] is emitted for each window once a block from a later
+/// window is ingested. A user is the fee payer of a transaction.
+#[derive(Debug, Clone)]
+pub struct ProgramStatsCollector {
+    window_slots: u64,
+    current_window: Option<u64>,
+    programs: HashMap<String, ProgramUsageCounter>,
+    transactions: u64,
+    completed: Vec<ProgramUsageReport>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ProgramUsageCounter {
+    invocations: u64,
+    successes: u64,
+    failures: u64,
+    transactions: u64,
+    users: HashSet<String>,
+}
+
+impl Default for ProgramStatsCollector {
+    fn default() -> Self {
+        ProgramStatsCollector::new(DEFAULT_STATS_WINDOW_SLOTS)
+    }
+}
+
+impl ProgramStatsCollector {
+    /// Create a collector that emits a report every `window_slots` slots
+    pub fn new(window_slots: u64) -> Self {
+        ProgramStatsCollector {
+            window_slots: window_slots.max(1),
+            current_window: None,
+            programs: HashMap::default(),
+            transactions: 0,
+            completed: Vec::default(),
+        }
+    }
+
+    /// Add the program invocations of all transactions in a block.
+    /// The slot is required since the `getBlock` response does not contain the slot of the block itself.
+    pub fn ingest_block(&mut self, slot: u64, block: &Block) -> AtollResult<()> {
+        let window = slot / self.window_slots;
+
+        match self.current_window {
+            Some(current) if current != window => {
+                let report = self.build_report(current);
+                self.completed.push(report);
+                self.programs.clear();
+                self.transactions = 0;
+                self.current_window = Some(window);
+            }
+            None => self.current_window = Some(window),
+            _ => (),
+        }
+
+        for transaction in &block.transactions {
+            let fee_payer = transaction.fee_payer()?;
+            let parsed = ParsedLogs::parse(&transaction.meta.log_messages);
+            let mut seen_in_transaction = HashSet::<&str>::new();
+
+            for invocation in &parsed.invocations {
+                let counter = self
+                    .programs
+                    .entry(invocation.program_id.clone())
+                    .or_default();
+
+                counter.invocations += 1;
+                match invocation.outcome {
+                    InvocationOutcome::Success => counter.successes += 1,
+                    InvocationOutcome::Failed(_) => counter.failures += 1,
+                    InvocationOutcome::Incomplete => (),
+                }
+
+                if seen_in_transaction.insert(&invocation.program_id) {
+                    counter.transactions += 1;
+                    counter.users.insert(fee_payer.clone());
+                }
+            }
+
+            self.transactions += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Take the reports of all windows that have been completed so far
+    pub fn drain_reports(&mut self) -> Vec<ProgramUsageReport> {
+        core::mem::take(&mut self.completed)
+    }
+
+    /// Get a report for the window that is still being collected
+    pub fn current_report(&self) -> Option<ProgramUsageReport> {
+        self.current_window.map(|window| self.build_report(window))
+    }
+
+    fn build_report(&self, window: u64) -> ProgramUsageReport {
+        let mut programs = self
+            .programs
+            .iter()
+            .map(|(program_id, counter)| ProgramUsage {
+                program_id: program_id.clone(),
+                invocations: counter.invocations,
+                successes: counter.successes,
+                failures: counter.failures,
+                transactions: counter.transactions,
+                unique_users: counter.users.len() as u64,
+            })
+            .collect::<Vec<ProgramUsage>>();
+        programs.sort_by(|a, b| {
+            b.invocations
+                .cmp(&a.invocations)
+                .then_with(|| a.program_id.cmp(&b.program_id))
+        });
+
+        ProgramUsageReport {
+            start_slot: window * self.window_slots,
+            end_slot: (window + 1) * self.window_slots - 1,
+            transactions: self.transactions,
+            programs,
+        }
+    }
+}
+
+/// Usage statistics of a single program in a window
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramUsage {
+    pub program_id: String,
+    /// The number of times the program was invoked, including CPI
+    pub invocations: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// The number of transactions that invoked the program
+    pub transactions: u64,
+    /// The number of distinct fee payers of transactions that invoked the program
+    pub unique_users: u64,
+}
+
+impl ProgramUsage {
+    /// The share of invocations with a known outcome that succeeded, from `0.0` to `1.0`
+    pub fn success_rate(&self) -> f64 {
+        let completed = self.successes + self.failures;
+
+        if completed == 0 {
+            return 0.0;
+        }
+
+        self.successes as f64 / completed as f64
+    }
+
+    /// The share of invocations with a known outcome that failed, from `0.0` to `1.0`
+    pub fn failure_rate(&self) -> f64 {
+        let completed = self.successes + self.failures;
+
+        if completed == 0 {
+            return 0.0;
+        }
+
+        self.failures as f64 / completed as f64
+    }
+}
+
+/// Program usage statistics for an inclusive range of slots
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramUsageReport {
+    pub start_slot: u64,
+    pub end_slot: u64,
+    pub transactions: u64,
+    /// Programs sorted by the number of invocations, highest first
+    pub programs: Vec<ProgramUsage>,
+}
@@ -12,3 +12,42 @@ pub use rpc::*;
 
 mod errors;
 pub use errors::*;
+
+mod logs;
+pub use logs::*;
+
+mod wire;
+pub(crate) use wire::*;
+
+pub(crate) mod runtime;
+
+mod indexer;
+pub use indexer::*;
+
+mod signer;
+pub use signer::*;
+
+mod benchmark;
+pub use benchmark::*;
+
+mod staking;
+pub use staking::*;
+
+mod holders;
+pub use holders::*;
+
+mod topology;
+pub use topology::*;
+
+mod streams;
+pub use streams::*;
+
+mod pubsub;
+pub use pubsub::*;
+
+mod schema;
+pub use schema::*;
+
+/// Building, signing and serializing transactions. Kept in its own module since
+/// [Instruction] and [AccountMeta] at the crate root are the forms the node returns
+pub mod tx;
@@ -0,0 +1,143 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// The outcome of a program invocation as reported by the runtime logs
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub enum InvocationOutcome {
+    Success,
+    /// The program failed with the error message logged by the runtime
+    Failed(String),
+    /// No outcome was logged, usually because the logs were truncated
+    #[default]
+    Incomplete,
+}
+
+/// A single program invocation recovered from the log messages of a transaction
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramInvocation {
+    /// The base58 program id
+    pub program_id: String,
+    /// The invocation depth, top-level instructions have a depth of `1`
+    pub depth: u8,
+    /// The index of the invocation that called this one through CPI
+    pub parent: Option<usize>,
+    /// The compute units consumed including those consumed by nested invocations
+    pub compute_units_consumed: Option<u64>,
+    /// The compute units available to the invocation
+    pub compute_units_limit: Option<u64>,
+    pub outcome: InvocationOutcome,
+    /// Messages logged with `Program log:`
+    pub logs: Vec<String>,
+    /// Base64 data logged with `Program data:`
+    pub data: Vec<String>,
+}
+
+/// The log messages of a transaction parsed into program invocations
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedLogs {
+    /// The invocations in the order they were invoked
+    pub invocations: Vec<ProgramInvocation>,
+    /// The runtime stopped recording logs because the log limit was exceeded
+    pub truncated: bool,
+}
+
+impl ParsedLogs {
+    /// Parse the `logMessages` of a transaction
+    pub fn parse(log_messages: &[String]) -> Self {
+        let mut parsed = ParsedLogs::default();
+        let mut stack = Vec::<usize>::new();
+
+        for message in log_messages {
+            if message == "Log truncated" {
+                parsed.truncated = true;
+                continue;
+            }
+
+            let Some(body) = message.strip_prefix("Program ") else {
+                continue;
+            };
+
+            if let Some(log) = body.strip_prefix("log: ") {
+                if let Some(index) = stack.last() {
+                    parsed.invocations[*index].logs.push(log.to_owned());
+                }
+            } else if let Some(data) = body.strip_prefix("data: ") {
+                if let Some(index) = stack.last() {
+                    parsed.invocations[*index].data.push(data.to_owned());
+                }
+            } else if body.starts_with("return: ") {
+                continue;
+            } else if let Some((program_id, rest)) = body.split_once(' ') {
+                if let Some(depth) = rest
+                    .strip_prefix("invoke [")
+                    .and_then(|depth| depth.strip_suffix(']'))
+                {
+                    parsed.invocations.push(ProgramInvocation {
+                        program_id: program_id.to_owned(),
+                        depth: depth.parse().unwrap_or_default(),
+                        parent: stack.last().copied(),
+                        ..Default::default()
+                    });
+                    stack.push(parsed.invocations.len() - 1);
+                } else if let Some(units) = rest.strip_prefix("consumed ") {
+                    if let Some(index) = stack.last() {
+                        let mut units = units.split(' ');
+                        let invocation = &mut parsed.invocations[*index];
+                        invocation.compute_units_consumed =
+                            units.next().and_then(|value| value.parse().ok());
+                        invocation.compute_units_limit =
+                            units.nth(1).and_then(|value| value.parse().ok());
+                    }
+                } else if rest == "success" {
+                    if let Some(index) = stack.pop() {
+                        parsed.invocations[index].outcome = InvocationOutcome::Success;
+                    }
+                } else if let Some(error) = rest.strip_prefix("failed: ") {
+                    if let Some(index) = stack.pop() {
+                        parsed.invocations[index].outcome =
+                            InvocationOutcome::Failed(error.to_owned());
+                    }
+                }
+            }
+        }
+
+        parsed
+    }
+
+    /// The compute units consumed by an invocation excluding those consumed by the
+    /// invocations it called through CPI
+    pub fn exclusive_compute_units(&self, index: usize) -> Option<u64> {
+        let consumed = self.invocations.get(index)?.compute_units_consumed?;
+
+        let nested = self
+            .invocations
+            .iter()
+            .filter(|invocation| invocation.parent == Some(index))
+            .filter_map(|invocation| invocation.compute_units_consumed)
+            .sum::<u64>();
+
+        Some(consumed.saturating_sub(nested))
+    }
+
+    /// The top-level invocations, one for each instruction of the transaction
+    pub fn top_level(&self) -> impl Iterator<Item = &ProgramInvocation> {
+        self.invocations
+            .iter()
+            .filter(|invocation| invocation.parent.is_none())
+    }
+
+    /// The first failed invocation, if any
+    pub fn failed_invocation(&self) -> Option<&ProgramInvocation> {
+        self.invocations
+            .iter()
+            .find(|invocation| matches!(invocation.outcome, InvocationOutcome::Failed(_)))
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{AtollError, AtollResult};
+use async_channel::{self as channel, Receiver, Sender, TrySendError};
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::Stream;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// The default number of notifications buffered for a consumer
+pub const DEFAULT_NOTIFICATION_CAPACITY: usize = 1024;
+
+/// What happens when a notification arrives and the buffer of the consumer is full
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OverflowPolicy {
+    /// Discard the oldest buffered notification to make room for the new one
+    #[default]
+    DropOldest,
+    /// Discard the new notification and keep the buffered ones
+    DropNewest,
+    /// Block reading from the WebSocket until the consumer catches up, which also
+    /// delays the other subscriptions sharing the connection
+    PauseSubscription,
+    /// Fail with [AtollError::BufferOverflow] so the subscription can be torn down
+    Error,
+}
+
+/// Creates the bounded buffer between the WebSocket reader and a consumer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotificationBuffer {
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+impl Default for NotificationBuffer {
+    fn default() -> Self {
+        NotificationBuffer::new()
+    }
+}
+
+impl NotificationBuffer {
+    pub fn new() -> Self {
+        NotificationBuffer {
+            capacity: DEFAULT_NOTIFICATION_CAPACITY,
+            policy: OverflowPolicy::default(),
+        }
+    }
+
+    pub fn change_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+
+        self
+    }
+
+    pub fn change_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.policy = policy;
+
+        self
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Create the producer and consumer halves of the buffer
+    pub fn build<T>(&self) -> (NotificationSender<T>, NotificationReceiver<T>) {
+        let (sender, receiver) = channel::bounded(self.capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        (
+            NotificationSender {
+                sender,
+                oldest: receiver.clone(),
+                policy: self.policy,
+                dropped: dropped.clone(),
+            },
+            NotificationReceiver { receiver, dropped },
+        )
+    }
+}
+
+/// The half of a [NotificationBuffer] used by the WebSocket reader
+#[derive(Debug)]
+pub struct NotificationSender<T> {
+    sender: Sender<T>,
+    // Kept to discard the oldest notification under `OverflowPolicy::DropOldest`
+    oldest: Receiver<T>,
+    policy: OverflowPolicy,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<T> Clone for NotificationSender<T> {
+    fn clone(&self) -> Self {
+        NotificationSender {
+            sender: self.sender.clone(),
+            oldest: self.oldest.clone(),
+            policy: self.policy,
+            dropped: self.dropped.clone(),
+        }
+    }
+}
+
+impl<T> NotificationSender<T> {
+    /// Buffer a notification applying the overflow policy when the buffer is full.
+    /// Returns [AtollError::ChannelClosed] once the consumer is dropped
+    pub async fn send(&self, notification: T) -> AtollResult<()> {
+        let mut notification = notification;
+
+        loop {
+            match self.sender.try_send(notification) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(_)) => return Err(AtollError::ChannelClosed),
+                Err(TrySendError::Full(rejected)) => match self.policy {
+                    OverflowPolicy::DropOldest => {
+                        if self.oldest.try_recv().is_ok() {
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                        }
+                        notification = rejected;
+                    }
+                    OverflowPolicy::DropNewest => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+
+                        return Ok(());
+                    }
+                    OverflowPolicy::PauseSubscription => {
+                        return self
+                            .sender
+                            .send(rejected)
+                            .await
+                            .map_err(|_| AtollError::ChannelClosed)
+                    }
+                    OverflowPolicy::Error => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+
+                        return Err(AtollError::BufferOverflow(self.capacity()));
+                    }
+                },
+            }
+        }
+    }
+
+    /// Buffer a notification only if there is room, without applying the overflow
+    /// policy. Returns `false` if the buffer is full
+    pub fn try_send(&self, notification: T) -> AtollResult<bool> {
+        match self.sender.try_send(notification) {
+            Ok(()) => Ok(true),
+            Err(TrySendError::Full(_)) => Ok(false),
+            Err(TrySendError::Closed(_)) => Err(AtollError::ChannelClosed),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.sender.capacity().unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_empty()
+    }
+
+    /// The consumer was dropped and no further notifications will be read
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+/// The half of a [NotificationBuffer] read by the consumer, also a `futures::Stream`
+#[derive(Debug)]
+pub struct NotificationReceiver<T> {
+    receiver: Receiver<T>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<T> NotificationReceiver<T> {
+    /// The next notification, `None` once the reader is closed and the buffer drained
+    pub async fn recv(&self) -> Option<T> {
+        self.receiver.recv().await.ok()
+    }
+
+    pub fn try_recv(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// The number of notifications discarded because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+}
+
+impl<T> Drop for NotificationReceiver<T> {
+    fn drop(&mut self) {
+        // The sender keeps a receiver for `OverflowPolicy::DropOldest`,
+        // close the channel explicitly so the reader learns the consumer is gone
+        self.receiver.close();
+    }
+}
+
+impl<T> Stream for NotificationReceiver<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
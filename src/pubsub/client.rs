@@ -0,0 +1,905 @@
+use crate::{
+    default_ws_transport, runtime, AtollError, AtollResult, Cluster, NotificationBuffer,
+    NotificationReceiver, NotificationSender, RetryPolicy, RpcHeaders, WsConnection, WsMessage,
+    WsTransport,
+};
+use async_channel::{Receiver, Sender, TryRecvError};
+use core::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::{future, Stream};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+/// The default time to open the connection and to wait for the answer to a request
+pub const DEFAULT_PUBSUB_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default delay before the first attempt to re-establish a dropped connection
+pub const DEFAULT_RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// The default longest delay between attempts to re-establish a dropped connection
+pub const DEFAULT_RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// The default time between pings keeping an idle connection open
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The default time to wait for the answer to a ping before reconnecting
+pub const DEFAULT_PONG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the reader waits for a message before sending the queued requests
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The options of a [PubsubClient]
+#[derive(Debug, Clone)]
+pub struct PubsubConfig {
+    url: String,
+    headers: RpcHeaders,
+    buffer: NotificationBuffer,
+    timeout: Duration,
+    reconnect: RetryPolicy,
+    ping_interval: Duration,
+    pong_timeout: Duration,
+    transport: Arc<dyn WsTransport>,
+}
+
+impl PubsubConfig {
+    /// Connect to the pubsub endpoint of the cluster, see [Cluster::ws_url]
+    pub fn new(cluster: &Cluster) -> Self {
+        PubsubConfig {
+            url: cluster.ws_url(),
+            headers: RpcHeaders::default(),
+            buffer: NotificationBuffer::default(),
+            timeout: DEFAULT_PUBSUB_TIMEOUT,
+            reconnect: RetryPolicy::new()
+                .change_max_attempts(u32::MAX)
+                .change_base_delay(DEFAULT_RECONNECT_BASE_DELAY)
+                .change_max_delay(DEFAULT_RECONNECT_MAX_DELAY),
+            ping_interval: DEFAULT_PING_INTERVAL,
+            pong_timeout: DEFAULT_PONG_TIMEOUT,
+            transport: default_ws_transport(),
+        }
+    }
+
+    /// Connect to another `ws://` or `wss://` URL, for providers serving pubsub
+    /// on a separate host
+    pub fn change_url(mut self, url: &str) -> Self {
+        self.url = url.to_owned();
+
+        self
+    }
+
+    /// The headers of the WebSocket handshake, like an `Authorization` header
+    pub fn change_headers(mut self, headers: RpcHeaders) -> Self {
+        self.headers = headers;
+
+        self
+    }
+
+    /// The buffer created for each subscription, see [PubsubClient::change_buffer]
+    /// to change it for some subscriptions
+    pub fn change_buffer(mut self, buffer: NotificationBuffer) -> Self {
+        self.buffer = buffer;
+
+        self
+    }
+
+    /// The time to open the connection and to wait for the answer to a request
+    pub fn change_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// The delays between attempts to re-establish a dropped connection. The
+    /// dropped connection counts as the first attempt, so [RetryPolicy::never]
+    /// ends every subscription once the connection drops. By default the client
+    /// keeps trying with delays growing from half a second to 30 seconds
+    pub fn change_reconnect(mut self, reconnect: RetryPolicy) -> Self {
+        self.reconnect = reconnect;
+
+        self
+    }
+
+    /// The time between pings, which keep proxies and load balancers from
+    /// closing an idle connection. `Duration::ZERO` sends no pings
+    pub fn change_ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+
+        self
+    }
+
+    /// The time to wait for the answer to a ping before the connection is
+    /// considered dead and re-established
+    pub fn change_pong_timeout(mut self, pong_timeout: Duration) -> Self {
+        self.pong_timeout = pong_timeout;
+
+        self
+    }
+
+    pub fn change_transport(mut self, transport: Arc<dyn WsTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn reconnect(&self) -> &RetryPolicy {
+        &self.reconnect
+    }
+
+    pub fn ping_interval(&self) -> Duration {
+        self.ping_interval
+    }
+
+    pub fn pong_timeout(&self) -> Duration {
+        self.pong_timeout
+    }
+
+    /// Open the WebSocket and start reading from it on a background thread
+    pub async fn connect(self) -> AtollResult<PubsubClient> {
+        let config = self.clone();
+        let connection = runtime::unblock(move || config.open()).await?;
+        let config = self.clone();
+
+        let (outgoing, queued) = async_channel::unbounded();
+        let shared = Arc::new(Shared {
+            config: self,
+            next_id: AtomicU64::new(1),
+            next_key: AtomicU64::new(1),
+            outgoing,
+            pending: Mutex::default(),
+            active: Mutex::default(),
+            routes: Mutex::default(),
+        });
+
+        let reader = Arc::downgrade(&shared);
+        std::thread::Builder::new()
+            .name("atoll-pubsub".to_owned())
+            .spawn(move || read_loop(reader, config, connection, queued))
+            .map_err(|error| AtollError::Transport(error.to_string()))?;
+
+        Ok(PubsubClient {
+            buffer: shared.config.buffer,
+            shared,
+        })
+    }
+
+    fn open(&self) -> AtollResult<Box<dyn WsConnection>> {
+        self.transport
+            .connect(&self.url, &self.headers, self.timeout)
+    }
+}
+
+/// A client of the WebSocket pubsub API of a node. Every subscription of a client
+/// shares its connection, which a background thread reads from and routes the
+/// notifications of each subscription into its own [NotificationBuffer].
+/// A dropped connection is re-established with the reconnect policy and every
+/// subscription is made again, with a [Notification::Gap] marking where
+/// notifications may have been missed.
+/// Clones share the connection, which is closed once the client and all of its
+/// subscriptions are dropped
+#[derive(Debug, Clone)]
+pub struct PubsubClient {
+    shared: Arc<Shared>,
+    buffer: NotificationBuffer,
+}
+
+impl PubsubClient {
+    /// Connect to the pubsub endpoint of the cluster with the default options
+    pub async fn connect(cluster: &Cluster) -> AtollResult<Self> {
+        PubsubConfig::new(cluster).connect().await
+    }
+
+    pub fn config(&self) -> &PubsubConfig {
+        &self.shared.config
+    }
+
+    /// The buffer of the subscriptions made with this handle, its capacity and
+    /// what happens when the consumer falls behind. Change it on a clone to give
+    /// one subscription its own buffer, the clone keeps sharing the connection
+    pub fn change_buffer(mut self, buffer: NotificationBuffer) -> Self {
+        self.buffer = buffer;
+
+        self
+    }
+
+    pub fn buffer(&self) -> NotificationBuffer {
+        self.buffer
+    }
+
+    /// The number of subscriptions made on the node, identical subscriptions
+    /// sharing one count once
+    pub fn subscriptions(&self) -> usize {
+        self.shared.active.lock().unwrap().len()
+    }
+
+    /// Subscribe with the JSON-RPC method, routing the `result` of each notification
+    /// into the buffer of the returned subscription. Notifications that do not
+    /// deserialize into `T` are skipped. Subscribing again with the same method and
+    /// parameters shares the subscription already made on the node, which is only
+    /// unsubscribed once every [Subscription] sharing it is gone
+    pub async fn subscribe<T>(
+        &self,
+        method: &str,
+        unsubscribe_method: &str,
+        params: Value,
+    ) -> AtollResult<Subscription<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let (sender, receiver) = self.buffer.build::<Notification<T>>();
+        let sender = Arc::new(GapSender {
+            sender,
+            pending_gap: AtomicBool::new(false),
+        });
+        let gap_sender = sender.clone();
+        let consumer = Arc::new(Consumer {
+            deliver: Box::new(move |result| sender.deliver(result)),
+            gap: Box::new(move || gap_sender.gap()),
+        });
+
+        let topic = format!("{method}{params}");
+        let key = self.shared.next_key.fetch_add(1, Ordering::Relaxed);
+        let subscription = Subscription {
+            topic: topic.clone(),
+            key,
+            client: self.clone(),
+            receiver,
+        };
+
+        let (route, shared) = {
+            let mut active = self.shared.active.lock().unwrap();
+            let (route, shared) = match active.get(&topic) {
+                Some(route) => (route.clone(), true),
+                None => {
+                    let route = Arc::new(Route {
+                        topic: topic.clone(),
+                        method: method.to_owned(),
+                        unsubscribe_method: unsubscribe_method.to_owned(),
+                        params: params.clone(),
+                        server_id: Mutex::default(),
+                        consumers: Mutex::default(),
+                        waiting: Mutex::default(),
+                    });
+                    active.insert(topic, route.clone());
+
+                    (route, false)
+                }
+            };
+            route.consumers.lock().unwrap().insert(key, consumer);
+
+            (route, shared)
+        };
+
+        let result = if shared {
+            let (reply, response) = async_channel::bounded(1);
+            {
+                let mut waiting = route.waiting.lock().unwrap();
+                if route.server_id.lock().unwrap().is_some() {
+                    return Ok(subscription);
+                }
+                // Answered once the node answers the subscription being made
+                waiting.push(reply);
+            }
+
+            self.wait(response).await
+        } else {
+            self.request(method, params, Some(route)).await
+        };
+
+        let subscribed = result.and_then(|result| {
+            result.as_u64().ok_or_else(|| {
+                AtollError::SerdeJsonDeser(format!(
+                    "`{method}` was not answered with a subscription id"
+                ))
+            })
+        });
+
+        match subscribed {
+            Ok(_) => Ok(subscription),
+            Err(error) => {
+                subscription.forget();
+
+                Err(error)
+            }
+        }
+    }
+
+    /// Send a JSON-RPC request over the connection and wait for its `result`.
+    /// The route is installed before any notification of a new subscription is read
+    async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        route: Option<Arc<Route>>,
+    ) -> AtollResult<Value> {
+        let (reply, response) = async_channel::bounded(1);
+        let id = self.shared.send(
+            method,
+            params,
+            PendingRequest {
+                reply: Some(reply),
+                route,
+            },
+        )?;
+
+        let result = self.wait(response).await;
+
+        if result.is_err() {
+            self.shared.pending.lock().unwrap().remove(&id);
+        }
+
+        result
+    }
+
+    /// Wait for an answer up to the timeout
+    async fn wait(&self, response: Receiver<AtollResult<Value>>) -> AtollResult<Value> {
+        let timeout = self.shared.config.timeout;
+
+        future::or(
+            async {
+                response
+                    .recv()
+                    .await
+                    .unwrap_or_else(|_| Err(connection_closed()))
+            },
+            async {
+                runtime::sleep(timeout).await;
+
+                Err(AtollError::DeadlineExceeded)
+            },
+        )
+        .await
+    }
+}
+
+/// An item of a [Subscription]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notification<T> {
+    /// A notification sent by the node
+    Value(T),
+    /// The connection dropped and the subscription was made again once it was
+    /// re-established, the notifications sent in between were missed
+    Gap,
+}
+
+impl<T> Notification<T> {
+    /// The notification, `None` for a gap
+    pub fn value(self) -> Option<T> {
+        match self {
+            Notification::Value(value) => Some(value),
+            Notification::Gap => None,
+        }
+    }
+
+    pub fn is_gap(&self) -> bool {
+        matches!(self, Notification::Gap)
+    }
+}
+
+/// The notifications of one subscription, also a `futures_core::Stream` of
+/// [Notification]s that works with `select!` and the `StreamExt` combinators.
+/// Dropping it unsubscribes in the background once no other subscription shares
+/// it, [Subscription::unsubscribe] also waits for the node to answer
+#[derive(Debug)]
+pub struct Subscription<T> {
+    /// The method and parameters, shared by identical subscriptions
+    topic: String,
+    /// Identifies this subscription among those sharing the topic
+    key: u64,
+    client: PubsubClient,
+    receiver: NotificationReceiver<Notification<T>>,
+}
+
+impl<T> Subscription<T> {
+    /// The id the node gave the subscription, `None` while the connection is
+    /// re-established or once the subscription ended
+    pub fn id(&self) -> Option<u64> {
+        self.client
+            .shared
+            .active
+            .lock()
+            .unwrap()
+            .get(&self.topic)
+            .and_then(|route| *route.server_id.lock().unwrap())
+    }
+
+    /// The next notification, `None` once the subscription ended and the buffer is drained
+    pub async fn recv(&self) -> Option<Notification<T>> {
+        self.receiver.recv().await
+    }
+
+    /// The number of notifications discarded because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.receiver.dropped()
+    }
+
+    /// Stop routing notifications without unsubscribing, for subscriptions the
+    /// node ends by itself like `signatureSubscribe`
+    pub(crate) fn forget(self) {
+        self.client.shared.remove(&self.topic, self.key);
+    }
+
+    /// Stop the subscription on the node, once no other subscription shares it
+    pub async fn unsubscribe(self) -> AtollResult<()> {
+        let Some((server_id, route)) = self.client.shared.remove(&self.topic, self.key) else {
+            return Ok(());
+        };
+
+        self.client
+            .request(&route.unsubscribe_method, json!([server_id]), None)
+            .await
+            .map(|_| ())
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        // Does nothing after `unsubscribe` or `forget` already removed the consumer
+        self.client.shared.release(&self.topic, self.key);
+    }
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = Notification<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// The state shared by the handles of a client and its reader thread
+struct Shared {
+    config: PubsubConfig,
+    next_id: AtomicU64,
+    next_key: AtomicU64,
+    /// Requests queued for the reader thread, which owns the connection
+    outgoing: Sender<String>,
+    pending: Mutex<HashMap<u64, PendingRequest>>,
+    /// Every subscription by its topic, made again after a reconnection
+    active: Mutex<HashMap<String, Arc<Route>>>,
+    /// The subscriptions by the id the node gave them on the current connection
+    routes: Mutex<HashMap<u64, Arc<Route>>>,
+}
+
+impl fmt::Debug for Shared {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shared")
+            .field("config", &self.config)
+            .field("pending", &self.pending.lock().unwrap().len())
+            .field("active", &self.active.lock().unwrap().len())
+            .finish()
+    }
+}
+
+struct PendingRequest {
+    /// `None` for requests nobody waits for, like replayed subscriptions
+    reply: Option<Sender<AtollResult<Value>>>,
+    route: Option<Arc<Route>>,
+}
+
+/// A subscription on the node and the consumers sharing it
+struct Route {
+    topic: String,
+    method: String,
+    unsubscribe_method: String,
+    params: Value,
+    /// The id the node gave the subscription on the current connection
+    server_id: Mutex<Option<u64>>,
+    consumers: Mutex<HashMap<u64, Arc<Consumer>>>,
+    /// Subscriptions joining while the node has not answered yet
+    waiting: Mutex<Vec<Sender<AtollResult<Value>>>>,
+}
+
+/// The buffer of one [Subscription]
+struct Consumer {
+    /// Buffer the `result` of a notification, `false` once the consumer is gone
+    deliver: Box<dyn Fn(Value) -> bool + Send + Sync>,
+    /// Buffer a [Notification::Gap], `false` once the consumer is gone
+    gap: Box<dyn Fn() -> bool + Send + Sync>,
+}
+
+/// Why the reader stopped serving a connection
+enum Stop {
+    /// Every handle of the client was dropped
+    Dropped,
+    /// The connection failed or the node closed it
+    Failed,
+}
+
+impl Shared {
+    /// Queue a request for the reader thread, returning its id
+    fn send(&self, method: &str, params: Value, pending: PendingRequest) -> AtollResult<u64> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().unwrap().insert(id, pending);
+
+        let body = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if self.outgoing.try_send(body.to_string()).is_err() {
+            self.pending.lock().unwrap().remove(&id);
+
+            return Err(connection_closed());
+        }
+
+        Ok(id)
+    }
+
+    /// Stop routing to the consumer and queue an unsubscribe request nobody
+    /// waits for if it was the last one
+    fn release(&self, topic: &str, key: u64) {
+        if let Some((server_id, route)) = self.remove(topic, key) {
+            let pending = PendingRequest {
+                reply: None,
+                route: None,
+            };
+            self.send(&route.unsubscribe_method, json!([server_id]), pending)
+                .ok();
+        }
+    }
+
+    fn dispatch(&self, text: &str) {
+        let Ok(message) = serde_json::from_str::<Value>(text) else {
+            return;
+        };
+
+        if let Some(id) = message["id"].as_u64() {
+            let Some(pending) = self.pending.lock().unwrap().remove(&id) else {
+                return;
+            };
+
+            // Built for each recipient since errors cannot be cloned
+            let result = || match message.get("error") {
+                Some(error) => Err(AtollError::Rpc {
+                    code: error["code"].as_i64().unwrap_or_default() as i16,
+                    message: error["message"].as_str().unwrap_or_default().to_owned(),
+                    data: error.get("data").map(|data| match data {
+                        Value::String(text) => text.to_owned(),
+                        data => data.to_string(),
+                    }),
+                }),
+                None => Ok(message["result"].clone()),
+            };
+
+            if let Some(route) = pending.route {
+                self.install(&route, &result);
+            }
+
+            if let Some(reply) = pending.reply {
+                reply.try_send(result()).ok();
+            }
+
+            return;
+        }
+
+        let params = &message["params"];
+        let Some(server_id) = params["subscription"].as_u64() else {
+            return;
+        };
+
+        // Deliver without holding a lock, a paused subscription may block
+        let Some(route) = self.routes.lock().unwrap().get(&server_id).cloned() else {
+            return;
+        };
+        let consumers = route
+            .consumers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, consumer)| (*key, consumer.clone()))
+            .collect::<Vec<(u64, Arc<Consumer>)>>();
+
+        for (key, consumer) in consumers {
+            if !(consumer.deliver)(params["result"].clone()) {
+                self.release(&route.topic, key);
+            }
+        }
+    }
+
+    /// Route the notifications of a subscription the node answered and answer
+    /// the subscriptions waiting to share it
+    fn install(&self, route: &Arc<Route>, result: &dyn Fn() -> AtollResult<Value>) {
+        let mut waiting = route.waiting.lock().unwrap();
+        let server_id = result().ok().and_then(|result| result.as_u64());
+
+        {
+            let mut active = self.active.lock().unwrap();
+            let is_active = active
+                .get(&route.topic)
+                .is_some_and(|active| Arc::ptr_eq(active, route));
+
+            match server_id {
+                Some(server_id) if is_active => {
+                    *route.server_id.lock().unwrap() = Some(server_id);
+                    self.routes.lock().unwrap().insert(server_id, route.clone());
+                }
+                // Every consumer left before the node answered
+                Some(server_id) => {
+                    let pending = PendingRequest {
+                        reply: None,
+                        route: None,
+                    };
+                    self.send(&route.unsubscribe_method, json!([server_id]), pending)
+                        .ok();
+                }
+                // A subscription the node refused to make ends
+                None if is_active => {
+                    active.remove(&route.topic);
+                }
+                None => (),
+            }
+        }
+
+        for reply in waiting.drain(..) {
+            reply.try_send(result()).ok();
+        }
+    }
+
+    /// Stop routing to the consumer, returning the id of the subscription on the
+    /// node if it was the last consumer sharing it
+    fn remove(&self, topic: &str, key: u64) -> Option<(u64, Arc<Route>)> {
+        let route = {
+            let mut active = self.active.lock().unwrap();
+            let route = active.get(topic)?.clone();
+
+            let mut consumers = route.consumers.lock().unwrap();
+            consumers.remove(&key)?;
+            if !consumers.is_empty() {
+                return None;
+            }
+            drop(consumers);
+
+            active.remove(topic);
+
+            route
+        };
+
+        let server_id = route.server_id.lock().unwrap().take()?;
+        self.routes.lock().unwrap().remove(&server_id);
+
+        Some((server_id, route))
+    }
+
+    /// Fail the pending requests and forget the ids the dropped connection gave
+    fn interrupt(&self, queued: &Receiver<String>) {
+        while queued.try_recv().is_ok() {}
+
+        self.pending.lock().unwrap().clear();
+        self.routes.lock().unwrap().clear();
+        self.active
+            .lock()
+            .unwrap()
+            .values()
+            .for_each(|route| *route.server_id.lock().unwrap() = None);
+    }
+
+    /// Mark a gap in every subscription and make it again on the new connection
+    fn replay(&self) {
+        let routes = self
+            .active
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<Arc<Route>>>();
+
+        for route in routes {
+            let consumers = route
+                .consumers
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(key, consumer)| (*key, consumer.clone()))
+                .collect::<Vec<(u64, Arc<Consumer>)>>();
+
+            for (key, consumer) in consumers {
+                if !(consumer.gap)() {
+                    self.remove(&route.topic, key);
+                }
+            }
+
+            if route.consumers.lock().unwrap().is_empty() {
+                continue;
+            }
+
+            let pending = PendingRequest {
+                reply: None,
+                route: Some(route.clone()),
+            };
+            self.send(&route.method, route.params.clone(), pending).ok();
+        }
+    }
+
+    /// Fail the pending requests and end every subscription
+    fn disconnect(&self) {
+        self.outgoing.close();
+        self.pending.lock().unwrap().clear();
+        self.routes.lock().unwrap().clear();
+        self.active.lock().unwrap().clear();
+    }
+}
+
+/// Buffers the notifications of a consumer, holding back a [Notification::Gap]
+/// that did not fit instead of evicting a buffered notification for it
+struct GapSender<T> {
+    sender: NotificationSender<Notification<T>>,
+    pending_gap: AtomicBool,
+}
+
+impl<T: DeserializeOwned> GapSender<T> {
+    /// Buffer the gap if there is room, otherwise send it ahead of the next notification
+    fn gap(&self) -> bool {
+        match self.sender.try_send(Notification::Gap) {
+            Ok(true) => true,
+            Ok(false) => {
+                self.pending_gap.store(true, Ordering::Release);
+
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn deliver(&self, result: Value) -> bool {
+        let Ok(notification) = serde_json::from_value::<T>(result) else {
+            return !self.sender.is_closed();
+        };
+
+        // The held back gap is a notification like any other now, subject to the overflow policy
+        if self.pending_gap.swap(false, Ordering::AcqRel)
+            && future::block_on(self.sender.send(Notification::Gap)).is_err()
+        {
+            return false;
+        }
+
+        future::block_on(self.sender.send(Notification::Value(notification))).is_ok()
+    }
+}
+
+/// Owns the connection, re-establishing it and making the subscriptions again
+/// each time it drops
+fn read_loop(
+    shared: Weak<Shared>,
+    config: PubsubConfig,
+    mut connection: Box<dyn WsConnection>,
+    queued: Receiver<String>,
+) {
+    loop {
+        let stop = serve(&shared, &config, connection.as_mut(), &queued);
+        connection.close();
+
+        let Some(client) = shared.upgrade() else {
+            return;
+        };
+        if matches!(stop, Stop::Dropped) {
+            return client.disconnect();
+        }
+
+        client.interrupt(&queued);
+        // Let the client be dropped while waiting to reconnect
+        drop(client);
+
+        let Some(reconnected) = reconnect(&shared, &config) else {
+            if let Some(client) = shared.upgrade() {
+                client.disconnect();
+            }
+
+            return;
+        };
+        connection = reconnected;
+
+        match shared.upgrade() {
+            Some(client) => client.replay(),
+            None => return connection.close(),
+        }
+    }
+}
+
+/// Send the queued requests and dispatch what is read until the connection stops,
+/// pinging it to keep it open and to detect when it silently died
+fn serve(
+    shared: &Weak<Shared>,
+    config: &PubsubConfig,
+    connection: &mut dyn WsConnection,
+    queued: &Receiver<String>,
+) -> Stop {
+    let mut pinged = Instant::now();
+    let mut ping_sequence = 0u64;
+    let mut awaiting_pong = false;
+
+    loop {
+        match send_queued(connection, queued) {
+            Ok(true) => (),
+            Ok(false) => return Stop::Dropped,
+            Err(_) => return Stop::Failed,
+        }
+
+        if !config.ping_interval.is_zero() {
+            if awaiting_pong && pinged.elapsed() >= config.pong_timeout {
+                return Stop::Failed;
+            }
+
+            if !awaiting_pong && pinged.elapsed() >= config.ping_interval {
+                ping_sequence += 1;
+                if connection.ping(&ping_sequence.to_be_bytes()).is_err() {
+                    return Stop::Failed;
+                }
+
+                pinged = Instant::now();
+                awaiting_pong = true;
+            }
+        }
+
+        match connection.recv(POLL_INTERVAL) {
+            Ok(Some(WsMessage::Text(text))) => match shared.upgrade() {
+                Some(shared) => shared.dispatch(&text),
+                None => return Stop::Dropped,
+            },
+            Ok(Some(WsMessage::Pong(payload))) => {
+                // Pongs answering an earlier ping or sent unprompted are ignored
+                if payload == ping_sequence.to_be_bytes() {
+                    awaiting_pong = false;
+                }
+            }
+            Ok(None) => (),
+            Ok(Some(WsMessage::Close)) | Err(_) => return Stop::Failed,
+        }
+    }
+}
+
+/// Open a new connection with the delays of the reconnect policy, `None` once
+/// the attempts are spent or the client is dropped
+fn reconnect(shared: &Weak<Shared>, config: &PubsubConfig) -> Option<Box<dyn WsConnection>> {
+    let mut attempt = 1;
+
+    while attempt < config.reconnect.max_attempts() {
+        let mut delay = config.reconnect.delay(attempt);
+
+        // Sleep in steps to notice a dropped client
+        loop {
+            if shared.strong_count() == 0 {
+                return None;
+            }
+            if delay.is_zero() {
+                break;
+            }
+
+            let step = delay.min(POLL_INTERVAL * 5);
+            std::thread::sleep(step);
+            delay -= step;
+        }
+
+        match config.open() {
+            Ok(connection) => return Some(connection),
+            Err(_) => attempt += 1,
+        }
+    }
+
+    None
+}
+
+/// Send the queued requests, `false` once the queue is closed
+fn send_queued(connection: &mut dyn WsConnection, queued: &Receiver<String>) -> AtollResult<bool> {
+    loop {
+        match queued.try_recv() {
+            Ok(text) => connection.send_text(&text)?,
+            Err(TryRecvError::Empty) => return Ok(true),
+            Err(TryRecvError::Closed) => return Ok(false),
+        }
+    }
+}
+
+fn connection_closed() -> AtollError {
+    AtollError::Transport("The pubsub connection is closed".to_owned())
+}
@@ -0,0 +1,22 @@
+mod buffer;
+pub use buffer::*;
+
+#[cfg(not(feature = "wasm"))]
+mod transport;
+#[cfg(not(feature = "wasm"))]
+pub use transport::*;
+
+#[cfg(not(feature = "wasm"))]
+mod tcp_ws;
+#[cfg(not(feature = "wasm"))]
+pub use tcp_ws::*;
+
+#[cfg(not(feature = "wasm"))]
+mod client;
+#[cfg(not(feature = "wasm"))]
+pub use client::*;
+
+#[cfg(not(feature = "wasm"))]
+mod subscriptions;
+#[cfg(not(feature = "wasm"))]
+pub use subscriptions::*;
@@ -0,0 +1,346 @@
+use crate::{
+    AtollError, AtollResult, Block, Commitment, Encoding, GetAccountInfo, KeyedAccount,
+    Notification, ProgramAccountsFilter, PubsubClient, Subscription, TransactionError,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// The slot a notification was produced at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct PubsubContext {
+    pub slot: u64,
+}
+
+/// The `result` of notifications that carry the slot they were produced at
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PubsubResult<T> {
+    pub context: PubsubContext,
+    pub value: T,
+}
+
+/// A notification of `accountSubscribe`, the account after it changed
+pub type AccountNotification = PubsubResult<GetAccountInfo>;
+
+/// A notification of `programSubscribe`, an account of the program after it changed
+pub type ProgramNotification = PubsubResult<KeyedAccount>;
+
+/// Selects the transactions whose logs `logsSubscribe` sends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogsFilter {
+    /// All transactions except simple vote transactions
+    All,
+    /// All transactions including simple vote transactions
+    AllWithVotes,
+    /// Transactions mentioning the base58 encoded address
+    Mentions(String),
+}
+
+impl LogsFilter {
+    fn to_json(&self) -> Value {
+        match self {
+            LogsFilter::All => json!("all"),
+            LogsFilter::AllWithVotes => json!("allWithVotes"),
+            LogsFilter::Mentions(address) => json!({ "mentions": [address] }),
+        }
+    }
+}
+
+/// The logs of a transaction sent by `logsSubscribe`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TransactionLogs {
+    pub signature: String,
+    /// The error the transaction failed with, `None` if it succeeded
+    pub err: Option<TransactionError>,
+    /// The log messages, empty if the node did not record them
+    #[serde(default)]
+    pub logs: Vec<String>,
+}
+
+/// A notification of `logsSubscribe`
+pub type LogsNotification = PubsubResult<TransactionLogs>;
+
+/// The outcome of a transaction sent by `signatureSubscribe`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SignatureResult {
+    /// The error the transaction failed with, `None` if it succeeded
+    pub err: Option<TransactionError>,
+}
+
+/// A notification of `signatureSubscribe`
+pub type SignatureNotification = PubsubResult<SignatureResult>;
+
+/// A notification of `slotSubscribe`, sent each time the node processes a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct SlotNotification {
+    pub slot: u64,
+    pub parent: u64,
+    /// The current root slot
+    pub root: u64,
+}
+
+/// The transaction counts of a frozen bank
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotTransactionStats {
+    pub num_transaction_entries: u64,
+    pub num_successful_transactions: u64,
+    pub num_failed_transactions: u64,
+    pub max_transactions_per_entry: u64,
+}
+
+/// A notification of `slotsUpdatesSubscribe`, a step of a slot through the
+/// node. `timestamp` is the Unix time in milliseconds
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SlotUpdate {
+    /// The first shred of the slot arrived
+    FirstShredReceived { slot: u64, timestamp: u64 },
+    /// All shreds of the slot arrived
+    Completed { slot: u64, timestamp: u64 },
+    /// The bank of the slot was created on top of its parent
+    CreatedBank {
+        slot: u64,
+        parent: u64,
+        timestamp: u64,
+    },
+    /// The bank of the slot was frozen after replaying its transactions
+    Frozen {
+        slot: u64,
+        timestamp: u64,
+        stats: SlotTransactionStats,
+    },
+    /// The slot was marked dead, for example because replaying it failed
+    Dead {
+        slot: u64,
+        timestamp: u64,
+        err: String,
+    },
+    /// The cluster optimistically confirmed the slot
+    OptimisticConfirmation { slot: u64, timestamp: u64 },
+    /// The slot was rooted
+    Root { slot: u64, timestamp: u64 },
+}
+
+impl SlotUpdate {
+    pub fn slot(&self) -> u64 {
+        match self {
+            Self::FirstShredReceived { slot, .. }
+            | Self::Completed { slot, .. }
+            | Self::CreatedBank { slot, .. }
+            | Self::Frozen { slot, .. }
+            | Self::Dead { slot, .. }
+            | Self::OptimisticConfirmation { slot, .. }
+            | Self::Root { slot, .. } => *slot,
+        }
+    }
+
+    /// The Unix time of the update in milliseconds
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            Self::FirstShredReceived { timestamp, .. }
+            | Self::Completed { timestamp, .. }
+            | Self::CreatedBank { timestamp, .. }
+            | Self::Frozen { timestamp, .. }
+            | Self::Dead { timestamp, .. }
+            | Self::OptimisticConfirmation { timestamp, .. }
+            | Self::Root { timestamp, .. } => *timestamp,
+        }
+    }
+}
+
+/// Selects the blocks `blockSubscribe` sends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockFilter {
+    All,
+    /// Blocks with a transaction mentioning the base58 encoded account or program
+    MentionsAccountOrProgram(String),
+}
+
+impl BlockFilter {
+    fn to_json(&self) -> Value {
+        match self {
+            BlockFilter::All => json!("all"),
+            BlockFilter::MentionsAccountOrProgram(address) => {
+                json!({ "mentionsAccountOrProgram": address })
+            }
+        }
+    }
+}
+
+/// Why `blockSubscribe` could not send a block
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub enum BlockUpdateError {
+    /// The node failed to read the block from its ledger
+    BlockStoreError,
+    UnsupportedTransactionVersion(u8),
+}
+
+/// A block confirmed or finalized by the cluster
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BlockUpdate {
+    pub slot: u64,
+    pub err: Option<BlockUpdateError>,
+    /// `None` when `err` says why the block is missing
+    pub block: Option<Block>,
+}
+
+/// A notification of `blockSubscribe`
+pub type BlockNotification = PubsubResult<BlockUpdate>;
+
+impl PubsubClient {
+    /// `accountSubscribe`, a notification each time the lamports or data of the
+    /// account at the base58 encoded address change
+    pub async fn account_subscribe(
+        &self,
+        pubkey: &str,
+        commitment: Commitment,
+        encoding: Encoding,
+    ) -> AtollResult<Subscription<AccountNotification>> {
+        let config = json!({
+            "commitment": <&str>::from(commitment),
+            "encoding": <&str>::from(encoding),
+        });
+
+        self.subscribe(
+            "accountSubscribe",
+            "accountUnsubscribe",
+            json!([pubkey, config]),
+        )
+        .await
+    }
+
+    /// `programSubscribe`, a notification each time an account owned by the base58
+    /// encoded program changes and matches all the filters
+    pub async fn program_subscribe(
+        &self,
+        program_id: &str,
+        filters: &[ProgramAccountsFilter],
+        commitment: Commitment,
+        encoding: Encoding,
+    ) -> AtollResult<Subscription<ProgramNotification>> {
+        let mut config = json!({
+            "commitment": <&str>::from(commitment),
+            "encoding": <&str>::from(encoding),
+        });
+
+        if !filters.is_empty() {
+            config["filters"] = filters
+                .iter()
+                .map(|filter| serde_json::from_str::<Value>(&filter.to_json().dump()))
+                .collect::<Result<Value, _>>()?;
+        }
+
+        self.subscribe(
+            "programSubscribe",
+            "programUnsubscribe",
+            json!([program_id, config]),
+        )
+        .await
+    }
+
+    /// `logsSubscribe`, the logs of each transaction matching the filter
+    pub async fn logs_subscribe(
+        &self,
+        filter: LogsFilter,
+        commitment: Commitment,
+    ) -> AtollResult<Subscription<LogsNotification>> {
+        let config = json!({ "commitment": <&str>::from(commitment) });
+
+        self.subscribe(
+            "logsSubscribe",
+            "logsUnsubscribe",
+            json!([filter.to_json(), config]),
+        )
+        .await
+    }
+
+    /// `signatureSubscribe`, resolves once the transaction with the base58 encoded
+    /// signature reaches the commitment. A transaction that fails resolves with its
+    /// error in [SignatureResult::err]. It waits as long as the connection is open,
+    /// so race it with a timer or the expiry of the blockhash of the transaction
+    pub async fn signature_subscribe(
+        &self,
+        signature: &str,
+        commitment: Commitment,
+    ) -> AtollResult<SignatureNotification> {
+        let subscription = self.signature_subscription(signature, commitment).await?;
+        // A gap means the subscription was made again, which still resolves
+        let mut notification = None;
+        while let Some(received) = subscription.recv().await {
+            if let Notification::Value(value) = received {
+                notification = Some(value);
+
+                break;
+            }
+        }
+
+        // The node ends the subscription after its only notification
+        subscription.forget();
+
+        notification.ok_or_else(|| {
+            AtollError::Transport(
+                "The pubsub connection closed before the transaction reached the commitment"
+                    .to_owned(),
+            )
+        })
+    }
+
+    /// The subscription behind [Self::signature_subscribe], made before the
+    /// transaction is sent so its outcome cannot be missed
+    pub(crate) async fn signature_subscription(
+        &self,
+        signature: &str,
+        commitment: Commitment,
+    ) -> AtollResult<Subscription<SignatureNotification>> {
+        let config = json!({ "commitment": <&str>::from(commitment) });
+
+        self.subscribe(
+            "signatureSubscribe",
+            "signatureUnsubscribe",
+            json!([signature, config]),
+        )
+        .await
+    }
+
+    /// `slotSubscribe`, a notification each time the node processes a slot
+    pub async fn slot_subscribe(&self) -> AtollResult<Subscription<SlotNotification>> {
+        self.subscribe("slotSubscribe", "slotUnsubscribe", json!([]))
+            .await
+    }
+
+    /// `slotsUpdatesSubscribe`, a notification for each step of every slot through
+    /// the node. The node may not serve it since it is marked unstable
+    pub async fn slots_updates_subscribe(&self) -> AtollResult<Subscription<SlotUpdate>> {
+        self.subscribe(
+            "slotsUpdatesSubscribe",
+            "slotsUpdatesUnsubscribe",
+            json!([]),
+        )
+        .await
+    }
+
+    /// `blockSubscribe`, each block matching the filter once it reaches the
+    /// commitment, with the same base64 encoded transactions and rewards as
+    /// [crate::GetBlockRequest]. Only nodes started with
+    /// `--rpc-pubsub-enable-block-subscription` serve it
+    pub async fn block_subscribe(
+        &self,
+        filter: BlockFilter,
+        commitment: Commitment,
+    ) -> AtollResult<Subscription<BlockNotification>> {
+        let config = json!({
+            "commitment": <&str>::from(commitment),
+            "encoding": "base64",
+            "transactionDetails": "full",
+            "showRewards": true,
+            "maxSupportedTransactionVersion": 0,
+        });
+
+        self.subscribe(
+            "blockSubscribe",
+            "blockUnsubscribe",
+            json!([filter.to_json(), config]),
+        )
+        .await
+    }
+}
@@ -0,0 +1,766 @@
+use crate::{
+    runtime::Instant, AtollError, AtollResult, RpcHeaders, WsConnection, WsMessage, WsTransport,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+use web3utilities::Utilities;
+
+/// The GUID a server appends to the key of the handshake, RFC 6455 section 1.3
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The largest handshake response read before giving up on the server
+const MAX_HANDSHAKE_BYTES: usize = 16 * 1024;
+
+/// The largest message accepted, `blockSubscribe` notifications can be tens of megabytes
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Connects to `ws://` endpoints over a `TcpStream`, and to `wss://` endpoints with
+/// rustls when the `tls` feature is enabled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TcpWsTransport;
+
+impl WsTransport for TcpWsTransport {
+    fn connect(
+        &self,
+        url: &str,
+        headers: &RpcHeaders,
+        timeout: Duration,
+    ) -> AtollResult<Box<dyn WsConnection>> {
+        let target = WsTarget::parse(url)?;
+
+        let address = (target.host.as_str(), target.port)
+            .to_socket_addrs()
+            .map_err(io_error)?
+            .next()
+            .ok_or_else(|| {
+                AtollError::Transport(format!("`{}` did not resolve to an address", target.host))
+            })?;
+
+        let tcp = TcpStream::connect_timeout(&address, timeout).map_err(io_error)?;
+        tcp.set_nodelay(true).map_err(io_error)?;
+        tcp.set_read_timeout(Some(timeout)).map_err(io_error)?;
+        tcp.set_write_timeout(Some(timeout)).map_err(io_error)?;
+
+        let stream = if target.secure {
+            WsStream::tls(&target.host, tcp)?
+        } else {
+            WsStream::Plain(tcp)
+        };
+
+        let mut connection = TcpWsConnection {
+            stream,
+            buffer: Vec::new(),
+            fragments: Vec::new(),
+        };
+        connection.handshake(&target, headers)?;
+
+        Ok(Box::new(connection))
+    }
+}
+
+/// The parts of a `ws://` or `wss://` URL
+#[derive(Debug)]
+struct WsTarget {
+    secure: bool,
+    /// The host and port as written in the URL, sent as the `Host` header
+    authority: String,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl WsTarget {
+    fn parse(url: &str) -> AtollResult<Self> {
+        let invalid = |reason: &str| AtollError::InvalidUrl(format!("`{url}` {reason}"));
+
+        let (secure, rest) = if let Some(rest) = url.strip_prefix("wss://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("ws://") {
+            (false, rest)
+        } else {
+            return Err(invalid("must use the `ws` or `wss` scheme"));
+        };
+
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+
+        let (host, port) = match authority.strip_prefix('[') {
+            // An IPv6 address like `[::1]:8900`
+            Some(bracketed) => {
+                let (host, port) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| invalid("has an unterminated IPv6 address"))?;
+                (host, port.strip_prefix(':'))
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+
+        if host.is_empty() {
+            return Err(invalid("does not name a host"));
+        }
+
+        let port = match port {
+            Some(port) => port
+                .parse::<u16>()
+                .map_err(|_| invalid("has an invalid port"))?,
+            None if secure => 443,
+            None => 80,
+        };
+
+        Ok(WsTarget {
+            secure,
+            authority: authority.to_owned(),
+            host: host.to_owned(),
+            port,
+            path: if path.starts_with('?') {
+                format!("/{path}")
+            } else {
+                path.to_owned()
+            },
+        })
+    }
+}
+
+enum WsStream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+}
+
+impl WsStream {
+    #[cfg(feature = "tls")]
+    fn tls(host: &str, tcp: TcpStream) -> AtollResult<Self> {
+        let server_name = rustls::ServerName::try_from(host)
+            .map_err(|error| AtollError::InvalidUrl(format!("`{host}` {error}")))?;
+        let connection = rustls::ClientConnection::new(tls_config(), server_name)
+            .map_err(|error| AtollError::Transport(error.to_string()))?;
+
+        Ok(WsStream::Tls(Box::new(rustls::StreamOwned::new(
+            connection, tcp,
+        ))))
+    }
+
+    #[cfg(not(feature = "tls"))]
+    fn tls(_host: &str, _tcp: TcpStream) -> AtollResult<Self> {
+        Err(AtollError::Transport(
+            "Connecting to `wss://` endpoints needs the `tls` feature of atoll \
+            or a client given another transport"
+                .to_owned(),
+        ))
+    }
+
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            WsStream::Plain(tcp) => tcp,
+            #[cfg(feature = "tls")]
+            WsStream::Tls(stream) => &stream.sock,
+        }
+    }
+}
+
+impl Read for WsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            WsStream::Plain(tcp) => tcp.read(buf),
+            #[cfg(feature = "tls")]
+            WsStream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for WsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            WsStream::Plain(tcp) => tcp.write(buf),
+            #[cfg(feature = "tls")]
+            WsStream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            WsStream::Plain(tcp) => tcp.flush(),
+            #[cfg(feature = "tls")]
+            WsStream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// The TLS configuration trusting the Mozilla root certificates, built once
+#[cfg(feature = "tls")]
+fn tls_config() -> std::sync::Arc<rustls::ClientConfig> {
+    static CONFIG: std::sync::OnceLock<std::sync::Arc<rustls::ClientConfig>> =
+        std::sync::OnceLock::new();
+
+    CONFIG
+        .get_or_init(|| {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+                rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    anchor.subject,
+                    anchor.spki,
+                    anchor.name_constraints,
+                )
+            }));
+
+            std::sync::Arc::new(
+                rustls::ClientConfig::builder()
+                    .with_safe_defaults()
+                    .with_root_certificates(roots)
+                    .with_no_client_auth(),
+            )
+        })
+        .clone()
+}
+
+/// A WebSocket frame, unmasked
+#[derive(Debug)]
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+struct TcpWsConnection {
+    stream: WsStream,
+    /// Bytes read but not yet parsed into frames
+    buffer: Vec<u8>,
+    /// The payload of a fragmented message received so far
+    fragments: Vec<u8>,
+}
+
+impl TcpWsConnection {
+    fn handshake(&mut self, target: &WsTarget, headers: &RpcHeaders) -> AtollResult<()> {
+        let key = BASE64.encode(&Utilities::rand32_chacha20()[..16]);
+
+        let mut request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+            Sec-WebSocket-Key: {key}\r\nSec-WebSocket-Version: 13\r\n",
+            target.path, target.authority
+        );
+        headers.iter().for_each(|(name, value)| {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        });
+        request.push_str("\r\n");
+
+        self.stream
+            .write_all(request.as_bytes())
+            .and_then(|_| self.stream.flush())
+            .map_err(io_error)?;
+
+        let end = loop {
+            if let Some(index) = self
+                .buffer
+                .windows(4)
+                .position(|window| window == b"\r\n\r\n")
+            {
+                break index + 4;
+            }
+
+            if self.buffer.len() > MAX_HANDSHAKE_BYTES {
+                return Err(AtollError::Transport(
+                    "The WebSocket handshake response is too large".to_owned(),
+                ));
+            }
+
+            self.fill().map_err(io_error)?;
+        };
+
+        let head = String::from_utf8_lossy(&self.buffer[..end]).into_owned();
+        // Frames sent right after the handshake stay in the buffer
+        self.buffer.drain(..end);
+
+        let mut lines = head.split("\r\n");
+        let status = lines.next().unwrap_or_default();
+
+        if status.split(' ').nth(1) != Some("101") {
+            return Err(AtollError::Transport(format!(
+                "The pubsub endpoint refused the WebSocket upgrade with `{status}`"
+            )));
+        }
+
+        let accept = lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("sec-websocket-accept"))
+            .map(|(_, value)| value.trim());
+        let expected = accept_key(&key);
+
+        if accept != Some(expected.as_str()) {
+            return Err(AtollError::Transport(
+                "The pubsub endpoint answered with an invalid `Sec-WebSocket-Accept`".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Read the bytes available into the buffer, failing once the server hung up
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 16 * 1024];
+
+        match self.stream.read(&mut chunk)? {
+            0 => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The pubsub endpoint closed the connection",
+            )),
+            read => {
+                self.buffer.extend_from_slice(&chunk[..read]);
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Parse the first frame of the buffer, `None` until all of it was read
+    fn parse_frame(&mut self) -> AtollResult<Option<Frame>> {
+        let buffer = &self.buffer;
+
+        if buffer.len() < 2 {
+            return Ok(None);
+        }
+
+        let fin = buffer[0] & 0x80 != 0;
+        let opcode = buffer[0] & 0x0f;
+        let masked = buffer[1] & 0x80 != 0;
+
+        let (length, mut offset) = match buffer[1] & 0x7f {
+            126 if buffer.len() >= 4 => (u16::from_be_bytes([buffer[2], buffer[3]]) as u64, 4),
+            127 if buffer.len() >= 10 => {
+                let mut length = [0u8; 8];
+                length.copy_from_slice(&buffer[2..10]);
+
+                (u64::from_be_bytes(length), 10)
+            }
+            126 | 127 => return Ok(None),
+            length => (length as u64, 2),
+        };
+
+        let length = usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= MAX_MESSAGE_BYTES)
+            .ok_or_else(|| {
+                AtollError::Transport(format!(
+                    "A WebSocket frame of {length} bytes is larger than {MAX_MESSAGE_BYTES} bytes"
+                ))
+            })?;
+
+        let mask = if masked {
+            if buffer.len() < offset + 4 {
+                return Ok(None);
+            }
+            let mask = [
+                buffer[offset],
+                buffer[offset + 1],
+                buffer[offset + 2],
+                buffer[offset + 3],
+            ];
+            offset += 4;
+
+            Some(mask)
+        } else {
+            None
+        };
+
+        if buffer.len() < offset + length {
+            return Ok(None);
+        }
+
+        let mut payload = self.buffer[offset..offset + length].to_vec();
+        if let Some(mask) = mask {
+            payload
+                .iter_mut()
+                .enumerate()
+                .for_each(|(index, byte)| *byte ^= mask[index % 4]);
+        }
+        self.buffer.drain(..offset + length);
+
+        Ok(Some(Frame {
+            fin,
+            opcode,
+            payload,
+        }))
+    }
+
+    /// Answer control frames and join fragments, returning complete messages
+    fn handle(&mut self, frame: Frame) -> AtollResult<Option<WsMessage>> {
+        match frame.opcode {
+            OPCODE_CONTINUATION | OPCODE_TEXT | OPCODE_BINARY => {
+                if frame.opcode != OPCODE_CONTINUATION {
+                    self.fragments.clear();
+                }
+                self.fragments.extend_from_slice(&frame.payload);
+
+                if self.fragments.len() > MAX_MESSAGE_BYTES {
+                    return Err(AtollError::Transport(format!(
+                        "A WebSocket message is larger than {MAX_MESSAGE_BYTES} bytes"
+                    )));
+                }
+
+                if !frame.fin {
+                    return Ok(None);
+                }
+
+                String::from_utf8(core::mem::take(&mut self.fragments))
+                    .map(|text| Some(WsMessage::Text(text)))
+                    .map_err(|error| AtollError::Transport(error.to_string()))
+            }
+            OPCODE_CLOSE => {
+                self.write_frame(OPCODE_CLOSE, &frame.payload).ok();
+
+                Ok(Some(WsMessage::Close))
+            }
+            OPCODE_PING => {
+                self.write_frame(OPCODE_PONG, &frame.payload)?;
+
+                Ok(None)
+            }
+            OPCODE_PONG => Ok(Some(WsMessage::Pong(frame.payload))),
+            opcode => Err(AtollError::Transport(format!(
+                "The WebSocket frame has the unknown opcode {opcode}"
+            ))),
+        }
+    }
+
+    /// Send a frame masked with a random key as clients must
+    fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> AtollResult<()> {
+        let mut frame = Vec::with_capacity(payload.len() + 14);
+        frame.push(0x80 | opcode);
+
+        match payload.len() {
+            length if length < 126 => frame.push(0x80 | length as u8),
+            length if length <= u16::MAX as usize => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(length as u16).to_be_bytes());
+            }
+            length => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(length as u64).to_be_bytes());
+            }
+        }
+
+        let random = Utilities::rand32_chacha20();
+        let mask = [random[0], random[1], random[2], random[3]];
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            payload
+                .iter()
+                .enumerate()
+                .map(|(index, byte)| byte ^ mask[index % 4]),
+        );
+
+        self.stream
+            .write_all(&frame)
+            .and_then(|_| self.stream.flush())
+            .map_err(io_error)
+    }
+}
+
+impl WsConnection for TcpWsConnection {
+    fn send_text(&mut self, text: &str) -> AtollResult<()> {
+        self.write_frame(OPCODE_TEXT, text.as_bytes())
+    }
+
+    fn ping(&mut self, payload: &[u8]) -> AtollResult<()> {
+        self.write_frame(OPCODE_PING, payload)
+    }
+
+    fn recv(&mut self, timeout: Duration) -> AtollResult<Option<WsMessage>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            while let Some(frame) = self.parse_frame()? {
+                if let Some(message) = self.handle(frame)? {
+                    return Ok(Some(message));
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+
+            self.stream
+                .tcp()
+                .set_read_timeout(Some(remaining))
+                .map_err(io_error)?;
+
+            match self.fill() {
+                Ok(()) => (),
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None)
+                }
+                Err(error) => return Err(io_error(error)),
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        // Status 1000, a normal closure
+        self.write_frame(OPCODE_CLOSE, &1000u16.to_be_bytes()).ok();
+        self.stream.tcp().shutdown(Shutdown::Both).ok();
+    }
+}
+
+fn io_error(error: io::Error) -> AtollError {
+    AtollError::Transport(error.to_string())
+}
+
+/// The `Sec-WebSocket-Accept` a server answers the handshake key with
+fn accept_key(key: &str) -> String {
+    BASE64.encode(sha1(format!("{key}{ACCEPT_GUID}").as_bytes()))
+}
+
+/// SHA-1 of the handshake key, RFC 3174. Only used to check `Sec-WebSocket-Accept`
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        block.chunks(4).enumerate().for_each(|(index, word)| {
+            words[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        });
+        for index in 16..80 {
+            words[index] =
+                (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16])
+                    .rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+
+        for (index, word) in words.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        state = [
+            state[0].wrapping_add(a),
+            state[1].wrapping_add(b),
+            state[2].wrapping_add(c),
+            state[3].wrapping_add(d),
+            state[4].wrapping_add(e),
+        ];
+    }
+
+    let mut digest = [0u8; 20];
+    digest
+        .chunks_mut(4)
+        .zip(state)
+        .for_each(|(chunk, word)| chunk.copy_from_slice(&word.to_be_bytes()));
+
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// A client connection and the server side of its socket
+    fn connection_pair() -> (TcpWsConnection, TcpWsConnection) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        let connection = |tcp| TcpWsConnection {
+            stream: WsStream::Plain(tcp),
+            buffer: Vec::new(),
+            fragments: Vec::new(),
+        };
+
+        (connection(client), connection(server))
+    }
+
+    /// Parse and handle every frame of the buffer, returning the complete messages
+    fn messages(connection: &mut TcpWsConnection) -> Vec<WsMessage> {
+        let mut messages = Vec::new();
+
+        while let Some(frame) = connection.parse_frame().unwrap() {
+            messages.extend(connection.handle(frame).unwrap());
+        }
+
+        messages
+    }
+
+    #[test]
+    fn accept_key_matches_rfc_6455() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn parses_unmasked_and_masked_frames() {
+        let (mut connection, _server) = connection_pair();
+
+        // The single-frame examples of RFC 6455 section 5.7
+        connection
+            .buffer
+            .extend_from_slice(&[0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f]);
+        connection.buffer.extend_from_slice(&[
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ]);
+
+        assert_eq!(
+            messages(&mut connection),
+            vec![
+                WsMessage::Text("Hello".to_owned()),
+                WsMessage::Text("Hello".to_owned())
+            ]
+        );
+        assert!(connection.buffer.is_empty());
+    }
+
+    #[test]
+    fn joins_fragmented_messages() {
+        let (mut connection, _server) = connection_pair();
+
+        connection
+            .buffer
+            .extend_from_slice(&[0x01, 0x03, 0x48, 0x65, 0x6c]);
+        assert!(messages(&mut connection).is_empty());
+
+        // A pong may arrive between the fragments of a message
+        connection.buffer.extend_from_slice(&[0x8a, 0x01, 0x2a]);
+        connection
+            .buffer
+            .extend_from_slice(&[0x80, 0x02, 0x6c, 0x6f]);
+
+        assert_eq!(
+            messages(&mut connection),
+            vec![
+                WsMessage::Pong(vec![0x2a]),
+                WsMessage::Text("Hello".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn waits_for_partial_frames() {
+        let (mut connection, _server) = connection_pair();
+
+        let mut frame = vec![0x81, 0x7e, 0x01, 0x00];
+        frame.extend([b'a'; 256]);
+
+        for byte in &frame[..frame.len() - 1] {
+            connection.buffer.push(*byte);
+            assert!(connection.parse_frame().unwrap().is_none());
+        }
+        connection.buffer.push(frame[frame.len() - 1]);
+
+        let parsed = connection.parse_frame().unwrap().unwrap();
+        assert!(parsed.fin);
+        assert_eq!(parsed.opcode, OPCODE_TEXT);
+        assert_eq!(parsed.payload, vec![b'a'; 256]);
+    }
+
+    #[test]
+    fn parses_extended_lengths() {
+        let (mut connection, _server) = connection_pair();
+
+        // 256 bytes with a 16 bit length and 64 KiB with a 64 bit length, RFC 6455 section 5.7
+        connection
+            .buffer
+            .extend_from_slice(&[0x82, 0x7e, 0x01, 0x00]);
+        connection.buffer.extend(vec![b'b'; 256]);
+        connection
+            .buffer
+            .extend_from_slice(&[0x82, 0x7f, 0, 0, 0, 0, 0, 0x01, 0x00, 0x00]);
+        connection.buffer.extend(vec![b'c'; 65536]);
+
+        assert_eq!(
+            messages(&mut connection),
+            vec![
+                WsMessage::Text("b".repeat(256)),
+                WsMessage::Text("c".repeat(65536))
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_frames_over_the_limit() {
+        let (mut connection, _server) = connection_pair();
+
+        connection.buffer.extend_from_slice(&[0x82, 0x7f]);
+        connection
+            .buffer
+            .extend_from_slice(&(MAX_MESSAGE_BYTES as u64 + 1).to_be_bytes());
+
+        assert!(connection.parse_frame().is_err());
+    }
+
+    #[test]
+    fn writes_masked_frames_of_every_length() {
+        let (mut client, mut server) = connection_pair();
+
+        for length in [5, 125, 126, 300, 65535, 65536, 70000] {
+            let text = "x".repeat(length);
+            client.send_text(&text).unwrap();
+
+            // Client frames are masked, which the server side unmasks while parsing
+            assert_eq!(
+                server.recv(Duration::from_secs(5)).unwrap(),
+                Some(WsMessage::Text(text))
+            );
+        }
+    }
+
+    #[test]
+    fn answers_pings_with_pongs() {
+        let (mut client, mut server) = connection_pair();
+
+        client
+            .buffer
+            .extend_from_slice(&[0x89, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f]);
+        assert!(messages(&mut client).is_empty());
+
+        assert_eq!(
+            server.recv(Duration::from_secs(5)).unwrap(),
+            Some(WsMessage::Pong(b"Hello".to_vec()))
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{AtollResult, RpcHeaders};
+use std::{sync::Arc, time::Duration};
+
+/// A message read from a WebSocket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsMessage {
+    /// A text message, a JSON-RPC response or notification
+    Text(String),
+    /// The answer to a ping with its payload
+    Pong(Vec<u8>),
+    /// The server closed the connection
+    Close,
+}
+
+/// An open WebSocket. It is owned by the reader thread of a [crate::PubsubClient],
+/// so the methods block and are never called concurrently
+pub trait WsConnection: Send + 'static {
+    /// Send a text message
+    fn send_text(&mut self, text: &str) -> AtollResult<()>;
+
+    /// Send a ping, the server answers with a [WsMessage::Pong] carrying the payload
+    fn ping(&mut self, payload: &[u8]) -> AtollResult<()>;
+
+    /// Wait up to `timeout` for the next message, `Ok(None)` if none arrived in time.
+    /// Pings from the server are answered by the connection and not returned
+    fn recv(&mut self, timeout: Duration) -> AtollResult<Option<WsMessage>>;
+
+    /// Send a close message, errors are ignored since the connection is going away
+    fn close(&mut self);
+}
+
+/// Opens the WebSocket of a [crate::PubsubClient]. Implement it to use another
+/// WebSocket client. The default is [TcpWsTransport], which connects to `wss://`
+/// endpoints when the `tls` feature provides the rustls stack
+pub trait WsTransport: core::fmt::Debug + Send + Sync + 'static {
+    /// Open the WebSocket with the headers, failing if the handshake takes longer than `timeout`
+    fn connect(
+        &self,
+        url: &str,
+        headers: &RpcHeaders,
+        timeout: Duration,
+    ) -> AtollResult<Box<dyn WsConnection>>;
+}
+
+/// The transport of clients that are not given one with `change_transport`
+pub(crate) fn default_ws_transport() -> Arc<dyn WsTransport> {
+    Arc::new(crate::TcpWsTransport)
+}
@@ -0,0 +1,219 @@
+use crate::{
+    default_transport, next_request_id, AtollError, AtollResult, Cluster, HttpTransport,
+    RequestOutcome, RpcHeaders, RpcJsonError, RpcMethod, RpcRequest, TransportRequest,
+    TransportResponse, DEFAULT_REQUEST_TIMEOUT,
+};
+use core::{fmt, marker::PhantomData};
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// Sends several requests in one HTTP POST as a JSON-RPC batch.
+/// Each request keeps its own method, parameters and response type and
+/// its response is looked up with the [BatchHandle] returned when it was added.
+/// The cluster of the batch is used for every request, the `before_send` hook of
+/// the middlewares of each request is applied to its entry
+#[derive(Debug)]
+pub struct RpcBatch {
+    cluster: Cluster,
+    requests: Vec<(u64, RpcRequest)>,
+    timeout: Duration,
+    headers: RpcHeaders,
+    transport: Arc<dyn HttpTransport>,
+}
+
+/// Identifies a request inside a [RpcBatch] and pins the type of its response
+#[derive(Debug)]
+pub struct BatchHandle<T> {
+    id: u64,
+    response: PhantomData<T>,
+}
+
+impl<T> BatchHandle<T> {
+    /// The JSON-RPC id of the request in the batch
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Default for RpcBatch {
+    fn default() -> Self {
+        RpcBatch::new()
+    }
+}
+
+impl RpcBatch {
+    pub fn new() -> Self {
+        RpcBatch {
+            cluster: Cluster::default(),
+            requests: Vec::default(),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            headers: RpcHeaders::default(),
+            transport: default_transport(),
+        }
+    }
+
+    pub fn change_cluster(mut self, cluster: Cluster) -> Self {
+        self.cluster = cluster;
+
+        self
+    }
+
+    /// The time the whole batch may take, rounded up to whole seconds
+    pub fn change_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// The HTTP headers of the batch. Headers of the individual requests are not sent
+    pub fn change_headers(mut self, headers: RpcHeaders) -> Self {
+        self.headers = headers;
+
+        self
+    }
+
+    /// Send the batch through the transport instead of the default one
+    pub fn change_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Add a request to the batch under a new id unique within the process
+    pub fn add<T: fmt::Debug + DeserializeOwned>(&mut self, request: RpcRequest) -> BatchHandle<T> {
+        let id = next_request_id();
+        self.requests.push((id, request));
+
+        BatchHandle {
+            id,
+            response: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Send all requests in one round trip
+    pub async fn send(self) -> AtollResult<BatchResponses> {
+        let (http_request, methods) = self.http_request();
+        let response = self.transport.clone().post(http_request).await?;
+
+        Self::parse(response, methods)
+    }
+
+    /// Send all requests in one round trip on the current thread
+    pub fn send_blocking(self) -> AtollResult<BatchResponses> {
+        let (http_request, methods) = self.http_request();
+
+        Self::parse(self.transport.post_blocking(&http_request)?, methods)
+    }
+
+    fn http_request(&self) -> (TransportRequest, HashMap<u64, RpcMethod>) {
+        let mut body = json::JsonValue::new_array();
+        let mut methods = HashMap::new();
+
+        for (id, request) in self.requests.iter() {
+            // Pushing onto an array value cannot fail
+            body.push(request.to_sent_json(request.method(), *id)).ok();
+            methods.insert(*id, *request.method());
+        }
+
+        let http_request = TransportRequest {
+            url: self.cluster.url().to_owned(),
+            headers: self.headers.clone().add("Content-Type", "application/json"),
+            body: body.dump(),
+            timeout: self.timeout,
+        };
+
+        (http_request, methods)
+    }
+
+    fn parse(
+        response: TransportResponse,
+        methods: HashMap<u64, RpcMethod>,
+    ) -> AtollResult<BatchResponses> {
+        let response_body = response.as_str()?;
+
+        let responses = match serde_json::from_str::<Vec<serde_json::Value>>(response_body) {
+            Ok(responses) => responses,
+            // The node rejected the batch as a whole
+            Err(error) => {
+                return match serde_json::from_str::<RpcJsonError>(response_body) {
+                    Ok(json_error) => RequestOutcome::<()>::InvalidJson(json_error)
+                        .into_result()
+                        .map(|_| BatchResponses::default()),
+                    Err(_) => Err(AtollError::SerdeJsonDeser(error.to_string())),
+                };
+            }
+        };
+
+        let mut bodies = HashMap::new();
+        for response in responses {
+            // Errors for entries the node could not read have a `null` id
+            let Some(id) = response.get("id").and_then(|id| id.as_u64()) else {
+                continue;
+            };
+
+            if !methods.contains_key(&id) {
+                // Report the lowest request id still waiting for its response
+                let expected = methods
+                    .keys()
+                    .filter(|id| !bodies.contains_key(*id))
+                    .min()
+                    .copied()
+                    .unwrap_or_default();
+
+                return Err(AtollError::ResponseIdMismatch {
+                    expected,
+                    received: id,
+                });
+            }
+
+            bodies.insert(id, response.to_string());
+        }
+
+        Ok(BatchResponses { methods, bodies })
+    }
+}
+
+/// The responses of a [RpcBatch] correlated by request id
+#[derive(Debug, Default)]
+pub struct BatchResponses {
+    methods: HashMap<u64, RpcMethod>,
+    bodies: HashMap<u64, String>,
+}
+
+impl BatchResponses {
+    /// The response of the request or the error the node returned for it
+    pub fn outcome<T: fmt::Debug + DeserializeOwned>(
+        &self,
+        handle: &BatchHandle<T>,
+    ) -> AtollResult<RequestOutcome<T>> {
+        match (self.methods.get(&handle.id), self.bodies.get(&handle.id)) {
+            (Some(method), Some(body)) => method.is_ok_or::<T>(body),
+            _ => Err(AtollError::NotFound(format!(
+                "The batch has no response for request id `{}`",
+                handle.id
+            ))),
+        }
+    }
+
+    /// The `result` of the request, see [RequestOutcome::into_result]
+    pub fn get<T: fmt::Debug + DeserializeOwned>(&self, handle: &BatchHandle<T>) -> AtollResult<T> {
+        self.outcome(handle)?.into_result()
+    }
+
+    /// The number of responses received
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+}
@@ -1,13 +1,28 @@
-use crate::{TransactionError, TransactionResult};
-use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    tx::VersionedTransaction, AtollError, AtollResult, Encoding, TransactionError,
+    TransactionResult, WireMessage, WireReader,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
+use web3utilities::Utilities;
 
 #[derive(
-    Debug, PartialEq, PartialOrd, Clone, Deserialize, Serialize, BorshSerialize, BorshDeserialize,
+    Debug,
+    PartialEq,
+    PartialOrd,
+    Clone,
+    Deserialize,
+    Serialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct Block {
-    pub block_height: u64,
+    /// `None` for blocks produced before block heights were recorded
+    /// or served by the deprecated `getConfirmedBlock`
+    pub block_height: Option<u64>,
     pub block_time: u64,
     pub blockhash: String,
     pub parent_slot: u64,
@@ -26,6 +41,7 @@ pub struct Block {
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct Rewards {
@@ -47,6 +63,7 @@ pub struct Rewards {
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 pub enum RewardType {
     Fee,
@@ -56,7 +73,15 @@ pub enum RewardType {
 }
 
 #[derive(
-    Debug, PartialEq, PartialOrd, Clone, Deserialize, Serialize, BorshSerialize, BorshDeserialize,
+    Debug,
+    PartialEq,
+    PartialOrd,
+    Clone,
+    Deserialize,
+    Serialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct TxWithMeta {
@@ -64,10 +89,156 @@ pub struct TxWithMeta {
     pub transaction: (String, String),
 }
 
+/// A single transaction fetched by its signature with `getTransaction`
 #[derive(
     Debug, PartialEq, PartialOrd, Clone, Deserialize, Serialize, BorshSerialize, BorshDeserialize,
 )]
 #[serde(rename_all = "camelCase")]
+pub struct ConfirmedTransaction {
+    /// The slot of the block containing the transaction
+    pub slot: u64,
+    /// The estimated production time of the block as a Unix timestamp, if known
+    pub block_time: Option<i64>,
+    /// `None` when the request did not set `maxSupportedTransactionVersion`
+    pub version: Option<TransactionVersion>,
+    #[serde(flatten)]
+    pub tx_with_meta: TxWithMeta,
+}
+
+/// The version of a transaction message
+#[derive(Debug, Eq, PartialEq, PartialOrd, Clone, Copy, BorshSerialize, BorshDeserialize)]
+pub enum TransactionVersion {
+    /// A message without address lookup tables, answered as `"legacy"`
+    Legacy,
+    /// A versioned message like `0`
+    Number(u8),
+}
+
+impl Serialize for TransactionVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TransactionVersion::Legacy => serializer.serialize_str("legacy"),
+            TransactionVersion::Number(version) => serializer.serialize_u8(*version),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TransactionVersion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawVersion {
+            Number(u8),
+            Name(String),
+        }
+
+        match RawVersion::deserialize(deserializer)? {
+            RawVersion::Number(version) => Ok(TransactionVersion::Number(version)),
+            RawVersion::Name(name) if name == "legacy" => Ok(TransactionVersion::Legacy),
+            RawVersion::Name(name) => Err(serde::de::Error::custom(format!(
+                "Unknown transaction version `{name}`"
+            ))),
+        }
+    }
+}
+
+impl TxWithMeta {
+    /// Decode the encoded transaction into the bytes of its wire format
+    pub fn to_wire_bytes(&self) -> AtollResult<Vec<u8>> {
+        let (data, encoding) = &self.transaction;
+
+        match Encoding::from(encoding.as_str()) {
+            Encoding::Base58 => Utilities::base58_to_bytes(data).map_err(AtollError::Utilities),
+            Encoding::Base64 => Ok(BASE64.decode(data)?),
+            Encoding::UnsupportedEncoding => Err(AtollError::UnsupportedEncoding(encoding.clone())),
+        }
+    }
+
+    /// Decode the transaction into its signatures and typed message
+    pub fn decode(&self) -> AtollResult<VersionedTransaction> {
+        VersionedTransaction::deserialize(&self.to_wire_bytes()?)
+    }
+
+    /// The base58 encoded signatures of the transaction. The first signature
+    /// is the one used to identify the transaction
+    pub fn signatures(&self) -> AtollResult<Vec<String>> {
+        let wire_bytes = self.to_wire_bytes()?;
+        let mut reader = WireReader::new(&wire_bytes);
+
+        (0..reader.read_compact_u16()?)
+            .map(|_| Ok(bs58::encode(reader.read_bytes(64)?).into_string()))
+            .collect()
+    }
+
+    /// The number of signatures in the transaction, used to compute the base fee
+    pub fn signature_count(&self) -> AtollResult<usize> {
+        let wire_bytes = self.to_wire_bytes()?;
+
+        Ok(WireReader::new(&wire_bytes).read_compact_u16()? as usize)
+    }
+
+    /// The base58 encoded static account keys of the transaction message, signers first.
+    /// Accounts loaded from address lookup tables are in `meta.loaded_addresses`
+    pub fn account_keys(&self) -> AtollResult<Vec<String>> {
+        Ok(self.decode_message()?.account_keys)
+    }
+
+    /// The static account keys followed by the writable and then the readonly
+    /// accounts loaded from address lookup tables. This is the order used
+    /// by the account indexes of both top-level and inner instructions
+    pub fn all_account_keys(&self) -> AtollResult<Vec<String>> {
+        let mut account_keys = self.account_keys()?;
+
+        if let Some(loaded_addresses) = self.meta.loaded_addresses.as_ref() {
+            account_keys.extend(loaded_addresses.writable.iter().cloned());
+            account_keys.extend(loaded_addresses.readonly.iter().cloned());
+        }
+
+        Ok(account_keys)
+    }
+
+    /// The top-level instructions of the transaction with base58 encoded data,
+    /// in the same form as the instructions in `meta.inner_instructions`
+    pub fn instructions(&self) -> AtollResult<Vec<Instruction>> {
+        Ok(self.decode_message()?.instructions)
+    }
+
+    /// The base58 encoded accounts that signed the transaction, the fee payer first
+    pub fn signers(&self) -> AtollResult<Vec<String>> {
+        let message = self.decode_message()?;
+        let mut account_keys = message.account_keys;
+        account_keys.truncate(message.num_required_signatures as usize);
+
+        Ok(account_keys)
+    }
+
+    /// The base58 encoded account that paid the fees for the transaction
+    pub fn fee_payer(&self) -> AtollResult<String> {
+        self.signers()?
+            .into_iter()
+            .next()
+            .ok_or(AtollError::MalformedTransaction(
+                "The transaction has no fee payer".to_owned(),
+            ))
+    }
+
+    fn decode_message(&self) -> AtollResult<WireMessage> {
+        WireMessage::from_transaction_bytes(&self.to_wire_bytes()?)
+    }
+}
+
+#[derive(
+    Debug,
+    PartialEq,
+    PartialOrd,
+    Clone,
+    Deserialize,
+    Serialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
 pub struct TxMetadata {
     pub err: Option<TransactionError>,
     pub status: TransactionResult<()>,
@@ -85,7 +256,15 @@ pub struct TxMetadata {
 }
 
 #[derive(
-    Debug, PartialEq, PartialOrd, Clone, Deserialize, Serialize, BorshSerialize, BorshDeserialize,
+    Debug,
+    PartialEq,
+    PartialOrd,
+    Clone,
+    Deserialize,
+    Serialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionTokenBalance {
@@ -97,7 +276,15 @@ pub struct TransactionTokenBalance {
 }
 
 #[derive(
-    Debug, PartialEq, PartialOrd, Clone, Deserialize, Serialize, BorshSerialize, BorshDeserialize,
+    Debug,
+    PartialEq,
+    PartialOrd,
+    Clone,
+    Deserialize,
+    Serialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct TokenBalances {
@@ -108,7 +295,15 @@ pub struct TokenBalances {
 }
 
 #[derive(
-    Debug, PartialEq, PartialOrd, Clone, Deserialize, Serialize, BorshSerialize, BorshDeserialize,
+    Debug,
+    PartialEq,
+    PartialOrd,
+    Clone,
+    Deserialize,
+    Serialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct TokenAmount {
@@ -128,6 +323,7 @@ pub struct TokenAmount {
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct InnerInstructions {
@@ -145,6 +341,7 @@ pub struct InnerInstructions {
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct Instruction {
@@ -163,6 +360,7 @@ pub struct Instruction {
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct AccountMeta {
@@ -182,6 +380,7 @@ pub struct AccountMeta {
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct LoadedAddresses {
@@ -199,6 +398,7 @@ pub struct LoadedAddresses {
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionReturnData {
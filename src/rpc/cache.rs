@@ -0,0 +1,159 @@
+use crate::{runtime::Instant, AtollResult, HttpResponse, RpcMethod, TransportResponse};
+use core::fmt;
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+/// Caches the responses of methods whose result rarely changes so hot loops
+/// do not send the same request to the node again and again.
+/// Responses are keyed by cluster, method and parameters and kept for the
+/// time to live of their method. Methods without a time to live are never cached.
+/// Share it between requests with [crate::RpcRequest::add_cache]
+#[derive(Debug)]
+pub struct ResponseCache {
+    ttls: HashMap<RpcMethod, Duration>,
+    entries: Mutex<HashMap<CacheKey, CachedResponse>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    pub(crate) url: String,
+    pub(crate) method: RpcMethod,
+    pub(crate) params: String,
+}
+
+/// A successful response stored by a [ResponseCache]
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    status_code: u16,
+    headers: HashMap<String, String>,
+    reason_phrase: String,
+    body: String,
+    stored_at: Instant,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        ResponseCache::new()
+    }
+}
+
+impl ResponseCache {
+    /// A cache with the default time to live of `getGenesisHash`, `getVersion`,
+    /// `getEpochSchedule` and `getMinimumBalanceForRentExemption`
+    pub fn new() -> Self {
+        ResponseCache::empty()
+            .change_ttl(RpcMethod::GetGenesisHash, Duration::from_secs(24 * 60 * 60))
+            .change_ttl(RpcMethod::GetVersion, Duration::from_secs(5 * 60))
+            .change_ttl(RpcMethod::GetEpochSchedule, Duration::from_secs(60 * 60))
+            .change_ttl(
+                RpcMethod::GetMinimumBalanceForRentExemption,
+                Duration::from_secs(60 * 60),
+            )
+    }
+
+    /// A cache without any cached method
+    pub fn empty() -> Self {
+        ResponseCache {
+            ttls: HashMap::new(),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache the responses of the method for `ttl`
+    pub fn change_ttl(mut self, method: RpcMethod, ttl: Duration) -> Self {
+        self.ttls.insert(method, ttl);
+
+        self
+    }
+
+    /// Stop caching the responses of the method
+    pub fn remove_ttl(mut self, method: RpcMethod) -> Self {
+        self.ttls.remove(&method);
+
+        self
+    }
+
+    pub fn ttl(&self, method: &RpcMethod) -> Option<Duration> {
+        self.ttls.get(method).copied()
+    }
+
+    /// Drop all cached responses
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Drop the cached responses whose time to live has passed
+    pub fn purge_expired(&self) {
+        self.entries.lock().unwrap().retain(|key, entry| {
+            self.ttl(&key.method)
+                .map(|ttl| entry.stored_at.elapsed() < ttl)
+                .unwrap_or(false)
+        });
+    }
+
+    /// The number of cached responses, including expired ones not purged yet
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let ttl = self.ttl(&key.method)?;
+        let mut entries = self.entries.lock().unwrap();
+
+        match entries.get(key) {
+            Some(entry) if entry.stored_at.elapsed() < ttl => Some(entry.clone()),
+            Some(_) => {
+                entries.remove(key);
+
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Store the response if its method is cached and it is a successful result
+    pub(crate) fn insert(&self, key: CacheKey, response: &TransportResponse) {
+        if self.ttl(&key.method).is_none() || response.status_code != 200 {
+            return;
+        }
+
+        let Ok(body) = response.as_str() else {
+            return;
+        };
+
+        let is_result = serde_json::from_str::<serde_json::Value>(body)
+            .map(|value| value.get("result").is_some())
+            .unwrap_or(false);
+
+        if is_result {
+            self.entries.lock().unwrap().insert(
+                key,
+                CachedResponse {
+                    status_code: response.status_code,
+                    headers: response.headers.clone(),
+                    reason_phrase: response.reason_phrase.clone(),
+                    body: body.to_owned(),
+                    stored_at: Instant::now(),
+                },
+            );
+        }
+    }
+}
+
+impl CachedResponse {
+    pub(crate) fn parse<T: fmt::Debug + DeserializeOwned>(
+        &self,
+        method: &RpcMethod,
+    ) -> AtollResult<HttpResponse<T>> {
+        Ok(HttpResponse {
+            status_code: self.status_code,
+            headers: self.headers.clone(),
+            reason_phrase: self.reason_phrase.clone(),
+            body: method.is_ok_or::<T>(&self.body)?,
+        })
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A token shared between the owner of a request and the request itself.
+/// Cancelling it aborts every request it was added to, including requests waiting
+/// for a response or for their next retry, for example when the application shuts down
+/// or the caller's deadline passes. The aborted requests return [crate::AtollError::Cancelled].
+/// A blocking HTTP call already running on the blocking thread pool completes in the
+/// background and its response is discarded
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    inner: Arc<CancellationInner>,
+}
+
+#[derive(Debug)]
+struct CancellationInner {
+    cancelled: AtomicBool,
+    /// Closed on cancellation to wake every task waiting on [CancellationToken::cancelled]
+    sender: async_channel::Sender<()>,
+    receiver: async_channel::Receiver<()>,
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        let (sender, receiver) = async_channel::bounded(1);
+
+        CancellationToken {
+            inner: Arc::new(CancellationInner {
+                cancelled: AtomicBool::new(false),
+                sender,
+                receiver,
+            }),
+        }
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancel all requests holding a clone of this token
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.sender.close();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the token is cancelled
+    pub async fn cancelled(&self) {
+        if !self.is_cancelled() {
+            // Nothing is ever sent, `recv` only returns when the channel is closed
+            self.inner.receiver.recv().await.ok();
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{
+    AtollError, AtollResult, Cluster, GetHealthRequest, GetVersionRequest, HttpResponse, RpcMethod,
+    RpcRequest, Unconfigured,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr, sync::Mutex};
+
+/// The response of `getVersion`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct NodeVersion {
+    #[serde(rename = "solana-core")]
+    pub solana_core: String,
+    #[serde(rename = "feature-set")]
+    pub feature_set: Option<u32>,
+}
+
+/// A `major.minor.patch` version of `solana-core`
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct CoreVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl CoreVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        CoreVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for CoreVersion {
+    type Err = AtollError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        // Drop pre-release or build metadata like `1.16.7-rc1`
+        let release = value.split(['-', '+', ' ']).next().unwrap_or_default();
+        let mut parts = release.split('.').map(|part| part.parse::<u16>());
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch))) => {
+                Ok(CoreVersion::new(major, minor, patch))
+            }
+            _ => Err(AtollError::UnsupportedByNode(format!(
+                "Unable to parse the node version `{value}`"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for CoreVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The JSON-RPC error code of `getHealth` on an unhealthy node
+pub const NODE_UNHEALTHY_CODE: i16 = -32005;
+
+/// The response of `getHealth`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub enum NodeHealth {
+    Healthy,
+    /// The node is behind the cluster by this many slots
+    Behind(u64),
+    /// The node reported itself unhealthy without saying how far behind it is
+    Unhealthy(String),
+}
+
+impl NodeHealth {
+    /// Call `getHealth` on the node of the client, see [RpcRequest::template]. A node
+    /// answering with a JSON-RPC error is unhealthy, failing to answer at all is an error
+    pub async fn fetch(client: &RpcRequest<Unconfigured>) -> AtollResult<Self> {
+        NodeHealth::from_response(
+            client
+                .template()
+                .add_typed(GetHealthRequest)
+                .request()
+                .await,
+        )
+    }
+
+    /// Interpret the response of [crate::GetHealthRequest], reading how far the
+    /// node is behind from the `numSlotsBehind` of the error data
+    pub fn from_response(response: AtollResult<HttpResponse<String>>) -> AtollResult<Self> {
+        match response.and_then(|response| response.into_result()) {
+            Ok(status) if status == "ok" => Ok(NodeHealth::Healthy),
+            Ok(status) => Ok(NodeHealth::Unhealthy(status)),
+            Err(AtollError::Rpc {
+                code: NODE_UNHEALTHY_CODE,
+                message,
+                data,
+            }) => Ok(data
+                .and_then(|data| json::parse(&data).ok())
+                .and_then(|data| data["numSlotsBehind"].as_u64())
+                .map(NodeHealth::Behind)
+                .unwrap_or(NodeHealth::Unhealthy(message))),
+            Err(AtollError::Rpc { message, .. }) => Ok(NodeHealth::Unhealthy(message)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// How many slots the node is behind the cluster, if it said so
+    pub fn slots_behind(&self) -> Option<u64> {
+        match self {
+            Self::Behind(slots) => Some(*slots),
+            _ => None,
+        }
+    }
+}
+
+/// Request options gated by the version of the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RpcOption {
+    /// `maxSupportedTransactionVersion`, required to receive versioned transactions
+    MaxSupportedTransactionVersion,
+    /// `minContextSlot`
+    MinContextSlot,
+}
+
+impl RpcOption {
+    /// The name of the option in the request configuration object
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::MaxSupportedTransactionVersion => "maxSupportedTransactionVersion",
+            Self::MinContextSlot => "minContextSlot",
+        }
+    }
+
+    /// The first `solana-core` version supporting the option
+    pub fn introduced_in(&self) -> CoreVersion {
+        match self {
+            Self::MaxSupportedTransactionVersion => CoreVersion::new(1, 11, 0),
+            Self::MinContextSlot => CoreVersion::new(1, 11, 0),
+        }
+    }
+
+    /// The option named by a key of the request configuration object
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "maxSupportedTransactionVersion" => Some(Self::MaxSupportedTransactionVersion),
+            "minContextSlot" => Some(Self::MinContextSlot),
+            _ => None,
+        }
+    }
+}
+
+impl RpcMethod {
+    /// The first `solana-core` version serving the method, `None` if all
+    /// versions supported by this library serve it
+    pub fn introduced_in(&self) -> Option<CoreVersion> {
+        match self {
+            Self::GetBlock | Self::GetTransaction => Some(CoreVersion::new(1, 7, 0)),
+            Self::GetLatestBlockhash | Self::IsBlockhashValid => Some(CoreVersion::new(1, 9, 0)),
+            Self::GetStakeMinimumDelegation => Some(CoreVersion::new(1, 13, 0)),
+            Self::GetHighestSnapshotSlot => Some(CoreVersion::new(1, 9, 0)),
+            _ => None,
+        }
+    }
+
+    /// The `solana-core` version that no longer serves the method
+    pub fn removed_in(&self) -> Option<CoreVersion> {
+        match self {
+            Self::GetConfirmedBlock | Self::GetConfirmedTransaction | Self::GetStakeActivation => {
+                Some(CoreVersion::new(2, 0, 0))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What a node reported about itself through `getVersion` and `getHealth`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct NodeCapabilities {
+    pub version: NodeVersion,
+    pub core_version: CoreVersion,
+    pub health: NodeHealth,
+}
+
+impl NodeCapabilities {
+    /// Call `getVersion` and `getHealth` on the node of the client, see [RpcRequest::template]
+    pub async fn fetch(client: &RpcRequest<Unconfigured>) -> AtollResult<Self> {
+        let version = client
+            .template()
+            .add_typed(GetVersionRequest)
+            .request()
+            .await?
+            .into_result()?;
+
+        NodeCapabilities::new(version, NodeHealth::fetch(client).await?)
+    }
+
+    pub(crate) fn new(version: NodeVersion, health: NodeHealth) -> AtollResult<Self> {
+        Ok(NodeCapabilities {
+            core_version: version.solana_core.parse()?,
+            version,
+            health,
+        })
+    }
+
+    /// Check the method and the options of the request configuration object
+    pub fn check_request(&self, method: &RpcMethod, options: &[RpcOption]) -> AtollResult<()> {
+        self.check_method(method)?;
+        options
+            .iter()
+            .try_for_each(|option| self.check_option(*option))
+    }
+
+    /// Check that the node serves the method
+    pub fn check_method(&self, method: &RpcMethod) -> AtollResult<()> {
+        if let Some(introduced_in) = method.introduced_in() {
+            if self.core_version < introduced_in {
+                return Err(AtollError::UnsupportedByNode(format!(
+                    "`{}` requires solana-core {introduced_in} or newer but the node runs {}",
+                    method.to_upper_camel_case(),
+                    self.core_version
+                )));
+            }
+        }
+
+        if let Some(removed_in) = method.removed_in() {
+            if self.core_version >= removed_in {
+                return Err(AtollError::UnsupportedByNode(format!(
+                    "`{}` was removed in solana-core {removed_in} and the node runs {}",
+                    method.to_upper_camel_case(),
+                    self.core_version
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the node understands the request option
+    pub fn check_option(&self, option: RpcOption) -> AtollResult<()> {
+        let introduced_in = option.introduced_in();
+
+        if self.core_version < introduced_in {
+            return Err(AtollError::UnsupportedByNode(format!(
+                "`{}` requires solana-core {introduced_in} or newer but the node runs {}",
+                option.as_str(),
+                self.core_version
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn supports_method(&self, method: &RpcMethod) -> bool {
+        self.check_method(method).is_ok()
+    }
+
+    pub fn supports_option(&self, option: RpcOption) -> bool {
+        self.check_option(option).is_ok()
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.health == NodeHealth::Healthy
+    }
+}
+
+/// Caches the capabilities of each node so they are negotiated once on first use.
+/// Share it between requests with [crate::RpcRequest::add_capabilities] to reject
+/// methods and options the node does not support before they are sent
+#[derive(Debug, Default)]
+pub struct CapabilityCache {
+    nodes: Mutex<HashMap<String, NodeCapabilities>>,
+}
+
+impl CapabilityCache {
+    pub fn new() -> Self {
+        CapabilityCache::default()
+    }
+
+    /// The cached capabilities of the node, negotiating them on first use
+    pub async fn get_or_fetch(
+        &self,
+        client: &RpcRequest<Unconfigured>,
+    ) -> AtollResult<NodeCapabilities> {
+        if let Some(capabilities) = self.get(client.cluster()) {
+            return Ok(capabilities);
+        }
+
+        self.refresh(client).await
+    }
+
+    /// Negotiate the capabilities of the node again, for example after it was upgraded
+    pub async fn refresh(
+        &self,
+        client: &RpcRequest<Unconfigured>,
+    ) -> AtollResult<NodeCapabilities> {
+        let capabilities = NodeCapabilities::fetch(client).await?;
+        self.insert(client.cluster(), capabilities.clone());
+
+        Ok(capabilities)
+    }
+
+    pub fn get(&self, cluster: &Cluster) -> Option<NodeCapabilities> {
+        self.lock().get(cluster.url()).cloned()
+    }
+
+    pub fn invalidate(&self, cluster: &Cluster) {
+        self.lock().remove(cluster.url());
+    }
+
+    /// Check the method and options against the node before sending a request
+    pub async fn check(
+        &self,
+        client: &RpcRequest<Unconfigured>,
+        method: &RpcMethod,
+        options: &[RpcOption],
+    ) -> AtollResult<()> {
+        self.get_or_fetch(client)
+            .await?
+            .check_request(method, options)
+    }
+
+    pub(crate) fn insert(&self, cluster: &Cluster, capabilities: NodeCapabilities) {
+        self.lock().insert(cluster.url().to_owned(), capabilities);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, NodeCapabilities>> {
+        self.nodes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
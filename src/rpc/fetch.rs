@@ -0,0 +1,50 @@
+use crate::{
+    AtollError, AtollResult, HttpTransport, TransportFuture, TransportRequest, TransportResponse,
+};
+use std::sync::Arc;
+
+/// Sends requests with the `fetch` API of the browser or edge worker.
+/// `fetch` cannot block, so only the async `request` methods work with it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FetchTransport;
+
+impl HttpTransport for FetchTransport {
+    fn post_blocking(&self, _request: &TransportRequest) -> AtollResult<TransportResponse> {
+        Err(AtollError::Transport(
+            "`fetch` cannot block, use the async `request` methods on wasm".to_owned(),
+        ))
+    }
+
+    fn post(self: Arc<Self>, request: TransportRequest) -> TransportFuture {
+        Box::pin(async move {
+            let builder = request.headers.iter().fold(
+                gloo_net::http::Request::post(&request.url),
+                |builder, (key, value)| builder.header(key, value),
+            );
+
+            let response = builder
+                .body(request.body)
+                .map_err(Self::error)?
+                .send()
+                .await
+                .map_err(Self::error)?;
+
+            Ok(TransportResponse {
+                status_code: response.status(),
+                reason_phrase: response.status_text(),
+                headers: response
+                    .headers()
+                    .entries()
+                    .map(|(key, value)| (key.to_lowercase(), value))
+                    .collect(),
+                body: response.binary().await.map_err(Self::error)?,
+            })
+        })
+    }
+}
+
+impl FetchTransport {
+    fn error(error: gloo_net::Error) -> AtollError {
+        AtollError::Transport(error.to_string())
+    }
+}
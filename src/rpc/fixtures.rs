@@ -0,0 +1,348 @@
+use crate::{
+    AtollError, AtollResult, HttpTransport, TransportFuture, TransportRequest, TransportResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// How a [FixtureTransport] uses the fixture directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FixtureMode {
+    /// Send every request and write its response to the fixture directory
+    Record,
+    /// Answer every request from the fixture directory without touching the network
+    Replay,
+    /// Answer from the fixture directory and send and record the requests without a fixture
+    #[default]
+    ReplayOrRecord,
+}
+
+/// Records real responses to JSON files and replays them later, so tests can
+/// parse real payloads without depending on a live cluster.
+/// Fixtures are keyed by the method and parameters of the request, request ids
+/// are not part of the key and replayed responses carry the ids of the new request.
+/// Responses whose body is not JSON are passed through but never recorded, and so
+/// are HTTP error responses like 429 or 5xx and JSON-RPC errors, of the response or
+/// of any entry of a batch, unless [FixtureTransport::change_record_errors] is set
+#[derive(Debug)]
+pub struct FixtureTransport {
+    directory: PathBuf,
+    mode: FixtureMode,
+    record_errors: bool,
+    inner: Arc<dyn HttpTransport>,
+}
+
+/// A recorded response as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Fixture {
+    /// The request without its ids, kept to make the file readable
+    request: Value,
+    status_code: u16,
+    headers: HashMap<String, String>,
+    /// The response with the ids of the request replaced by their position in it
+    body: Value,
+}
+
+impl FixtureTransport {
+    /// Keep fixtures in the directory and send requests without a fixture through `inner`
+    pub fn new(directory: impl AsRef<Path>, inner: Arc<dyn HttpTransport>) -> Self {
+        FixtureTransport {
+            directory: directory.as_ref().to_path_buf(),
+            mode: FixtureMode::default(),
+            record_errors: false,
+            inner,
+        }
+    }
+
+    pub fn change_mode(mut self, mode: FixtureMode) -> Self {
+        self.mode = mode;
+
+        self
+    }
+
+    /// Also record responses with an HTTP status outside of `2xx` or a JSON-RPC
+    /// error, for tests of rate limiting or node failures
+    pub fn change_record_errors(mut self, record_errors: bool) -> Self {
+        self.record_errors = record_errors;
+
+        self
+    }
+
+    /// The file holding the fixture of the request
+    pub fn fixture_path(&self, request: &TransportRequest) -> AtollResult<PathBuf> {
+        let (entries, _) = Self::entries(request)?;
+
+        let name = match entries.as_slice() {
+            [entry] => entry["method"].as_str().unwrap_or("unknown").to_owned(),
+            _ => "batch".to_owned(),
+        };
+        let key = fnv1a(Value::Array(entries).to_string().as_bytes());
+
+        Ok(self.directory.join(format!("{name}-{key:016x}.json")))
+    }
+
+    fn replay(&self, request: &TransportRequest) -> AtollResult<Option<TransportResponse>> {
+        let path = self.fixture_path(request)?;
+
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(io_error) if io_error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(io_error) => {
+                return Err(AtollError::Io(format!("{}: {}", path.display(), io_error)))
+            }
+        };
+
+        let fixture = serde_json::from_slice::<Fixture>(&bytes)?;
+        let (_, ids) = Self::entries(request)?;
+        let mut body = fixture.body;
+        Self::map_ids(&mut body, |position| {
+            position
+                .as_u64()
+                .and_then(|position| ids.get(position as usize))
+                .cloned()
+        });
+
+        Ok(Some(TransportResponse {
+            status_code: fixture.status_code,
+            reason_phrase: String::default(),
+            headers: fixture.headers,
+            body: body.to_string().into_bytes(),
+        }))
+    }
+
+    fn record(&self, request: &TransportRequest, response: &TransportResponse) -> AtollResult<()> {
+        if !self.record_errors && !(200..300).contains(&response.status_code) {
+            return Ok(());
+        }
+
+        let Ok(mut body) = serde_json::from_slice::<Value>(&response.body) else {
+            return Ok(());
+        };
+
+        if !self.record_errors && Self::has_rpc_error(&body) {
+            return Ok(());
+        }
+
+        let (entries, ids) = Self::entries(request)?;
+        Self::map_ids(&mut body, |id| {
+            ids.iter()
+                .position(|request_id| request_id == id)
+                .map(Value::from)
+        });
+
+        let fixture = Fixture {
+            request: match entries.len() {
+                1 => entries[0].clone(),
+                _ => Value::Array(entries),
+            },
+            status_code: response.status_code,
+            headers: response.headers.clone(),
+            body,
+        };
+
+        fs::create_dir_all(&self.directory)
+            .map_err(|error| AtollError::Io(format!("{}: {}", self.directory.display(), error)))?;
+
+        let path = self.fixture_path(request)?;
+        fs::write(&path, serde_json::to_vec_pretty(&fixture)?)
+            .map_err(|error| AtollError::Io(format!("{}: {}", path.display(), error)))
+    }
+
+    /// Whether the response or an entry of the batch is a JSON-RPC error
+    fn has_rpc_error(body: &Value) -> bool {
+        match body {
+            Value::Array(responses) => responses
+                .iter()
+                .any(|response| response.get("error").is_some()),
+            response => response.get("error").is_some(),
+        }
+    }
+
+    /// The request objects without their ids, and the ids in order
+    fn entries(request: &TransportRequest) -> AtollResult<(Vec<Value>, Vec<Value>)> {
+        let entries = match serde_json::from_str::<Value>(&request.body)? {
+            Value::Array(entries) => entries,
+            entry => vec![entry],
+        };
+
+        Ok(entries
+            .into_iter()
+            .map(|mut entry| {
+                let id = entry
+                    .as_object_mut()
+                    .and_then(|entry| entry.remove("id"))
+                    .unwrap_or_default();
+
+                (entry, id)
+            })
+            .unzip())
+    }
+
+    /// Replace the id of every response object, leaving ids without a mapping untouched
+    fn map_ids(body: &mut Value, map: impl Fn(&Value) -> Option<Value>) {
+        let responses = match body {
+            Value::Array(responses) => responses.iter_mut().collect::<Vec<_>>(),
+            response => vec![response],
+        };
+
+        for id in responses
+            .into_iter()
+            .filter_map(|response| response.get_mut("id"))
+        {
+            if let Some(mapped) = map(id) {
+                *id = mapped;
+            }
+        }
+    }
+}
+
+impl FixtureTransport {
+    /// The recorded response, `None` if the request has to be sent
+    fn lookup(&self, request: &TransportRequest) -> AtollResult<Option<TransportResponse>> {
+        if self.mode == FixtureMode::Record {
+            return Ok(None);
+        }
+
+        match self.replay(request)? {
+            None if self.mode == FixtureMode::Replay => Err(AtollError::NotFound(format!(
+                "No fixture recorded at `{}`",
+                self.fixture_path(request)?.display()
+            ))),
+            replayed => Ok(replayed),
+        }
+    }
+}
+
+impl HttpTransport for FixtureTransport {
+    fn post_blocking(&self, request: &TransportRequest) -> AtollResult<TransportResponse> {
+        if let Some(response) = self.lookup(request)? {
+            return Ok(response);
+        }
+
+        let response = self.inner.post_blocking(request)?;
+        self.record(request, &response)?;
+
+        Ok(response)
+    }
+
+    fn post(self: Arc<Self>, request: TransportRequest) -> TransportFuture {
+        Box::pin(async move {
+            if let Some(response) = self.lookup(&request)? {
+                return Ok(response);
+            }
+
+            let response = self.inner.clone().post(request.clone()).await?;
+            self.record(&request, &response)?;
+
+            Ok(response)
+        })
+    }
+}
+
+/// The 64 bit FNV-1a hash, stable across Rust versions unlike the std hashers
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockTransport, RpcHeaders};
+    use std::time::Duration;
+
+    /// A fresh fixture directory for the test
+    fn directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("atoll-fixtures-{}-{name}", std::process::id()));
+        fs::remove_dir_all(&directory).ok();
+
+        directory
+    }
+
+    fn request(body: &str) -> TransportRequest {
+        TransportRequest {
+            url: "http://localhost:8899".to_owned(),
+            headers: RpcHeaders::default(),
+            body: body.to_owned(),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    fn mock() -> Arc<MockTransport> {
+        Arc::new(
+            MockTransport::new()
+                .add_result("getSlot", 1234.into())
+                .add_rpc_error("getBalance", -32602, "Invalid param")
+                .add_http_response("getEpochInfo", TransportResponse::new(503, "{}")),
+        )
+    }
+
+    const REQUESTS: [&str; 4] = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"getSlot"}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"getBalance","params":["x"]}"#,
+        r#"{"jsonrpc":"2.0","id":3,"method":"getEpochInfo"}"#,
+        r#"[{"jsonrpc":"2.0","id":4,"method":"getSlot"},{"jsonrpc":"2.0","id":5,"method":"getBalance","params":["x"]}]"#,
+    ];
+
+    #[test]
+    fn records_only_successful_responses() {
+        let directory = directory("successful");
+        let inner = mock();
+        let transport = FixtureTransport::new(&directory, inner.clone());
+
+        for body in REQUESTS {
+            transport.post_blocking(&request(body)).unwrap();
+        }
+
+        assert!(transport.replay(&request(REQUESTS[0])).unwrap().is_some());
+        for body in &REQUESTS[1..] {
+            assert!(
+                transport.replay(&request(body)).unwrap().is_none(),
+                "{body}"
+            );
+        }
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+
+        // The recorded response is replayed with the id of the new request
+        let replayed = transport
+            .post_blocking(&request(r#"{"jsonrpc":"2.0","id":9,"method":"getSlot"}"#))
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&replayed.body).unwrap()["id"],
+            9
+        );
+        assert_eq!(inner.methods().len(), 5);
+
+        fs::remove_dir_all(&directory).ok();
+    }
+
+    #[test]
+    fn records_errors_when_opted_in() {
+        let directory = directory("errors");
+        let transport = FixtureTransport::new(&directory, mock()).change_record_errors(true);
+
+        for body in REQUESTS {
+            transport.post_blocking(&request(body)).unwrap();
+        }
+
+        for body in REQUESTS {
+            assert!(
+                transport.replay(&request(body)).unwrap().is_some(),
+                "{body}"
+            );
+        }
+        let replayed = transport.replay(&request(REQUESTS[2])).unwrap().unwrap();
+        assert_eq!(replayed.status_code, 503);
+
+        fs::remove_dir_all(&directory).ok();
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{AtollError, AtollResult, Cluster};
+use std::{collections::HashMap, sync::Mutex};
+
+/// Checks that an endpoint serves the cluster it is selected for by comparing its
+/// `getGenesisHash` with the expected hash, catching mainnet code pointed at a
+/// devnet endpoint. The hash is fetched on the first request to each endpoint and
+/// remembered. Endpoints without an expected hash, like a local validator, are not
+/// checked unless one is added with [GenesisVerifier::add_expected].
+/// Share it between requests with [crate::RpcRequest::add_genesis_verifier]
+#[derive(Debug, Default)]
+pub struct GenesisVerifier {
+    expected: HashMap<String, String>,
+    received: Mutex<HashMap<String, String>>,
+}
+
+impl GenesisVerifier {
+    /// A verifier expecting the genesis hashes of the public clusters
+    pub fn new() -> Self {
+        GenesisVerifier::default()
+    }
+
+    /// Expect the base58 encoded genesis hash from the endpoint of the cluster,
+    /// for example the public cluster a custom endpoint belongs to
+    pub fn add_expected(mut self, cluster: &Cluster, genesis_hash: &str) -> Self {
+        self.expected
+            .insert(cluster.url().to_owned(), genesis_hash.to_owned());
+
+        self
+    }
+
+    /// The genesis hash expected from the endpoint of the cluster
+    pub fn expected(&self, cluster: &Cluster) -> Option<&str> {
+        self.expected
+            .get(cluster.url())
+            .map(String::as_str)
+            .or_else(|| cluster.genesis_hash())
+    }
+
+    /// Whether the endpoint of the cluster still has to be asked for its genesis hash
+    pub(crate) fn is_pending(&self, cluster: &Cluster) -> bool {
+        self.expected(cluster).is_some()
+            && !self.received.lock().unwrap().contains_key(cluster.url())
+    }
+
+    /// Remember the genesis hash the endpoint answered with
+    pub(crate) fn insert(&self, cluster: &Cluster, genesis_hash: String) {
+        self.received
+            .lock()
+            .unwrap()
+            .insert(cluster.url().to_owned(), genesis_hash);
+    }
+
+    /// Fail with [AtollError::WrongCluster] if the endpoint answered with another
+    /// genesis hash than expected
+    pub(crate) fn verify(&self, cluster: &Cluster) -> AtollResult<()> {
+        let Some(expected) = self.expected(cluster) else {
+            return Ok(());
+        };
+
+        match self.received.lock().unwrap().get(cluster.url()) {
+            Some(received) if received != expected => Err(AtollError::WrongCluster {
+                url: crate::redact_url(cluster.url()),
+                expected: expected.to_owned(),
+                received: received.to_owned(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Forget the genesis hashes received so every endpoint is checked again
+    pub fn clear(&self) {
+        self.received.lock().unwrap().clear();
+    }
+}
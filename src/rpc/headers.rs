@@ -0,0 +1,62 @@
+/// HTTP headers sent with every request they are added to, for example the
+/// `Authorization` header or API key header of a private RPC provider.
+/// Build it once and share it between requests with [crate::RpcRequest::change_headers]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RpcHeaders {
+    headers: Vec<(String, String)>,
+}
+
+impl RpcHeaders {
+    pub fn new() -> Self {
+        RpcHeaders::default()
+    }
+
+    /// Add a header, replacing an earlier header with the same case-insensitive name
+    pub fn add(mut self, key: &str, value: &str) -> Self {
+        self.insert(key, value);
+
+        self
+    }
+
+    /// Add an `Authorization: Bearer <token>` header
+    pub fn add_bearer_token(self, token: &str) -> Self {
+        self.add("Authorization", &format!("Bearer {token}"))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    pub(crate) fn insert(&mut self, key: &str, value: &str) {
+        self.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(key));
+        self.headers.push((key.to_owned(), value.to_owned()));
+    }
+
+    /// Apply the headers to a request
+    #[cfg(feature = "minreq")]
+    pub(crate) fn apply(&self, mut request: minreq::Request) -> minreq::Request {
+        for (key, value) in &self.headers {
+            request = request.with_header(key, value);
+        }
+
+        request
+    }
+}
@@ -0,0 +1,285 @@
+use crate::{
+    runtime::Instant, AtollResult, HttpTransport, TransportFuture, TransportRequest,
+    TransportResponse,
+};
+use core::fmt;
+use std::{sync::Arc, time::Duration};
+
+/// The default number of bytes of a body kept in a [RequestLog]
+pub const DEFAULT_MAX_LOGGED_BODY: usize = 1024;
+
+/// What replaces a redacted secret
+const REDACTED: &str = "<redacted>";
+
+/// Headers whose values are always redacted, compared case-insensitively
+const SECRET_HEADERS: [&str; 6] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+    "api-key",
+    "x-token",
+];
+
+/// Path segments at least this long are treated as API keys, as used by providers
+/// that put the key in the path like `https://example.rpc/<key>/`
+const SECRET_SEGMENT_LEN: usize = 20;
+
+/// Receives a [RequestLog] for every request sent through a [LoggingTransport]
+pub trait RequestLogSink: fmt::Debug + Send + Sync {
+    fn log(&self, entry: &RequestLog);
+}
+
+/// A request and its outcome with secrets redacted and bodies truncated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestLog {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub request_body: String,
+    /// The HTTP status, `None` if no response was received
+    pub status_code: Option<u16>,
+    pub response_body: Option<String>,
+    /// The error returned instead of a response
+    pub error: Option<String>,
+    pub latency: Duration,
+}
+
+impl fmt::Display for RequestLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "POST {} {}", self.url, self.request_body)?;
+
+        match (self.status_code, &self.response_body, &self.error) {
+            (Some(status_code), Some(body), _) => write!(f, " -> {status_code} {body}")?,
+            (_, _, Some(error)) => write!(f, " -> {error}")?,
+            _ => (),
+        }
+
+        write!(f, " ({}ms)", self.latency.as_millis())
+    }
+}
+
+/// An opt-in logger of requests and responses that wraps another transport.
+/// API keys in URLs and secret headers are redacted and bodies are truncated before
+/// they reach the sink, which can forward them to `tracing`, `log` or anything else
+#[derive(Debug)]
+pub struct LoggingTransport {
+    inner: Arc<dyn HttpTransport>,
+    sink: Arc<dyn RequestLogSink>,
+    max_body: usize,
+    secret_headers: Vec<String>,
+}
+
+impl LoggingTransport {
+    pub fn new(inner: Arc<dyn HttpTransport>, sink: Arc<dyn RequestLogSink>) -> Self {
+        LoggingTransport {
+            inner,
+            sink,
+            max_body: DEFAULT_MAX_LOGGED_BODY,
+            secret_headers: SECRET_HEADERS.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    /// Keep at most `max_body` bytes of every body
+    pub fn change_max_body(mut self, max_body: usize) -> Self {
+        self.max_body = max_body;
+
+        self
+    }
+
+    /// Redact the value of another header, like the custom key header of a provider
+    pub fn add_secret_header(mut self, name: &str) -> Self {
+        self.secret_headers.push(name.to_lowercase());
+
+        self
+    }
+
+    fn log(
+        &self,
+        request: &TransportRequest,
+        outcome: &AtollResult<TransportResponse>,
+        latency: Duration,
+    ) {
+        let (status_code, response_body, error) = match outcome {
+            Ok(response) => (
+                Some(response.status_code),
+                Some(truncate(
+                    &String::from_utf8_lossy(&response.body),
+                    self.max_body,
+                )),
+                None,
+            ),
+            Err(error) => (None, None, Some(redact_urls_in(&format!("{error:?}")))),
+        };
+
+        self.sink.log(&RequestLog {
+            url: redact_url(&request.url),
+            headers: request
+                .headers
+                .iter()
+                .map(|(key, value)| {
+                    let value = if self.secret_headers.contains(&key.to_lowercase()) {
+                        REDACTED
+                    } else {
+                        value
+                    };
+
+                    (key.to_owned(), value.to_owned())
+                })
+                .collect(),
+            request_body: truncate(&request.body, self.max_body),
+            status_code,
+            response_body,
+            error,
+            latency,
+        });
+    }
+}
+
+impl HttpTransport for LoggingTransport {
+    fn post_blocking(&self, request: &TransportRequest) -> AtollResult<TransportResponse> {
+        let started = Instant::now();
+        let outcome = self.inner.post_blocking(request);
+        self.log(request, &outcome, started.elapsed());
+
+        outcome
+    }
+
+    fn post(self: Arc<Self>, request: TransportRequest) -> TransportFuture {
+        Box::pin(async move {
+            let started = Instant::now();
+            let outcome = self.inner.clone().post(request.clone()).await;
+            self.log(&request, &outcome, started.elapsed());
+
+            outcome
+        })
+    }
+}
+
+/// Writes every [RequestLog] as a `tracing` debug event
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TracingLogSink;
+
+#[cfg(feature = "tracing")]
+impl RequestLogSink for TracingLogSink {
+    fn log(&self, entry: &RequestLog) {
+        tracing::debug!(
+            url = entry.url.as_str(),
+            status = entry.status_code,
+            latency_ms = entry.latency.as_millis() as u64,
+            request = entry.request_body.as_str(),
+            response = entry.response_body.as_deref(),
+            error = entry.error.as_deref(),
+            "RPC request"
+        );
+    }
+}
+
+/// Writes every [RequestLog] as a `log` debug record
+#[cfg(feature = "log")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LogCrateSink;
+
+#[cfg(feature = "log")]
+impl RequestLogSink for LogCrateSink {
+    fn log(&self, entry: &RequestLog) {
+        log::debug!(target: "atoll", "{entry}");
+    }
+}
+
+/// The URL with its credentials, query values and key-like path segments redacted
+pub fn redact_url(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+    let (authority, path) = match rest.split_once('/') {
+        Some((authority, path)) => (authority, Some(path)),
+        None => (rest, None),
+    };
+
+    let mut redacted = String::new();
+
+    if !scheme.is_empty() {
+        redacted.push_str(scheme);
+        redacted.push_str("://");
+    }
+
+    match authority.rsplit_once('@') {
+        Some((_, host)) => {
+            redacted.push_str(REDACTED);
+            redacted.push('@');
+            redacted.push_str(host);
+        }
+        None => redacted.push_str(authority),
+    }
+
+    if let Some(path) = path {
+        let segments = path
+            .split('/')
+            .map(|segment| {
+                if segment.len() >= SECRET_SEGMENT_LEN {
+                    REDACTED
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<&str>>();
+
+        redacted.push('/');
+        redacted.push_str(&segments.join("/"));
+    }
+
+    if let Some(query) = query {
+        let pairs = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, _)) => format!("{key}={REDACTED}"),
+                None => pair.to_owned(),
+            })
+            .collect::<Vec<String>>();
+
+        redacted.push('?');
+        redacted.push_str(&pairs.join("&"));
+    }
+
+    redacted
+}
+
+/// The text with every URL it mentions redacted, since transport errors often
+/// embed the URL they failed on
+fn redact_urls_in(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("://") {
+        let scheme_start = rest[..start]
+            .rfind(|c: char| !c.is_ascii_alphanumeric() && c != '+' && c != '-' && c != '.')
+            .map_or(0, |index| index + 1);
+        let end = rest[start..]
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | ')' | '>' | ','))
+            .map_or(rest.len(), |index| start + index);
+
+        redacted.push_str(&rest[..scheme_start]);
+        redacted.push_str(&redact_url(&rest[scheme_start..end]));
+        rest = &rest[end..];
+    }
+    redacted.push_str(rest);
+
+    redacted
+}
+
+/// At most `max` bytes of the text, cut on a character boundary
+fn truncate(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_owned();
+    }
+
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("{}... ({} bytes)", &text[..end], text.len())
+}
@@ -0,0 +1,109 @@
+use crate::{ErrorClass, RpcMethod};
+use core::fmt;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::Duration,
+};
+
+/// The upper bounds in milliseconds of the latency buckets of [RpcMetrics]
+pub const LATENCY_BUCKETS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Receives an [AttemptMetrics] for every attempt of every request it is added to
+/// with [crate::RpcRequest::add_metrics]. Implement it to forward the measurements
+/// to a metrics backend or use [RpcMetrics] to aggregate them in memory
+pub trait MetricsSink: fmt::Debug + Send + Sync {
+    fn record(&self, attempt: &AttemptMetrics);
+}
+
+/// The measurements of a single attempt of a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttemptMetrics {
+    pub method: RpcMethod,
+    /// The attempt counted from `1`, including rate limited attempts
+    pub attempt: u32,
+    /// The time from sending the request until the response or error
+    pub latency: Duration,
+    /// The HTTP status of the response, `None` if no response was received
+    pub status_code: Option<u16>,
+    /// The class of the failure, `None` for successful responses that are not JSON-RPC errors
+    pub error: Option<ErrorClass>,
+    /// The size of the response body
+    pub bytes_received: usize,
+}
+
+/// Aggregates the attempts of requests into counters and latency histograms per method
+#[derive(Debug, Default)]
+pub struct RpcMetrics {
+    methods: Mutex<HashMap<RpcMethod, MethodMetrics>>,
+}
+
+/// The aggregated measurements of a method
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MethodMetrics {
+    /// The number of attempts sent, retries included
+    pub requests: u64,
+    /// The number of failed attempts by class
+    pub failures: BTreeMap<ErrorClass, u64>,
+    /// The number of attempts per bucket of [LATENCY_BUCKETS_MS].
+    /// The last entry counts the attempts slower than the largest bucket
+    pub latency_histogram: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    pub latency_total: Duration,
+    pub bytes_received: u64,
+}
+
+impl MethodMetrics {
+    /// The number of failed attempts of every class
+    pub fn failed(&self) -> u64 {
+        self.failures.values().sum()
+    }
+
+    pub fn average_latency(&self) -> Option<Duration> {
+        u32::try_from(self.requests)
+            .ok()
+            .filter(|requests| *requests > 0)
+            .map(|requests| self.latency_total / requests)
+    }
+}
+
+impl RpcMetrics {
+    pub fn new() -> Self {
+        RpcMetrics::default()
+    }
+
+    /// The measurements of the method, `None` if it was never sent
+    pub fn get(&self, method: &RpcMethod) -> Option<MethodMetrics> {
+        self.methods.lock().unwrap().get(method).cloned()
+    }
+
+    /// The measurements of every method sent so far
+    pub fn snapshot(&self) -> HashMap<RpcMethod, MethodMetrics> {
+        self.methods.lock().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        self.methods.lock().unwrap().clear();
+    }
+}
+
+impl MetricsSink for RpcMetrics {
+    fn record(&self, attempt: &AttemptMetrics) {
+        let mut methods = self.methods.lock().unwrap();
+        let metrics = methods.entry(attempt.method).or_default();
+
+        let latency_ms = attempt.latency.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+
+        metrics.requests += 1;
+        metrics.latency_histogram[bucket] += 1;
+        metrics.latency_total += attempt.latency;
+        metrics.bytes_received += attempt.bytes_received as u64;
+
+        if let Some(class) = attempt.error {
+            *metrics.failures.entry(class).or_default() += 1;
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::HttpResponse;
+use core::fmt;
+use json::JsonValue;
+
+/// Hooks called on every request a middleware is added to with
+/// [crate::RpcRequest::add_middleware], for custom logging, metrics,
+/// signing requests for private gateways or rewriting responses
+pub trait RpcMiddleware: fmt::Debug + Send + Sync {
+    /// Inspect or modify the JSON-RPC request object before it is sent.
+    /// Called for every attempt, including retries
+    fn before_send(&self, _request: &mut JsonValue) {}
+
+    /// Inspect or modify the response before its `result` is deserialized
+    /// into the response type of the request
+    fn after_receive(&self, _response: &mut HttpResponse<serde_json::Value>) {}
+}
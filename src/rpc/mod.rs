@@ -12,3 +12,65 @@ pub use block::*;
 
 mod tx_error;
 pub use tx_error::*;
+
+mod router;
+pub use router::*;
+
+mod capabilities;
+pub use capabilities::*;
+
+mod scheduler;
+pub use scheduler::*;
+
+mod cancellation;
+pub use cancellation::*;
+
+mod batch;
+pub use batch::*;
+
+mod retry;
+pub use retry::*;
+
+mod headers;
+pub use headers::*;
+
+mod middleware;
+pub use middleware::*;
+
+mod cache;
+pub use cache::*;
+
+mod genesis;
+pub use genesis::*;
+
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
+
+mod transport;
+pub use transport::*;
+
+mod fixtures;
+pub use fixtures::*;
+
+mod typed;
+pub use typed::*;
+
+#[cfg(feature = "wasm")]
+mod fetch;
+#[cfg(feature = "wasm")]
+pub use fetch::*;
+
+#[cfg(feature = "reqwest")]
+mod reqwest_transport;
+#[cfg(feature = "reqwest")]
+pub use reqwest_transport::*;
+
+#[cfg(feature = "ureq")]
+mod ureq_transport;
+#[cfg(feature = "ureq")]
+pub use ureq_transport::*;
+
+mod logging;
+pub use logging::*;
@@ -0,0 +1,69 @@
+use crate::{
+    AtollError, AtollResult, HttpTransport, TransportFuture, TransportRequest, TransportResponse,
+};
+use std::sync::Arc;
+use web3utilities::{IoErrorKind, UtilitiesError};
+
+/// Sends requests with a `reqwest` client, sharing its connection pool and TLS settings.
+/// `reqwest` runs on tokio and cannot block, so only the async `request` methods work with it
+#[derive(Debug, Default, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Send requests with an existing client
+    pub fn new(client: reqwest::Client) -> Self {
+        ReqwestTransport { client }
+    }
+
+    fn error(error: reqwest::Error) -> AtollError {
+        if error.is_timeout() {
+            AtollError::Utilities(UtilitiesError::Io(IoErrorKind::TimedOut))
+        } else if error.is_connect() {
+            AtollError::Utilities(UtilitiesError::Io(IoErrorKind::ConnectionRefused))
+        } else {
+            AtollError::Transport(error.to_string())
+        }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn post_blocking(&self, _request: &TransportRequest) -> AtollResult<TransportResponse> {
+        Err(AtollError::Transport(
+            "`reqwest` cannot block, use the async `request` methods".to_owned(),
+        ))
+    }
+
+    fn post(self: Arc<Self>, request: TransportRequest) -> TransportFuture {
+        Box::pin(async move {
+            let response = request
+                .headers
+                .iter()
+                .fold(
+                    self.client.post(&request.url).timeout(request.timeout),
+                    |builder, (key, value)| builder.header(key, value),
+                )
+                .body(request.body)
+                .send()
+                .await
+                .map_err(Self::error)?;
+
+            let status = response.status();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((key.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+                })
+                .collect();
+
+            Ok(TransportResponse {
+                status_code: status.as_u16(),
+                reason_phrase: status.canonical_reason().unwrap_or_default().to_owned(),
+                headers,
+                body: response.bytes().await.map_err(Self::error)?.to_vec(),
+            })
+        })
+    }
+}
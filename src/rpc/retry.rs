@@ -0,0 +1,232 @@
+use crate::{
+    runtime::{SystemTime, UNIX_EPOCH},
+    AtollError, Minreq, TransportResponse,
+};
+use std::{collections::BTreeSet, time::Duration};
+use web3utilities::{IoErrorKind, UtilitiesError};
+
+/// The default number of times a request is sent before its error is returned
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// The default delay before the first retry, doubled for every following retry
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(250);
+/// The default upper bound of the delay between two attempts
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(10);
+/// The default total time spent waiting on HTTP 429 responses before giving up
+pub const DEFAULT_RATE_LIMIT_BUDGET: Duration = Duration::from_secs(30);
+
+/// The kind of failure a request ran into, used to decide whether it is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorClass {
+    /// The connection could not be established, like [Minreq::AddressNotFound]
+    Connection,
+    /// The request did not complete in time
+    Timeout,
+    /// The node answered with a 5xx HTTP status
+    ServerError,
+    /// The node answered with HTTP 429 Too Many Requests
+    RateLimited,
+    /// The node answered with a JSON-RPC error
+    Rpc,
+    /// Any other failure, like a response that could not be deserialized
+    Other,
+}
+
+impl ErrorClass {
+    /// The class of an error returned while sending a request
+    pub fn of(error: &AtollError) -> Self {
+        match error {
+            AtollError::Http(Minreq::AddressNotFound)
+            | AtollError::Http(Minreq::RustlsCreateConnection(_))
+            | AtollError::Http(Minreq::ProxyConnect) => ErrorClass::Connection,
+            AtollError::Utilities(UtilitiesError::Io(kind)) => match kind {
+                IoErrorKind::TimedOut | IoErrorKind::WouldBlock => ErrorClass::Timeout,
+                IoErrorKind::ConnectionRefused
+                | IoErrorKind::ConnectionReset
+                | IoErrorKind::ConnectionAborted
+                | IoErrorKind::NotConnected
+                | IoErrorKind::BrokenPipe
+                | IoErrorKind::Interrupted => ErrorClass::Connection,
+                _ => ErrorClass::Other,
+            },
+            AtollError::RateLimited { .. } => ErrorClass::RateLimited,
+            AtollError::Rpc { .. } => ErrorClass::Rpc,
+            _ => ErrorClass::Other,
+        }
+    }
+
+    /// The class of a response by its HTTP status, `None` for statuses that are not failures
+    pub fn of_status(status_code: u16) -> Option<Self> {
+        match status_code {
+            429 => Some(ErrorClass::RateLimited),
+            500..=599 => Some(ErrorClass::ServerError),
+            _ => None,
+        }
+    }
+
+    /// The class of a response, [ErrorClass::Rpc] for a successful HTTP status
+    /// whose body is a JSON-RPC error
+    pub fn of_response(response: &TransportResponse) -> Option<Self> {
+        /// Only looks for the `error` member, skipping the rest of the body
+        #[derive(serde::Deserialize)]
+        struct ErrorMember {
+            error: Option<serde::de::IgnoredAny>,
+        }
+
+        ErrorClass::of_status(response.status_code).or_else(|| {
+            serde_json::from_slice::<ErrorMember>(response.as_bytes())
+                .ok()
+                .and_then(|body| body.error)
+                .map(|_| ErrorClass::Rpc)
+        })
+    }
+}
+
+/// Decides how often and after how long a failed request is sent again.
+/// The delay grows exponentially from the base delay up to the maximum delay,
+/// and a random share of it given by the jitter is subtracted so that many
+/// clients failing at once do not retry in lockstep
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    retryable: BTreeSet<ErrorClass>,
+    rate_limit_budget: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new()
+    }
+}
+
+impl RetryPolicy {
+    /// Retry connection failures, timeouts and 5xx responses up to [DEFAULT_MAX_ATTEMPTS] times
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            jitter: 0.5,
+            retryable: [
+                ErrorClass::Connection,
+                ErrorClass::Timeout,
+                ErrorClass::ServerError,
+            ]
+            .into_iter()
+            .collect(),
+            rate_limit_budget: DEFAULT_RATE_LIMIT_BUDGET,
+        }
+    }
+
+    /// Send every request exactly once
+    pub fn never() -> Self {
+        RetryPolicy::new()
+            .change_max_attempts(1)
+            .change_rate_limit_budget(Duration::ZERO)
+    }
+
+    /// The total number of attempts including the first one
+    pub fn change_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+
+        self
+    }
+
+    pub fn change_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+
+        self
+    }
+
+    pub fn change_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+
+        self
+    }
+
+    /// The share of each delay from `0.0` to `1.0` that is randomized
+    pub fn change_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+
+        self
+    }
+
+    /// Retry failures of this class
+    pub fn add_retryable(mut self, class: ErrorClass) -> Self {
+        self.retryable.insert(class);
+
+        self
+    }
+
+    /// Return failures of this class right away
+    pub fn remove_retryable(mut self, class: ErrorClass) -> Self {
+        self.retryable.remove(&class);
+
+        self
+    }
+
+    /// The total time to wait on HTTP 429 responses, honoring their `Retry-After` header,
+    /// before failing with [AtollError::RateLimited]. Rate limited attempts are not
+    /// counted against the maximum attempts
+    pub fn change_rate_limit_budget(mut self, rate_limit_budget: Duration) -> Self {
+        self.rate_limit_budget = rate_limit_budget;
+
+        self
+    }
+
+    pub fn rate_limit_budget(&self) -> Duration {
+        self.rate_limit_budget
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn is_retryable(&self, class: ErrorClass) -> bool {
+        self.retryable.contains(&class)
+    }
+
+    /// Whether a failure of this class in the given attempt, counted from `1`, is retried
+    pub fn should_retry(&self, class: ErrorClass, attempt: u32) -> bool {
+        attempt < self.max_attempts && self.is_retryable(class)
+    }
+
+    /// The delay before the attempt after `attempt`, counted from `1`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+
+        delay.mul_f64(1.0 - self.jitter * random_fraction())
+    }
+}
+
+/// The delay requested by the `Retry-After` header of a response, in seconds.
+/// HTTP dates are not supported and yield `None`
+pub fn retry_after(response: &TransportResponse) -> Option<Duration> {
+    response
+        .headers
+        .get("retry-after")
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// A fraction from `0.0` to `1.0` that is random enough to spread out retries
+fn random_fraction() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+
+    // xorshift to spread nearby timestamps
+    let mut value = nanos as u64 | 1;
+    value ^= value << 13;
+    value ^= value >> 7;
+    value ^= value << 17;
+
+    (value % 10_000) as f64 / 10_000.0
+}
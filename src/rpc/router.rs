@@ -0,0 +1,538 @@
+use crate::{
+    runtime::{self, Instant},
+    AtollResult, Cluster, ErrorClass, HttpResponse, NodeHealth, ParameterlessMethod, RpcRequest,
+    Unconfigured,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::fmt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// The weight given to the newest observation when updating the moving averages
+const SMOOTHING: f64 = 0.2;
+
+/// The default time between two refreshes of an [EndpointRouter]
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The default number of slots an endpoint may fall behind before it is skipped
+pub const DEFAULT_MAX_SLOT_LAG: u64 = 50;
+
+/// How an [EndpointRouter] picks the endpoint of the next request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoutingMode {
+    /// Spread the requests over the eligible endpoints in proportion to their score,
+    /// so that every endpoint keeps receiving a share of the traffic and its score
+    /// stays current
+    #[default]
+    Weighted,
+    /// Send every request to the eligible endpoint with the best score
+    Healthiest,
+}
+
+/// Routes requests across several endpoints by a score computed from the observed
+/// latency, error rate and slot freshness of each endpoint, either in proportion
+/// to the score or always to the best endpoint, see [RoutingMode]. An endpoint is eligible
+/// if it reports itself healthy with `getHealth`, answered the last refresh and is
+/// at most `max_slot_lag` slots behind the most up to date endpoint.
+/// Refreshes run when the last one is older than the check interval, or
+/// continuously by running [EndpointRouter::monitor] in the background
+#[derive(Debug)]
+pub struct EndpointRouter {
+    endpoints: Mutex<Vec<EndpointScore>>,
+    mode: RoutingMode,
+    check_interval: Duration,
+    max_slot_lag: u64,
+    last_refresh: Mutex<Option<Instant>>,
+    refreshing: AtomicBool,
+}
+
+#[derive(Debug)]
+struct EndpointScore {
+    cluster: Cluster,
+    /// The configured request the health checks and routed requests are sent with
+    endpoint: RpcRequest<Unconfigured>,
+    health: Option<NodeHealth>,
+    reachable: bool,
+    latency_ms: Option<f64>,
+    error_rate: f64,
+    slot: Option<u64>,
+    current_weight: f64,
+}
+
+#[derive(Debug)]
+struct HealthCheck {
+    health: Option<NodeHealth>,
+    latency: Duration,
+    slot: Option<u64>,
+}
+
+/// Clears the refreshing flag when the refresh finishes or is dropped
+struct RefreshGuard<'a>(&'a AtomicBool);
+
+impl Drop for RefreshGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl EndpointRouter {
+    /// Create a router for the endpoints, all eligible with the same score until
+    /// the first refresh
+    pub fn new(endpoints: Vec<Cluster>) -> Self {
+        EndpointRouter::with_requests(
+            endpoints
+                .into_iter()
+                .map(|cluster| RpcRequest::new().change_cluster(cluster))
+                .collect(),
+        )
+    }
+
+    /// Create a router for the endpoints of the configured requests. The health checks
+    /// of an endpoint and the requests routed to it are sent through its transport
+    /// and with its headers, for example the API key of a private RPC provider
+    pub fn with_requests(endpoints: Vec<RpcRequest<Unconfigured>>) -> Self {
+        EndpointRouter {
+            endpoints: Mutex::new(
+                endpoints
+                    .into_iter()
+                    .map(|endpoint| EndpointScore {
+                        cluster: endpoint.cluster().clone(),
+                        endpoint,
+                        health: None,
+                        reachable: true,
+                        latency_ms: None,
+                        error_rate: 0.0,
+                        slot: None,
+                        current_weight: 0.0,
+                    })
+                    .collect(),
+            ),
+            mode: RoutingMode::default(),
+            check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+            max_slot_lag: DEFAULT_MAX_SLOT_LAG,
+            last_refresh: Mutex::new(None),
+            refreshing: AtomicBool::new(false),
+        }
+    }
+
+    pub fn change_mode(mut self, mode: RoutingMode) -> Self {
+        self.mode = mode;
+
+        self
+    }
+
+    pub fn change_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+
+        self
+    }
+
+    pub fn change_max_slot_lag(mut self, max_slot_lag: u64) -> Self {
+        self.max_slot_lag = max_slot_lag;
+
+        self
+    }
+
+    /// Select the endpoint for the next request among the eligible endpoints, or
+    /// among every endpoint when none is eligible. [RoutingMode::Weighted] uses
+    /// smooth weighted round-robin, [RoutingMode::Healthiest] the highest weight
+    pub fn select(&self) -> Option<Cluster> {
+        self.select_with(|endpoint| endpoint.cluster.clone())
+    }
+
+    fn select_with<T>(&self, selected: impl FnOnce(&EndpointScore) -> T) -> Option<T> {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let weights = self.weights(&endpoints);
+
+        if self.mode == RoutingMode::Healthiest {
+            // The first of several equally good endpoints is kept
+            return endpoints
+                .iter()
+                .zip(weights.iter())
+                .filter(|(_, weight)| **weight > 0.0)
+                .reduce(|best, next| if next.1 > best.1 { next } else { best })
+                .map(|(endpoint, _)| selected(endpoint));
+        }
+
+        let total = weights.iter().sum::<f64>();
+
+        endpoints
+            .iter_mut()
+            .zip(weights.iter())
+            .for_each(|(endpoint, weight)| endpoint.current_weight += weight);
+
+        let endpoint = endpoints
+            .iter_mut()
+            .zip(weights.iter())
+            .filter(|(_, weight)| **weight > 0.0)
+            .map(|(endpoint, _)| endpoint)
+            .max_by(|a, b| a.current_weight.total_cmp(&b.current_weight))?;
+        endpoint.current_weight -= total;
+
+        Some(selected(endpoint))
+    }
+
+    /// Record a request that received a response
+    pub fn record_success(&self, cluster: &Cluster, latency: Duration) {
+        self.update(cluster, |endpoint| {
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+            endpoint.latency_ms = Some(match endpoint.latency_ms {
+                Some(average) => average + SMOOTHING * (latency_ms - average),
+                None => latency_ms,
+            });
+            endpoint.error_rate -= SMOOTHING * endpoint.error_rate;
+        })
+    }
+
+    /// Record a request that failed without a response
+    pub fn record_failure(&self, cluster: &Cluster) {
+        self.update(cluster, |endpoint| {
+            endpoint.error_rate += SMOOTHING * (1.0 - endpoint.error_rate);
+        })
+    }
+
+    /// Record the latest slot seen from an endpoint
+    pub fn record_slot(&self, cluster: &Cluster, slot: u64) {
+        self.update(cluster, |endpoint| {
+            endpoint.slot = Some(endpoint.slot.unwrap_or_default().max(slot))
+        })
+    }
+
+    /// Check every endpoint concurrently with `getHealth` and `getSlot` to update
+    /// its health, latency and slot freshness. Only one refresh runs at a time,
+    /// callers arriving while one is in flight return at once and keep routing on
+    /// the current scores
+    pub async fn refresh(&self) {
+        if self
+            .refreshing
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+        let _guard = RefreshGuard(&self.refreshing);
+
+        let tasks = self
+            .endpoints
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|endpoint| {
+                let task = runtime::spawn(Self::check_endpoint(endpoint.endpoint.template()));
+
+                (endpoint.cluster.clone(), task)
+            })
+            .collect::<Vec<_>>();
+
+        for (cluster, task) in tasks {
+            let check = task.await;
+            let reachable = check.health.is_some() && check.slot.is_some();
+
+            match check.slot.filter(|_| reachable) {
+                Some(slot) => {
+                    self.record_success(&cluster, check.latency);
+                    self.record_slot(&cluster, slot);
+                }
+                None => self.record_failure(&cluster),
+            }
+            self.update(&cluster, |endpoint| {
+                endpoint.reachable = reachable;
+                endpoint.health = check.health;
+            });
+        }
+
+        *self.last_refresh.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Refresh the endpoints every check interval, forever
+    pub async fn monitor(&self) {
+        loop {
+            self.refresh().await;
+            runtime::sleep(self.check_interval).await;
+        }
+    }
+
+    /// Send a request to the selected endpoint, through its transport and with its
+    /// headers, and record the outcome, refreshing the endpoints first if the last
+    /// refresh is stale. An endpoint that fails to answer is skipped until it passes
+    /// the next refresh
+    pub async fn request<T: fmt::Debug + DeserializeOwned>(
+        &self,
+        request: RpcRequest,
+    ) -> AtollResult<HttpResponse<T>> {
+        if self.is_stale() {
+            self.refresh().await;
+        }
+
+        let Some(endpoint) = self.select_with(|endpoint| endpoint.endpoint.template()) else {
+            return request.request().await;
+        };
+
+        let cluster = endpoint.cluster().clone();
+        let started = Instant::now();
+        let outcome = request.change_endpoint(&endpoint).request::<T>().await;
+
+        match outcome.as_ref() {
+            Ok(_) => self.record_success(&cluster, started.elapsed()),
+            Err(error) => {
+                self.record_failure(&cluster);
+
+                if matches!(
+                    ErrorClass::of(error),
+                    ErrorClass::Connection | ErrorClass::Timeout | ErrorClass::ServerError
+                ) {
+                    self.update(&cluster, |endpoint| endpoint.reachable = false);
+                }
+            }
+        }
+
+        outcome
+    }
+
+    /// The current score of each endpoint
+    pub fn scores(&self) -> Vec<EndpointWeight> {
+        let endpoints = self.endpoints.lock().unwrap();
+        let weights = self.weights(&endpoints);
+        let highest_slot = Self::highest_slot(&endpoints);
+
+        endpoints
+            .iter()
+            .zip(weights)
+            .map(|(endpoint, weight)| EndpointWeight {
+                url: endpoint.cluster.url().to_owned(),
+                weight,
+                eligible: self.is_eligible(endpoint, highest_slot),
+                health: endpoint.health.clone(),
+                latency_ms: endpoint.latency_ms,
+                error_rate: endpoint.error_rate,
+                slot: endpoint.slot,
+                slot_lag: endpoint
+                    .slot
+                    .zip(highest_slot)
+                    .map(|(slot, highest)| highest.saturating_sub(slot)),
+            })
+            .collect()
+    }
+
+    async fn check_endpoint(endpoint: RpcRequest<Unconfigured>) -> HealthCheck {
+        let started = Instant::now();
+        let health = NodeHealth::fetch(&endpoint).await.ok();
+        let latency = started.elapsed();
+
+        let slot = endpoint
+            .add_parameterless_method(ParameterlessMethod::GetSlot)
+            .request::<u64>()
+            .await
+            .and_then(HttpResponse::into_result)
+            .ok();
+
+        HealthCheck {
+            health,
+            latency,
+            slot,
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        self.last_refresh
+            .lock()
+            .unwrap()
+            .map(|last_refresh| last_refresh.elapsed() >= self.check_interval)
+            .unwrap_or(true)
+    }
+
+    fn is_eligible(&self, endpoint: &EndpointScore, highest_slot: Option<u64>) -> bool {
+        let healthy = matches!(endpoint.health, None | Some(NodeHealth::Healthy));
+        let in_sync = endpoint
+            .slot
+            .zip(highest_slot)
+            .map(|(slot, highest)| highest.saturating_sub(slot) <= self.max_slot_lag)
+            .unwrap_or(true);
+
+        endpoint.reachable && healthy && in_sync
+    }
+
+    fn highest_slot(endpoints: &[EndpointScore]) -> Option<u64> {
+        endpoints
+            .iter()
+            .filter(|endpoint| endpoint.reachable)
+            .filter_map(|endpoint| endpoint.slot)
+            .max()
+    }
+
+    /// The weight of each endpoint, zero for the ineligible ones unless no
+    /// endpoint is eligible
+    fn weights(&self, endpoints: &[EndpointScore]) -> Vec<f64> {
+        let highest_slot = Self::highest_slot(endpoints);
+        let any_eligible = endpoints
+            .iter()
+            .any(|endpoint| self.is_eligible(endpoint, highest_slot));
+
+        endpoints
+            .iter()
+            .map(|endpoint| {
+                if any_eligible && !self.is_eligible(endpoint, highest_slot) {
+                    return 0.0;
+                }
+
+                // Unmeasured endpoints get an optimistic latency so that they are tried
+                let latency = 1000.0 / endpoint.latency_ms.unwrap_or(50.0).max(1.0);
+                let slot_lag = endpoint
+                    .slot
+                    .zip(highest_slot)
+                    .map(|(slot, highest)| highest.saturating_sub(slot))
+                    .unwrap_or_default();
+                let freshness = 1.0 / (1.0 + slot_lag as f64);
+                let reliability = (1.0 - endpoint.error_rate).max(0.01);
+
+                latency * freshness * reliability
+            })
+            .collect()
+    }
+
+    fn update(&self, cluster: &Cluster, update: impl FnOnce(&mut EndpointScore)) {
+        if let Some(endpoint) = self
+            .endpoints
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|endpoint| &endpoint.cluster == cluster)
+        {
+            update(endpoint)
+        }
+    }
+}
+
+/// The routing score of an endpoint
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointWeight {
+    pub url: String,
+    /// The relative share of requests routed to the endpoint
+    pub weight: f64,
+    /// Whether the endpoint is healthy, reachable and within the slot lag
+    pub eligible: bool,
+    /// The last `getHealth` answer, `None` if the endpoint did not answer or was
+    /// not refreshed yet
+    pub health: Option<NodeHealth>,
+    /// The moving average of the latency in milliseconds
+    pub latency_ms: Option<f64>,
+    /// The moving average of failed requests from `0.0` to `1.0`
+    pub error_rate: f64,
+    pub slot: Option<u64>,
+    /// The number of slots behind the most up to date endpoint
+    pub slot_lag: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints() -> Vec<Cluster> {
+        ["http://a.example", "http://b.example", "http://c.example"]
+            .into_iter()
+            .map(|url| Cluster::custom(url).unwrap())
+            .collect()
+    }
+
+    fn record(router: &EndpointRouter, latencies_ms: [u64; 3], slots: [u64; 3]) {
+        endpoints()
+            .iter()
+            .zip(latencies_ms.into_iter().zip(slots))
+            .for_each(|(cluster, (latency_ms, slot))| {
+                router.record_success(cluster, Duration::from_millis(latency_ms));
+                router.record_slot(cluster, slot);
+            });
+    }
+
+    #[test]
+    fn healthiest_always_selects_the_best_endpoint() {
+        let router = EndpointRouter::new(endpoints()).change_mode(RoutingMode::Healthiest);
+        record(&router, [80, 10, 40], [1000, 1000, 1000]);
+
+        let best = &endpoints()[1];
+        assert!((0..20).all(|_| router.select().as_ref() == Some(best)));
+    }
+
+    #[test]
+    fn healthiest_skips_endpoints_behind_the_slot_lag() {
+        let router = EndpointRouter::new(endpoints()).change_mode(RoutingMode::Healthiest);
+        record(&router, [80, 10, 40], [1000, 900, 1000]);
+
+        let best = &endpoints()[2];
+        assert!((0..20).all(|_| router.select().as_ref() == Some(best)));
+    }
+
+    #[test]
+    fn healthiest_skips_failing_endpoints() {
+        let router = EndpointRouter::new(endpoints()).change_mode(RoutingMode::Healthiest);
+        record(&router, [80, 10, 40], [1000, 1000, 1000]);
+        (0..10).for_each(|_| router.record_failure(&endpoints()[1]));
+
+        let best = &endpoints()[2];
+        assert!((0..20).all(|_| router.select().as_ref() == Some(best)));
+    }
+
+    #[cfg(all(feature = "rt-smol", not(any(feature = "rt-tokio", feature = "wasm"))))]
+    #[test]
+    fn health_checks_and_requests_use_the_endpoint_configuration() {
+        use crate::MockTransport;
+        use std::sync::Arc;
+
+        let transport = Arc::new(
+            MockTransport::new()
+                .add_result("getHealth", "ok".into())
+                .add_result("getSlot", 1000.into())
+                .add_result("getBlockHeight", 900.into()),
+        );
+        let endpoint = RpcRequest::new()
+            .change_cluster(endpoints().remove(0))
+            .change_transport(transport.clone())
+            .add_header("x-api-key", "secret");
+        let router = EndpointRouter::with_requests(vec![endpoint]);
+
+        let height = futures_lite::future::block_on(router.request::<u64>(
+            RpcRequest::new().add_parameterless_method(ParameterlessMethod::GetBlockHeight),
+        ))
+        .and_then(HttpResponse::into_result);
+
+        assert_eq!(height, Ok(900));
+        assert_eq!(
+            transport.methods(),
+            ["getHealth", "getSlot", "getBlockHeight"]
+        );
+        assert!(transport
+            .requests()
+            .iter()
+            .all(|request| request.url == "http://a.example"
+                && request.headers.get("x-api-key") == Some("secret")));
+
+        let scores = router.scores();
+        assert!(scores[0].eligible);
+        assert_eq!(scores[0].health, Some(NodeHealth::Healthy));
+        assert_eq!(scores[0].slot, Some(1000));
+    }
+
+    #[test]
+    fn weighted_spreads_requests_over_eligible_endpoints() {
+        let router = EndpointRouter::new(endpoints());
+        record(&router, [80, 10, 40], [1000, 1000, 1000]);
+
+        let selected = (0..100)
+            .filter_map(|_| router.select())
+            .collect::<Vec<Cluster>>();
+        let count = |cluster: &Cluster| selected.iter().filter(|s| *s == cluster).count();
+
+        assert!(endpoints().iter().all(|cluster| count(cluster) > 0));
+        assert!(count(&endpoints()[1]) > count(&endpoints()[2]));
+        assert!(count(&endpoints()[2]) > count(&endpoints()[0]));
+    }
+}
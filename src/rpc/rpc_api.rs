@@ -1,10 +1,17 @@
+use crate::{TokenAmount, TransactionError};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::Deserialize;
 
 type Base58String = String;
 type Base64String = String;
 type Encoding = String;
 
-#[derive(Debug, Deserialize)]
+/// The length of the first warmup epoch
+const MINIMUM_SLOTS_PER_EPOCH: u64 = 32;
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
 #[serde(rename_all = "camelCase")]
 pub struct GetAccountInfo {
     pub data: (Option<Base64String>, Encoding),
@@ -13,3 +20,431 @@ pub struct GetAccountInfo {
     pub owner: Base58String,
     pub rent_epoch: u64,
 }
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyedAccount {
+    pub pubkey: Base58String,
+    pub account: GetAccountInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterNode {
+    pub pubkey: Base58String,
+    pub gossip: Option<String>,
+    pub tpu: Option<String>,
+    pub rpc: Option<String>,
+    pub version: Option<String>,
+    pub feature_set: Option<u32>,
+    pub shred_version: Option<u16>,
+}
+
+/// The result of `simulateTransaction`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    /// The error the transaction would fail with, `None` if it would succeed
+    pub err: Option<TransactionError>,
+    /// The program logs, `None` if the transaction failed before it was executed
+    pub logs: Option<Vec<String>>,
+    pub units_consumed: Option<u64>,
+    pub return_data: Option<SimulationReturnData>,
+    /// The state of the requested accounts after the simulation, in request order
+    pub accounts: Option<Vec<Option<GetAccountInfo>>>,
+}
+
+/// The data the last program of a simulated transaction returned
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationReturnData {
+    pub program_id: Base58String,
+    pub data: (Base64String, Encoding),
+}
+
+/// A transaction signature of an address returned by `getSignaturesForAddress`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInfo {
+    pub signature: Base58String,
+    pub slot: u64,
+    /// The error the transaction failed with, `None` if it succeeded
+    pub err: Option<TransactionError>,
+    pub memo: Option<String>,
+    /// The estimated production time as a Unix timestamp, if known
+    pub block_time: Option<i64>,
+}
+
+/// The status of a transaction returned by `getSignatureStatuses`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureStatus {
+    /// The slot the transaction was processed in
+    pub slot: u64,
+    /// The number of blocks since confirmation, `None` once the transaction is finalized
+    pub confirmations: Option<u64>,
+    /// The error the transaction failed with, `None` if it succeeded
+    pub err: Option<TransactionError>,
+    pub confirmation_status: Option<ConfirmationStatus>,
+}
+
+impl SignatureStatus {
+    /// Whether the cluster reached the commitment for the transaction
+    pub fn is_at_least(&self, status: ConfirmationStatus) -> bool {
+        self.confirmation_status
+            .map(|confirmation_status| confirmation_status >= status)
+            .unwrap_or(false)
+    }
+}
+
+/// The commitment the cluster reached for a transaction, ordered from weakest to strongest
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfirmationStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+/// The result of `getLatestBlockhash`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestBlockhash {
+    pub blockhash: Base58String,
+    /// The last block height at which a transaction using the blockhash is accepted
+    pub last_valid_block_height: u64,
+}
+
+impl LatestBlockhash {
+    /// Whether a transaction using the blockhash is still accepted at the block height
+    pub fn is_valid_at(&self, block_height: u64) -> bool {
+        block_height <= self.last_valid_block_height
+    }
+}
+
+/// A token account of a mint returned by `getTokenLargestAccounts`
+#[derive(Debug, Clone, PartialEq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenLargestAccount {
+    pub address: Base58String,
+    #[serde(flatten)]
+    pub amount: TokenAmount,
+}
+
+/// The result of `getEpochInfo`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochInfo {
+    pub epoch: u64,
+    /// The slot relative to the start of the epoch
+    pub slot_index: u64,
+    pub slots_in_epoch: u64,
+    pub absolute_slot: u64,
+    pub block_height: u64,
+    /// The number of transactions processed without error since genesis, if known
+    pub transaction_count: Option<u64>,
+}
+
+impl EpochInfo {
+    /// The slots left until the next epoch starts
+    pub fn remaining_slots(&self) -> u64 {
+        self.slots_in_epoch.saturating_sub(self.slot_index)
+    }
+
+    /// The share of the epoch elapsed from `0.0` to `1.0`
+    pub fn progress(&self) -> f64 {
+        if self.slots_in_epoch == 0 {
+            return 0.0;
+        }
+
+        self.slot_index as f64 / self.slots_in_epoch as f64
+    }
+}
+
+/// The result of `getEpochSchedule`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochSchedule {
+    /// The number of slots of every epoch after warmup
+    pub slots_per_epoch: u64,
+    /// The number of slots before the start of an epoch at which its leader schedule is computed
+    pub leader_schedule_slot_offset: u64,
+    /// Whether the first epochs start short and double in length until `first_normal_epoch`
+    pub warmup: bool,
+    pub first_normal_epoch: u64,
+    pub first_normal_slot: u64,
+}
+
+impl EpochSchedule {
+    /// The epoch of the slot
+    pub fn epoch_of(&self, slot: u64) -> u64 {
+        if slot < self.first_normal_slot {
+            // Warmup epochs start at the minimum length and double every epoch
+            let epoch = slot
+                .saturating_add(MINIMUM_SLOTS_PER_EPOCH + 1)
+                .checked_next_power_of_two()
+                .map_or(u64::BITS, u64::trailing_zeros);
+
+            return epoch.saturating_sub(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros() + 1) as u64;
+        }
+
+        self.first_normal_epoch + (slot - self.first_normal_slot) / self.slots_per_epoch.max(1)
+    }
+
+    /// The first slot of the epoch
+    pub fn first_slot_of(&self, epoch: u64) -> u64 {
+        if epoch <= self.first_normal_epoch {
+            let epoch = u32::try_from(epoch).unwrap_or(u32::MAX);
+
+            return 2u64
+                .saturating_pow(epoch)
+                .saturating_sub(1)
+                .saturating_mul(MINIMUM_SLOTS_PER_EPOCH);
+        }
+
+        (epoch - self.first_normal_epoch)
+            .saturating_mul(self.slots_per_epoch)
+            .saturating_add(self.first_normal_slot)
+    }
+}
+
+/// The result of `getVoteAccounts`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteAccounts {
+    pub current: Vec<VoteAccount>,
+    /// Vote accounts that have not voted recently
+    pub delinquent: Vec<VoteAccount>,
+}
+
+/// A vote account of a validator
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteAccount {
+    pub vote_pubkey: Base58String,
+    /// The identity of the validator node
+    pub node_pubkey: Base58String,
+    /// The stake delegated to the vote account and active in this epoch in lamports
+    pub activated_stake: u64,
+    /// The share of rewards paid to the validator as a percentage
+    pub commission: u8,
+    /// Whether the vote account is staked for this epoch
+    pub epoch_vote_account: bool,
+    /// The credits earned by the end of each of the last epochs as
+    /// `(epoch, credits, previous_credits)`
+    pub epoch_credits: Vec<(u64, u64, u64)>,
+    /// The most recent slot voted on
+    pub last_vote: u64,
+    /// The current root slot of the vote account
+    pub root_slot: u64,
+}
+
+/// The result of `getSupply` in lamports
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct Supply {
+    pub total: u64,
+    pub circulating: u64,
+    pub non_circulating: u64,
+    /// The non-circulating accounts, empty if the request excluded them
+    pub non_circulating_accounts: Vec<Base58String>,
+}
+
+/// An account returned by `getLargestAccounts`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct LargestAccount {
+    pub address: Base58String,
+    pub lamports: u64,
+}
+
+/// The result of `getInflationGovernor`, rates are fractions like `0.08` for 8%
+#[derive(Debug, Clone, PartialEq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InflationGovernor {
+    /// The inflation rate at genesis
+    pub initial: f64,
+    /// The inflation rate once tapering ends
+    pub terminal: f64,
+    /// The yearly rate at which inflation is lowered
+    pub taper: f64,
+    /// The share of inflation paid to the foundation
+    pub foundation: f64,
+    /// How long the foundation receives inflation in years
+    pub foundation_term: f64,
+}
+
+/// The result of `getInflationRate` for the current epoch
+#[derive(Debug, Clone, PartialEq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InflationRate {
+    pub total: f64,
+    pub validator: f64,
+    pub foundation: f64,
+    pub epoch: u64,
+}
+
+/// The inflation reward of an address for an epoch, returned by `getInflationReward`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct InflationReward {
+    pub epoch: u64,
+    /// The slot the reward was credited in
+    pub effective_slot: u64,
+    /// The reward in lamports
+    pub amount: u64,
+    /// The balance of the account after the reward in lamports
+    pub post_balance: u64,
+    /// The commission of the vote account when the reward was credited
+    pub commission: Option<u8>,
+}
+
+/// The activation state of a stake account
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum StakeActivationState {
+    Activating,
+    Active,
+    Deactivating,
+    Inactive,
+}
+
+/// The result of `getStakeActivation`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct StakeActivation {
+    pub state: StakeActivationState,
+    /// The stake active during the epoch in lamports
+    pub active: u64,
+    /// The stake inactive during the epoch in lamports
+    pub inactive: u64,
+}
+
+/// The most samples `getRecentPerformanceSamples` returns, one per minute for 12 hours
+pub const MAX_PERFORMANCE_SAMPLES: usize = 720;
+
+/// A sample of `getRecentPerformanceSamples`, taken about every 60 seconds
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct PerfSample {
+    /// The slot the sample was taken at
+    pub slot: u64,
+    /// The transactions processed during the sample period, including votes
+    pub num_transactions: u64,
+    /// The non-vote transactions processed, `None` for nodes older than 1.15
+    pub num_non_vote_transactions: Option<u64>,
+    /// The slots completed during the sample period
+    pub num_slots: u64,
+    pub sample_period_secs: u16,
+}
+
+impl PerfSample {
+    /// The transactions per second over the sample period, including votes
+    pub fn tps(&self) -> f64 {
+        if self.sample_period_secs == 0 {
+            return 0.0;
+        }
+
+        self.num_transactions as f64 / self.sample_period_secs as f64
+    }
+
+    /// The non-vote transactions per second over the sample period
+    pub fn non_vote_tps(&self) -> Option<f64> {
+        if self.sample_period_secs == 0 {
+            return self.num_non_vote_transactions.map(|_| 0.0);
+        }
+
+        self.num_non_vote_transactions
+            .map(|transactions| transactions as f64 / self.sample_period_secs as f64)
+    }
+}
+
+/// The result of `getIdentity`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct NodeIdentity {
+    /// The base58 encoded identity of the node
+    pub identity: Base58String,
+}
+
+/// The result of `getHighestSnapshotSlot`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotSlot {
+    /// The slot of the highest full snapshot
+    pub full: u64,
+    /// The slot of the highest incremental snapshot based on `full`
+    pub incremental: Option<u64>,
+}
+
+/// The most accounts `getRecentPrioritizationFees` accepts, the most accounts a
+/// transaction can lock
+pub const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+
+/// An entry of `getRecentPrioritizationFees`, the lowest compute unit price in
+/// micro-lamports a transaction locking all the requested accounts paid in the slot
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Deserialize, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct PrioritizationFee {
+    pub slot: u64,
+    pub prioritization_fee: u64,
+}
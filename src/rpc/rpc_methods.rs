@@ -1,19 +1,77 @@
-use crate::{AtollError, AtollResult, HttpResponse, RequestOutcome, RpcJsonError, RpcResponse};
+use crate::{
+    AtollError, AtollResult, HttpResponse, RequestOutcome, RpcJsonError, RpcResponse,
+    TransportResponse,
+};
 use core::fmt;
+use json::JsonValue;
 use serde::de::DeserializeOwned;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RpcMethod {
     GetAccountInfo,
     GetBalance,
     GetBlock,
     GetBlockHeight,
+    SimulateTransaction,
+    SendTransaction,
+    RequestAirdrop,
+    GetSignatureStatuses,
+    GetSlot,
+    GetInflationRate,
+    GetVoteAccounts,
+    GetSupply,
+    GetProgramAccounts,
+    GetClusterNodes,
+    GetTransaction,
+    GetConfirmedBlock,
+    GetConfirmedTransaction,
+    GetVersion,
+    GetHealth,
+    GetGenesisHash,
+    GetEpochSchedule,
+    GetMinimumBalanceForRentExemption,
+    GetSignaturesForAddress,
+    GetLatestBlockhash,
+    IsBlockhashValid,
+    GetMultipleAccounts,
+    GetTokenAccountsByOwner,
+    GetTokenAccountsByDelegate,
+    GetTokenAccountBalance,
+    GetTokenSupply,
+    GetTokenLargestAccounts,
+    GetEpochInfo,
+    GetSlotLeader,
+    GetSlotLeaders,
+    GetBlocks,
+    GetBlocksWithLimit,
+    GetBlockTime,
+    GetLargestAccounts,
+    GetInflationGovernor,
+    GetInflationReward,
+    GetStakeActivation,
+    GetStakeMinimumDelegation,
+    GetRecentPerformanceSamples,
+    GetIdentity,
+    GetFirstAvailableBlock,
+    MinimumLedgerSlot,
+    GetHighestSnapshotSlot,
+    GetMaxRetransmitSlot,
+    GetMaxShredInsertSlot,
+    GetRecentPrioritizationFees,
 }
 
 impl RpcMethod {
     pub async fn parse<T: fmt::Debug + DeserializeOwned>(
         &self,
-        response: minreq::Response,
+        response: TransportResponse,
+    ) -> AtollResult<HttpResponse<T>> {
+        self.parse_blocking(response)
+    }
+
+    /// Parse the response on the current thread
+    pub fn parse_blocking<T: fmt::Debug + DeserializeOwned>(
+        &self,
+        response: TransportResponse,
     ) -> AtollResult<HttpResponse<T>> {
         let response_body = response.as_str()?;
 
@@ -30,6 +88,146 @@ impl RpcMethod {
             Self::GetBlockHeight => {
                 self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
             }
+            Self::SimulateTransaction => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::SendTransaction => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::RequestAirdrop => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetSignatureStatuses => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetSlot => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetInflationRate => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetVoteAccounts => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetSupply => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetProgramAccounts => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetClusterNodes => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetTransaction => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetConfirmedBlock => {
+                let response_body = self.normalize_deprecated(response_body);
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(&response_body)?)
+            }
+            Self::GetConfirmedTransaction => {
+                let response_body = self.normalize_deprecated(response_body);
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(&response_body)?)
+            }
+            Self::GetVersion => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetHealth => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetGenesisHash => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetEpochSchedule => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetMinimumBalanceForRentExemption => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetSignaturesForAddress => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetLatestBlockhash => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::IsBlockhashValid => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetMultipleAccounts => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetTokenAccountsByOwner => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetTokenAccountsByDelegate => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetTokenAccountBalance => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetTokenSupply => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetTokenLargestAccounts => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetEpochInfo => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetSlotLeader => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetSlotLeaders => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetBlocks => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetBlocksWithLimit => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetBlockTime => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetLargestAccounts => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetInflationGovernor => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetInflationReward => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetStakeActivation => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetStakeMinimumDelegation => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetRecentPerformanceSamples => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetIdentity => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetFirstAvailableBlock => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::MinimumLedgerSlot => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetHighestSnapshotSlot => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetMaxRetransmitSlot => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetMaxShredInsertSlot => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
+            Self::GetRecentPrioritizationFees => {
+                self.build_http_response::<T>(&response, self.is_ok_or::<T>(response_body)?)
+            }
         };
 
         Ok(http_response)
@@ -37,11 +235,11 @@ impl RpcMethod {
 
     fn build_http_response<T>(
         &self,
-        response: &minreq::Response,
+        response: &TransportResponse,
         body: RequestOutcome<T>,
     ) -> HttpResponse<T> {
         HttpResponse {
-            status_code: response.status_code as u16,
+            status_code: response.status_code,
             headers: response.headers.clone(),
             reason_phrase: response.reason_phrase.clone(),
             body,
@@ -64,12 +262,183 @@ impl RpcMethod {
         }
     }
 
+    /// The deprecated `getConfirmed*` method answered by older archival nodes
+    /// in place of this method, if any
+    pub fn deprecated_fallback(&self) -> Option<RpcMethod> {
+        match self {
+            Self::GetBlock => Some(Self::GetConfirmedBlock),
+            Self::GetTransaction => Some(Self::GetConfirmedTransaction),
+            _ => None,
+        }
+    }
+
+    /// Fill in the fields that the deprecated `getConfirmed*` methods omit so that
+    /// their responses deserialize into the same types as `getBlock` and `getTransaction`
+    fn normalize_deprecated(&self, response_body: &str) -> String {
+        let mut body = match json::parse(response_body) {
+            Ok(body) => body,
+            Err(_) => return response_body.to_owned(),
+        };
+
+        let result = &mut body["result"];
+
+        if result.is_object() {
+            match self {
+                Self::GetConfirmedBlock => {
+                    Self::default_to_array(result, &["rewards", "transactions"]);
+
+                    result["transactions"]
+                        .members_mut()
+                        .for_each(|transaction| Self::normalize_meta(&mut transaction["meta"]));
+                }
+                Self::GetConfirmedTransaction => Self::normalize_meta(&mut result["meta"]),
+                _ => (),
+            }
+        }
+
+        body.dump()
+    }
+
+    fn normalize_meta(meta: &mut JsonValue) {
+        if meta.is_object() {
+            Self::default_to_array(
+                meta,
+                &[
+                    "innerInstructions",
+                    "logMessages",
+                    "preTokenBalances",
+                    "postTokenBalances",
+                    "rewards",
+                ],
+            );
+        }
+    }
+
+    fn default_to_array(object: &mut JsonValue, keys: &[&str]) {
+        keys.iter().for_each(|key| {
+            if object[*key].is_null() {
+                object[*key] = JsonValue::new_array();
+            }
+        });
+    }
+
     pub fn to_upper_camel_case(&self) -> &str {
         match self {
             Self::GetAccountInfo => "getAccountInfo",
             Self::GetBalance => "getBalance",
             Self::GetBlock => "getBlock",
             Self::GetBlockHeight => "getBlockHeight",
+            Self::SimulateTransaction => "simulateTransaction",
+            Self::SendTransaction => "sendTransaction",
+            Self::RequestAirdrop => "requestAirdrop",
+            Self::GetSignatureStatuses => "getSignatureStatuses",
+            Self::GetSlot => "getSlot",
+            Self::GetInflationRate => "getInflationRate",
+            Self::GetVoteAccounts => "getVoteAccounts",
+            Self::GetSupply => "getSupply",
+            Self::GetProgramAccounts => "getProgramAccounts",
+            Self::GetClusterNodes => "getClusterNodes",
+            Self::GetTransaction => "getTransaction",
+            Self::GetConfirmedBlock => "getConfirmedBlock",
+            Self::GetConfirmedTransaction => "getConfirmedTransaction",
+            Self::GetVersion => "getVersion",
+            Self::GetHealth => "getHealth",
+            Self::GetGenesisHash => "getGenesisHash",
+            Self::GetEpochSchedule => "getEpochSchedule",
+            Self::GetMinimumBalanceForRentExemption => "getMinimumBalanceForRentExemption",
+            Self::GetSignaturesForAddress => "getSignaturesForAddress",
+            Self::GetLatestBlockhash => "getLatestBlockhash",
+            Self::IsBlockhashValid => "isBlockhashValid",
+            Self::GetMultipleAccounts => "getMultipleAccounts",
+            Self::GetTokenAccountsByOwner => "getTokenAccountsByOwner",
+            Self::GetTokenAccountsByDelegate => "getTokenAccountsByDelegate",
+            Self::GetTokenAccountBalance => "getTokenAccountBalance",
+            Self::GetTokenSupply => "getTokenSupply",
+            Self::GetTokenLargestAccounts => "getTokenLargestAccounts",
+            Self::GetEpochInfo => "getEpochInfo",
+            Self::GetSlotLeader => "getSlotLeader",
+            Self::GetSlotLeaders => "getSlotLeaders",
+            Self::GetBlocks => "getBlocks",
+            Self::GetBlocksWithLimit => "getBlocksWithLimit",
+            Self::GetBlockTime => "getBlockTime",
+            Self::GetLargestAccounts => "getLargestAccounts",
+            Self::GetInflationGovernor => "getInflationGovernor",
+            Self::GetInflationReward => "getInflationReward",
+            Self::GetStakeActivation => "getStakeActivation",
+            Self::GetStakeMinimumDelegation => "getStakeMinimumDelegation",
+            Self::GetRecentPerformanceSamples => "getRecentPerformanceSamples",
+            Self::GetIdentity => "getIdentity",
+            Self::GetFirstAvailableBlock => "getFirstAvailableBlock",
+            Self::MinimumLedgerSlot => "minimumLedgerSlot",
+            Self::GetHighestSnapshotSlot => "getHighestSnapshotSlot",
+            Self::GetMaxRetransmitSlot => "getMaxRetransmitSlot",
+            Self::GetMaxShredInsertSlot => "getMaxShredInsertSlot",
+            Self::GetRecentPrioritizationFees => "getRecentPrioritizationFees",
+        }
+    }
+}
+
+/// The methods whose parameters are all optional, the only ones that can be sent
+/// without a positional parameter, see [crate::RpcRequest::add_parameterless_method]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParameterlessMethod {
+    GetBlockHeight,
+    GetSlot,
+    GetInflationRate,
+    GetVoteAccounts,
+    GetSupply,
+    GetClusterNodes,
+    GetVersion,
+    GetHealth,
+    GetGenesisHash,
+    GetEpochSchedule,
+    GetLatestBlockhash,
+    GetEpochInfo,
+    GetSlotLeader,
+    GetLargestAccounts,
+    GetInflationGovernor,
+    GetStakeMinimumDelegation,
+    GetRecentPerformanceSamples,
+    GetIdentity,
+    GetFirstAvailableBlock,
+    MinimumLedgerSlot,
+    GetHighestSnapshotSlot,
+    GetMaxRetransmitSlot,
+    GetMaxShredInsertSlot,
+    GetRecentPrioritizationFees,
+}
+
+impl From<ParameterlessMethod> for RpcMethod {
+    fn from(method: ParameterlessMethod) -> Self {
+        match method {
+            ParameterlessMethod::GetBlockHeight => RpcMethod::GetBlockHeight,
+            ParameterlessMethod::GetSlot => RpcMethod::GetSlot,
+            ParameterlessMethod::GetInflationRate => RpcMethod::GetInflationRate,
+            ParameterlessMethod::GetVoteAccounts => RpcMethod::GetVoteAccounts,
+            ParameterlessMethod::GetSupply => RpcMethod::GetSupply,
+            ParameterlessMethod::GetClusterNodes => RpcMethod::GetClusterNodes,
+            ParameterlessMethod::GetVersion => RpcMethod::GetVersion,
+            ParameterlessMethod::GetHealth => RpcMethod::GetHealth,
+            ParameterlessMethod::GetGenesisHash => RpcMethod::GetGenesisHash,
+            ParameterlessMethod::GetEpochSchedule => RpcMethod::GetEpochSchedule,
+            ParameterlessMethod::GetLatestBlockhash => RpcMethod::GetLatestBlockhash,
+            ParameterlessMethod::GetEpochInfo => RpcMethod::GetEpochInfo,
+            ParameterlessMethod::GetSlotLeader => RpcMethod::GetSlotLeader,
+            ParameterlessMethod::GetLargestAccounts => RpcMethod::GetLargestAccounts,
+            ParameterlessMethod::GetInflationGovernor => RpcMethod::GetInflationGovernor,
+            ParameterlessMethod::GetStakeMinimumDelegation => RpcMethod::GetStakeMinimumDelegation,
+            ParameterlessMethod::GetRecentPerformanceSamples => {
+                RpcMethod::GetRecentPerformanceSamples
+            }
+            ParameterlessMethod::GetIdentity => RpcMethod::GetIdentity,
+            ParameterlessMethod::GetFirstAvailableBlock => RpcMethod::GetFirstAvailableBlock,
+            ParameterlessMethod::MinimumLedgerSlot => RpcMethod::MinimumLedgerSlot,
+            ParameterlessMethod::GetHighestSnapshotSlot => RpcMethod::GetHighestSnapshotSlot,
+            ParameterlessMethod::GetMaxRetransmitSlot => RpcMethod::GetMaxRetransmitSlot,
+            ParameterlessMethod::GetMaxShredInsertSlot => RpcMethod::GetMaxShredInsertSlot,
+            ParameterlessMethod::GetRecentPrioritizationFees => {
+                RpcMethod::GetRecentPrioritizationFees
+            }
         }
     }
 }
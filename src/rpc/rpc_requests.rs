@@ -1,48 +1,208 @@
-use crate::{AtollResult, RpcMethod};
+use crate::{
+    default_transport, retry_after,
+    runtime::{self, Instant},
+    AtollError, AtollResult, CacheKey, CachedResponse, CancellationToken, CapabilityCache,
+    ErrorClass, GenesisVerifier, HttpTransport, NodeCapabilities, NodeHealth, NodeVersion,
+    ParameterlessMethod, RequestPriority, ResponseCache, RetryPolicy, RpcHeaders, RpcMethod,
+    RpcMiddleware, RpcOption, TransportRequest, TransportResponse,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
-use core::fmt;
+use core::{fmt, future::Future};
 use json::JsonValue;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use smol::unblock;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use web3utilities::{IoErrorKind, UtilitiesError};
 
+/// The default time a single attempt of a request may take
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Counts the attempts of a request for its retry policy
+#[derive(Debug)]
+struct Attempts {
+    /// The current attempt counted from `1`, excluding rate limited attempts
+    attempt: u32,
+    rate_limited: u32,
+    rate_limit_waited: Duration,
+}
+
+impl Attempts {
+    fn new() -> Self {
+        Attempts {
+            attempt: 1,
+            rate_limited: 0,
+            rate_limit_waited: Duration::ZERO,
+        }
+    }
+}
+
+/// The request has no method yet
+#[derive(Debug)]
+pub struct Unconfigured;
+
+/// The method is selected and its parameters have to be provided
+#[derive(Debug)]
+pub struct AwaitingParams;
+
+/// The method and its required parameters are set and the request can be sent
 #[derive(Debug)]
-pub struct RpcRequest {
+pub struct Ready;
+
+/// A JSON-RPC request built in stages so that a request missing the parameters
+/// its method requires does not compile. Start with [RpcRequest::new] and either
+/// use a method specific transition like [RpcRequest::get_account_info] or
+/// select a method with [RpcRequest::add_method] followed by
+/// [RpcRequest::add_value], or with [RpcRequest::add_parameterless_method].
+/// Only a `RpcRequest<Ready>` can be sent
+#[derive(Debug)]
+pub struct RpcRequest<S = Ready> {
     jsonrpc: String,
-    id: u8,
+    id: u64,
     method: RpcMethod,
     value: Option<JsonValue>,
+    params: Vec<JsonValue>,
     cluster: Cluster,
     extras: Vec<(String, JsonValue)>,
+    deprecated_fallback: bool,
+    priority: RequestPriority,
+    cancellation: Option<CancellationToken>,
+    retry_policy: RetryPolicy,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    headers: RpcHeaders,
+    middlewares: Vec<Arc<dyn RpcMiddleware>>,
+    cache: Option<Arc<ResponseCache>>,
+    genesis: Option<Arc<GenesisVerifier>>,
+    capabilities: Option<Arc<CapabilityCache>>,
+    transport: Arc<dyn HttpTransport>,
+    #[cfg(feature = "metrics")]
+    metrics: Vec<Arc<dyn crate::MetricsSink>>,
+    state: PhantomData<S>,
 }
 
-impl Default for RpcRequest {
+impl Default for RpcRequest<Unconfigured> {
     fn default() -> Self {
         RpcRequest::new()
     }
 }
 
-impl RpcRequest {
+impl RpcRequest<Unconfigured> {
     pub fn new() -> Self {
         RpcRequest {
             jsonrpc: "2.0".to_string(),
-            id: 1,
+            id: next_request_id(),
             method: RpcMethod::GetAccountInfo,
             value: Option::None,
+            params: Vec::default(),
             cluster: Cluster::DevNet,
             extras: Vec::default(),
+            deprecated_fallback: false,
+            priority: RequestPriority::default(),
+            cancellation: Option::None,
+            retry_policy: RetryPolicy::default(),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            deadline: Option::None,
+            headers: RpcHeaders::default(),
+            middlewares: Vec::default(),
+            cache: Option::None,
+            genesis: Option::None,
+            capabilities: Option::None,
+            transport: default_transport(),
+            #[cfg(feature = "metrics")]
+            metrics: Vec::default(),
+            state: PhantomData,
         }
     }
 
-    pub fn change_jsonrpc(mut self, jsonrpc: &str) -> Self {
-        self.jsonrpc = jsonrpc.to_owned();
+    /// Select the method, its parameters are provided next
+    pub fn add_method(mut self, method: RpcMethod) -> RpcRequest<AwaitingParams> {
+        self.method = method;
 
-        self
+        self.into_state()
+    }
+
+    /// Select a method like `getSlot` whose parameters are all optional, ready to be sent
+    pub fn add_parameterless_method(self, method: ParameterlessMethod) -> RpcRequest<Ready> {
+        self.add_method(method.into()).without_value()
+    }
+
+    /// `getAccountInfo` for the base58 encoded address
+    pub fn get_account_info(self, address: &str) -> RpcRequest<Ready> {
+        self.add_method(RpcMethod::GetAccountInfo)
+            .add_value(address.into())
+    }
+
+    /// `getBalance` for the base58 encoded address
+    pub fn get_balance(self, address: &str) -> RpcRequest<Ready> {
+        self.add_method(RpcMethod::GetBalance)
+            .add_value(address.into())
     }
 
-    pub fn add_value(mut self, value: JsonValue) -> Self {
+    /// `getBlock` for the slot
+    pub fn get_block(self, slot: u64) -> RpcRequest<Ready> {
+        self.add_method(RpcMethod::GetBlock).add_value(slot.into())
+    }
+
+    /// `getTransaction` for the base58 encoded signature
+    pub fn get_transaction(self, signature: &str) -> RpcRequest<Ready> {
+        self.add_method(RpcMethod::GetTransaction)
+            .add_value(signature.into())
+    }
+}
+
+impl RpcRequest<AwaitingParams> {
+    /// The first positional parameter of the method
+    pub fn add_value(mut self, value: JsonValue) -> RpcRequest<Ready> {
         self.value = Some(value);
 
+        self.into_state()
+    }
+
+    /// Send the method without a positional parameter, which only [TypedRequest]s
+    /// and [RpcRequest::add_parameterless_method] know to be valid
+    pub(crate) fn without_value(self) -> RpcRequest<Ready> {
+        self.into_state()
+    }
+}
+
+impl<S> RpcRequest<S> {
+    fn into_state<N>(self) -> RpcRequest<N> {
+        RpcRequest {
+            jsonrpc: self.jsonrpc,
+            id: self.id,
+            method: self.method,
+            value: self.value,
+            params: self.params,
+            cluster: self.cluster,
+            extras: self.extras,
+            deprecated_fallback: self.deprecated_fallback,
+            priority: self.priority,
+            cancellation: self.cancellation,
+            retry_policy: self.retry_policy,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            headers: self.headers,
+            middlewares: self.middlewares,
+            cache: self.cache,
+            genesis: self.genesis,
+            capabilities: self.capabilities,
+            transport: self.transport,
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics,
+            state: PhantomData,
+        }
+    }
+
+    pub fn change_jsonrpc(mut self, jsonrpc: &str) -> Self {
+        self.jsonrpc = jsonrpc.to_owned();
+
         self
     }
 
@@ -52,8 +212,20 @@ impl RpcRequest {
         self
     }
 
-    pub fn add_method(mut self, method: RpcMethod) -> Self {
-        self.method = method;
+    /// Ask the node to answer only once it has processed the slot, for example the
+    /// context slot of an earlier response, so reads never go back in time.
+    /// A node that is behind answers with [MIN_CONTEXT_SLOT_NOT_REACHED]
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.extras.retain(|(key, _)| key != "minContextSlot");
+        self.extras.push(("minContextSlot".to_owned(), slot.into()));
+
+        self
+    }
+
+    /// A positional parameter after the first one, for methods like
+    /// `getTokenAccountsByOwner` that take a filter before the configuration object
+    pub fn add_param(mut self, param: JsonValue) -> Self {
+        self.params.push(param);
 
         self
     }
@@ -64,53 +236,808 @@ impl RpcRequest {
         self
     }
 
-    pub fn change_id(mut self, id: u8) -> Self {
+    /// Override the JSON-RPC id, which is otherwise unique within the process
+    pub fn change_id(mut self, id: u64) -> Self {
         self.id = id;
 
         self
     }
 
+    /// Retry with the deprecated `getConfirmed*` method name when the node does not
+    /// know the modern one, normalizing the response into the modern types
+    pub fn change_deprecated_fallback(mut self, enabled: bool) -> Self {
+        self.deprecated_fallback = enabled;
+
+        self
+    }
+
+    /// The priority class used by a [crate::RequestScheduler] to order this request
+    pub fn change_priority(mut self, priority: RequestPriority) -> Self {
+        self.priority = priority;
+
+        self
+    }
+
+    /// Abort the request with [AtollError::Cancelled] when the token is cancelled, also while
+    /// it waits for a response. Blocking requests are only aborted between attempts
+    pub fn add_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+
+        self
+    }
+
+    /// Retry transient failures like connection errors, timeouts and 5xx responses
+    pub fn change_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+
+        self
+    }
+
+    /// The time a single attempt may take before it fails as timed out and can be retried
+    pub fn change_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// The instant by which the request, including all retries, has to complete
+    /// or fail with [AtollError::DeadlineExceeded]
+    pub fn change_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+
+        self
+    }
+
+    /// Send an HTTP header with the request, like the API key of an RPC provider
+    pub fn add_header(mut self, key: &str, value: &str) -> Self {
+        self.headers.insert(key, value);
+
+        self
+    }
+
+    /// Replace the HTTP headers with a set shared between requests.
+    /// Call it before [RpcRequest::add_header] to add request specific headers
+    pub fn change_headers(mut self, headers: RpcHeaders) -> Self {
+        self.headers = headers;
+
+        self
+    }
+
+    /// Run the hooks of the middleware when the request is sent and its response received.
+    /// Middlewares run in the order they were added
+    pub fn add_middleware(mut self, middleware: Arc<dyn RpcMiddleware>) -> Self {
+        self.middlewares.push(middleware);
+
+        self
+    }
+
+    /// Report the measurements of every attempt of the request to the sink
+    #[cfg(feature = "metrics")]
+    pub fn add_metrics(mut self, sink: Arc<dyn crate::MetricsSink>) -> Self {
+        self.metrics.push(sink);
+
+        self
+    }
+
+    /// Send the request through the transport instead of the default one
+    pub fn change_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Answer the request from the cache while a response for the same cluster,
+    /// method and parameters is fresh, and store the response otherwise
+    pub fn add_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.cache = Some(cache);
+
+        self
+    }
+
+    /// Ask the endpoint for its genesis hash before the first request sent to it and
+    /// fail with [AtollError::WrongCluster] if it does not serve the selected cluster
+    pub fn add_genesis_verifier(mut self, verifier: Arc<GenesisVerifier>) -> Self {
+        self.genesis = Some(verifier);
+
+        self
+    }
+
+    /// Call `getVersion` and `getHealth` on the node the first time a method or option
+    /// gated by the node version is sent to it, and fail with [AtollError::UnsupportedByNode]
+    /// instead of sending a request the node does not understand
+    pub fn add_capabilities(mut self, capabilities: Arc<CapabilityCache>) -> Self {
+        self.capabilities = Some(capabilities);
+
+        self
+    }
+
+    /// A new request to the same endpoint with the same headers, transport, retries,
+    /// timeouts, middlewares, caches and cancellation, under a new id and without a
+    /// method or parameters. The helpers of the crate take a configured request as
+    /// their client and derive each request they send from it
+    pub fn template(&self) -> RpcRequest<Unconfigured> {
+        RpcRequest {
+            jsonrpc: self.jsonrpc.clone(),
+            id: next_request_id(),
+            method: RpcMethod::GetAccountInfo,
+            value: None,
+            params: Vec::default(),
+            cluster: self.cluster.clone(),
+            extras: Vec::default(),
+            deprecated_fallback: self.deprecated_fallback,
+            priority: self.priority,
+            cancellation: self.cancellation.clone(),
+            retry_policy: self.retry_policy.clone(),
+            timeout: self.timeout,
+            deadline: self.deadline,
+            headers: self.headers.clone(),
+            middlewares: self.middlewares.clone(),
+            cache: self.cache.clone(),
+            genesis: self.genesis.clone(),
+            capabilities: self.capabilities.clone(),
+            transport: self.transport.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics.clone(),
+            state: PhantomData,
+        }
+    }
+
+    /// Send the request to the endpoint of the other request, through its transport
+    /// and with its headers
+    pub(crate) fn change_endpoint<E>(mut self, endpoint: &RpcRequest<E>) -> Self {
+        self.cluster = endpoint.cluster.clone();
+        self.transport = endpoint.transport.clone();
+        // Headers of this request replace the endpoint headers of the same name
+        let mut headers = endpoint.headers.clone();
+        self.headers
+            .iter()
+            .for_each(|(key, value)| headers.insert(key, value));
+        self.headers = headers;
+
+        self
+    }
+
+    pub fn cluster(&self) -> &Cluster {
+        &self.cluster
+    }
+
+    pub fn priority(&self) -> RequestPriority {
+        self.priority
+    }
+
+    pub fn cancellation(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .map(|cancellation| cancellation.is_cancelled())
+            .unwrap_or(false)
+    }
+}
+
+impl RpcRequest<Ready> {
     pub async fn request<T: fmt::Debug + DeserializeOwned>(self) -> AtollResult<HttpResponse<T>> {
-        let method = self.method.to_upper_camel_case();
+        if self.is_genesis_pending() {
+            let genesis = self
+                .side_request(ParameterlessMethod::GetGenesisHash)
+                .send_side()
+                .await;
+            self.store_genesis(genesis)?;
+        }
+        // A mismatch stays stored, so every later request to the endpoint fails too
+        self.verify_genesis()?;
 
-        let mut extra_parameters = json::object::Object::new();
+        if self.is_capabilities_pending() {
+            let version = self
+                .side_request(ParameterlessMethod::GetVersion)
+                .send_side()
+                .await;
+            let health = self
+                .side_request(ParameterlessMethod::GetHealth)
+                .send_side()
+                .await;
+            self.store_capabilities(version, health)?;
+        }
+        self.check_capabilities()?;
 
-        self.extras.into_iter().for_each(|(key, value)| {
-            extra_parameters.insert(&key, value);
-        });
+        if let Some(cached) = self.cached() {
+            return self.parse_cached(&cached);
+        }
 
-        let json_body = json::object! {
-            jsonrpc: self.jsonrpc,
-            id: self.id,
-            method: method,
-            params: if extra_parameters.is_empty() {
-                json::array![
-                    self.value,
-                ]
+        let response = self.correlate(self.send(&self.method).await?)?;
+        self.store(&response);
+
+        if let Some(deprecated_method) = self.method.deprecated_fallback() {
+            if self.deprecated_fallback && Self::is_method_not_found(&response) {
+                let response = self.correlate(self.send(&deprecated_method).await?)?;
+
+                return self.parse(&deprecated_method, response);
+            }
+        }
+
+        self.parse(&self.method, response)
+    }
+
+    /// Send the request on the current thread without an async runtime.
+    /// Timeouts are rounded up to whole seconds
+    pub fn request_blocking<T: fmt::Debug + DeserializeOwned>(
+        self,
+    ) -> AtollResult<HttpResponse<T>> {
+        if self.is_genesis_pending() {
+            let genesis = self
+                .side_request(ParameterlessMethod::GetGenesisHash)
+                .send_side_blocking();
+            self.store_genesis(genesis)?;
+        }
+        // A mismatch stays stored, so every later request to the endpoint fails too
+        self.verify_genesis()?;
+
+        if self.is_capabilities_pending() {
+            let version = self
+                .side_request(ParameterlessMethod::GetVersion)
+                .send_side_blocking();
+            let health = self
+                .side_request(ParameterlessMethod::GetHealth)
+                .send_side_blocking();
+            self.store_capabilities(version, health)?;
+        }
+        self.check_capabilities()?;
+
+        if let Some(cached) = self.cached() {
+            return self.parse_cached(&cached);
+        }
+
+        let response = self.correlate(self.send_blocking(&self.method)?)?;
+        self.store(&response);
+
+        if let Some(deprecated_method) = self.method.deprecated_fallback() {
+            if self.deprecated_fallback && Self::is_method_not_found(&response) {
+                let response = self.correlate(self.send_blocking(&deprecated_method)?)?;
+
+                return self.parse(&deprecated_method, response);
+            }
+        }
+
+        self.parse(&self.method, response)
+    }
+
+    /// A request without parameters sent before this one, sharing its endpoint,
+    /// headers, transport and retries
+    fn side_request(&self, method: ParameterlessMethod) -> RpcRequest<Ready> {
+        let mut side = RpcRequest::new()
+            .change_cluster(self.cluster.clone())
+            .change_headers(self.headers.clone())
+            .change_transport(self.transport.clone())
+            .change_retry_policy(self.retry_policy.clone())
+            .change_timeout(self.timeout)
+            .add_parameterless_method(method);
+        side.cancellation = self.cancellation.clone();
+        side.deadline = self.deadline;
+
+        side
+    }
+
+    /// Send a side request, skipping the cache and the checks of [RpcRequest::request]
+    async fn send_side<T: fmt::Debug + DeserializeOwned>(self) -> AtollResult<HttpResponse<T>> {
+        let response = self.correlate(self.send(&self.method).await?)?;
+
+        self.parse(&self.method, response)
+    }
+
+    fn send_side_blocking<T: fmt::Debug + DeserializeOwned>(self) -> AtollResult<HttpResponse<T>> {
+        let response = self.correlate(self.send_blocking(&self.method)?)?;
+
+        self.parse(&self.method, response)
+    }
+
+    fn is_genesis_pending(&self) -> bool {
+        self.genesis
+            .as_ref()
+            .is_some_and(|verifier| verifier.is_pending(&self.cluster))
+    }
+
+    fn store_genesis(&self, response: AtollResult<HttpResponse<String>>) -> AtollResult<()> {
+        if let Some(verifier) = self.genesis.as_ref() {
+            verifier.insert(&self.cluster, response?.into_result()?);
+        }
+
+        Ok(())
+    }
+
+    fn verify_genesis(&self) -> AtollResult<()> {
+        match self.genesis.as_ref() {
+            Some(verifier) => verifier.verify(&self.cluster),
+            None => Ok(()),
+        }
+    }
+
+    /// The options of the configuration object gated by the node version
+    fn gated_options(&self) -> Vec<RpcOption> {
+        self.extras
+            .iter()
+            .filter_map(|(key, _)| RpcOption::from_key(key))
+            .collect()
+    }
+
+    fn is_version_gated(&self) -> bool {
+        self.method.introduced_in().is_some()
+            || self.method.removed_in().is_some()
+            || !self.gated_options().is_empty()
+    }
+
+    fn is_capabilities_pending(&self) -> bool {
+        self.capabilities
+            .as_ref()
+            .is_some_and(|capabilities| capabilities.get(&self.cluster).is_none())
+            && self.is_version_gated()
+    }
+
+    fn store_capabilities(
+        &self,
+        version: AtollResult<HttpResponse<NodeVersion>>,
+        health: AtollResult<HttpResponse<String>>,
+    ) -> AtollResult<()> {
+        if let Some(capabilities) = self.capabilities.as_ref() {
+            let version = version?.into_result()?;
+            let health = NodeHealth::from_response(health)?;
+            capabilities.insert(&self.cluster, NodeCapabilities::new(version, health)?);
+        }
+
+        Ok(())
+    }
+
+    fn check_capabilities(&self) -> AtollResult<()> {
+        match self
+            .capabilities
+            .as_ref()
+            .and_then(|capabilities| capabilities.get(&self.cluster))
+        {
+            Some(capabilities) if self.is_version_gated() => {
+                capabilities.check_request(&self.method, &self.gated_options())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the response answers this request
+    fn correlate(&self, response: TransportResponse) -> AtollResult<TransportResponse> {
+        let received = response
+            .as_str()
+            .ok()
+            .and_then(|body| serde_json::from_str::<ResponseId>(body).ok())
+            .and_then(|response_id| response_id.id);
+
+        match received {
+            Some(received) if received != self.id => Err(AtollError::ResponseIdMismatch {
+                expected: self.id,
+                received,
+            }),
+            _ => Ok(response),
+        }
+    }
+
+    fn cache_key(&self) -> CacheKey {
+        CacheKey {
+            url: self.cluster.url().to_owned(),
+            method: self.method,
+            params: self.to_json(&self.method, 0)["params"].dump(),
+        }
+    }
+
+    fn cached(&self) -> Option<CachedResponse> {
+        self.cache
+            .as_ref()
+            .and_then(|cache| cache.get(&self.cache_key()))
+    }
+
+    fn store(&self, response: &TransportResponse) {
+        if let Some(cache) = self.cache.as_ref() {
+            cache.insert(self.cache_key(), response);
+        }
+    }
+
+    fn parse_cached<T: fmt::Debug + DeserializeOwned>(
+        &self,
+        cached: &CachedResponse,
+    ) -> AtollResult<HttpResponse<T>> {
+        if self.middlewares.is_empty() {
+            return cached.parse(&self.method);
+        }
+
+        let mut http_response = cached.parse::<serde_json::Value>(&self.method)?;
+        self.middlewares
+            .iter()
+            .for_each(|middleware| middleware.after_receive(&mut http_response));
+
+        http_response.deserialize_result()
+    }
+
+    fn parse<T: fmt::Debug + DeserializeOwned>(
+        &self,
+        method: &RpcMethod,
+        response: TransportResponse,
+    ) -> AtollResult<HttpResponse<T>> {
+        if self.middlewares.is_empty() {
+            return method.parse_blocking(response);
+        }
+
+        let mut http_response = method.parse_blocking::<serde_json::Value>(response)?;
+        self.middlewares
+            .iter()
+            .for_each(|middleware| middleware.after_receive(&mut http_response));
+
+        http_response.deserialize_result()
+    }
+
+    /// Send the request, retrying failures as configured by the retry policy
+    async fn send(&self, method: &RpcMethod) -> AtollResult<TransportResponse> {
+        let mut attempts = Attempts::new();
+
+        loop {
+            let attempt = async {
+                let started = Instant::now();
+                let outcome = self.send_once(method).await;
+                self.trace_attempt(started.elapsed(), &outcome);
+                self.record_metrics(method, &attempts, started.elapsed(), &outcome);
+
+                outcome
+            };
+            let outcome = self.in_span(method, &attempts, attempt).await;
+
+            match self.next_attempt(&outcome, &mut attempts)? {
+                Some(wait) => self.pause(wait).await?,
+                None => return outcome,
+            }
+        }
+    }
+
+    fn send_blocking(&self, method: &RpcMethod) -> AtollResult<TransportResponse> {
+        let mut attempts = Attempts::new();
+
+        loop {
+            let outcome = self.in_span_blocking(method, &attempts, || {
+                let started = Instant::now();
+                let outcome = self.timeout().and_then(|(timeout, _)| {
+                    self.transport
+                        .post_blocking(&self.http_request(method, timeout))
+                });
+                self.trace_attempt(started.elapsed(), &outcome);
+                self.record_metrics(method, &attempts, started.elapsed(), &outcome);
+
+                outcome
+            });
+
+            match self.next_attempt(&outcome, &mut attempts)? {
+                Some(wait) => {
+                    self.check_pause(wait)?;
+                    std::thread::sleep(wait);
+                }
+                None => return outcome,
+            }
+        }
+    }
+
+    /// Report an attempt to the metrics sinks
+    #[cfg(feature = "metrics")]
+    fn record_metrics(
+        &self,
+        method: &RpcMethod,
+        attempts: &Attempts,
+        latency: Duration,
+        outcome: &AtollResult<TransportResponse>,
+    ) {
+        if self.metrics.is_empty() {
+            return;
+        }
+
+        let attempt = crate::AttemptMetrics {
+            method: *method,
+            attempt: attempts.attempt + attempts.rate_limited,
+            latency,
+            status_code: outcome.as_ref().ok().map(|response| response.status_code),
+            error: match outcome {
+                Ok(response) => ErrorClass::of_response(response),
+                Err(error) => Some(ErrorClass::of(error)),
+            },
+            bytes_received: outcome
+                .as_ref()
+                .map(|response| response.as_bytes().len())
+                .unwrap_or_default(),
+        };
+
+        self.metrics.iter().for_each(|sink| sink.record(&attempt));
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn record_metrics(
+        &self,
+        _method: &RpcMethod,
+        _attempts: &Attempts,
+        _latency: Duration,
+        _outcome: &AtollResult<TransportResponse>,
+    ) {
+    }
+
+    /// The `rpc_request` span an attempt is sent and reported in
+    #[cfg(feature = "tracing")]
+    fn attempt_span(&self, method: &RpcMethod, attempts: &Attempts) -> tracing::Span {
+        tracing::debug_span!(
+            "rpc_request",
+            method = method.to_upper_camel_case(),
+            cluster = %crate::redact_url(self.cluster.url()),
+            id = self.id,
+            attempt = attempts.attempt + attempts.rate_limited,
+        )
+    }
+
+    /// Run an attempt within its `rpc_request` span
+    #[cfg(feature = "tracing")]
+    async fn in_span<T>(
+        &self,
+        method: &RpcMethod,
+        attempts: &Attempts,
+        attempt: impl Future<Output = T>,
+    ) -> T {
+        use tracing::Instrument;
+
+        attempt
+            .instrument(self.attempt_span(method, attempts))
+            .await
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    async fn in_span<T>(
+        &self,
+        _method: &RpcMethod,
+        _attempts: &Attempts,
+        attempt: impl Future<Output = T>,
+    ) -> T {
+        attempt.await
+    }
+
+    /// Run a blocking attempt within its `rpc_request` span
+    #[cfg(feature = "tracing")]
+    fn in_span_blocking<T>(
+        &self,
+        method: &RpcMethod,
+        attempts: &Attempts,
+        attempt: impl FnOnce() -> T,
+    ) -> T {
+        self.attempt_span(method, attempts).in_scope(attempt)
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    fn in_span_blocking<T>(
+        &self,
+        _method: &RpcMethod,
+        _attempts: &Attempts,
+        attempt: impl FnOnce() -> T,
+    ) -> T {
+        attempt()
+    }
+
+    /// Emit an event for an attempt within its `rpc_request` span
+    #[cfg(feature = "tracing")]
+    fn trace_attempt(&self, latency: Duration, outcome: &AtollResult<TransportResponse>) {
+        let latency_ms = latency.as_millis() as u64;
+
+        match outcome {
+            Ok(response) => tracing::debug!(
+                latency_ms,
+                status = response.status_code,
+                "RPC response received"
+            ),
+            Err(error) => tracing::warn!(latency_ms, error = ?error, "RPC request failed"),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    fn trace_attempt(&self, _latency: Duration, _outcome: &AtollResult<TransportResponse>) {}
+
+    /// The time to wait before sending the request again, `None` to return the outcome
+    fn next_attempt(
+        &self,
+        outcome: &AtollResult<TransportResponse>,
+        attempts: &mut Attempts,
+    ) -> AtollResult<Option<Duration>> {
+        if let Ok(response) = outcome.as_ref() {
+            if response.status_code == 429 {
+                let retry_after = retry_after(response);
+                attempts.rate_limited = attempts.rate_limited.saturating_add(1);
+                let wait =
+                    retry_after.unwrap_or_else(|| self.retry_policy.delay(attempts.rate_limited));
+
+                // A wait that overflows the total exhausts the budget as well
+                match attempts.rate_limit_waited.checked_add(wait) {
+                    Some(waited) if waited <= self.retry_policy.rate_limit_budget() => {
+                        attempts.rate_limit_waited = waited;
+                    }
+                    _ => {
+                        return Err(AtollError::RateLimited {
+                            retry_after: retry_after.map(|retry_after| retry_after.as_secs()),
+                        })
+                    }
+                }
+
+                return Ok(Some(wait));
+            }
+        }
+
+        let class = match outcome.as_ref() {
+            Ok(response) => ErrorClass::of_response(response),
+            Err(error) => Some(ErrorClass::of(error)),
+        };
+
+        match class {
+            Some(class) if self.retry_policy.should_retry(class, attempts.attempt) => {
+                let wait = self.retry_policy.delay(attempts.attempt);
+                attempts.attempt += 1;
+
+                Ok(Some(wait))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn send_once(&self, method: &RpcMethod) -> AtollResult<TransportResponse> {
+        let (timeout, limited_by_deadline) = self.timeout()?;
+
+        // `minreq` only takes whole seconds, the timer enforces the exact timeout
+        let http_request = self.http_request(method, timeout);
+        let response = self.transport.clone().post(http_request);
+        let timed_out = async {
+            runtime::sleep(timeout).await;
+
+            if limited_by_deadline {
+                Err(AtollError::DeadlineExceeded)
             } else {
-                    json::array![
-                    self.value,
-                    extra_parameters
-                ]
+                Err(AtollError::Utilities(UtilitiesError::Io(
+                    IoErrorKind::TimedOut,
+                )))
+            }
+        };
+
+        self.or_cancelled(futures_lite::future::or(response, timed_out))
+            .await
+    }
+
+    /// Run the future unless the cancellation token of the request is cancelled first
+    async fn or_cancelled<T>(
+        &self,
+        future: impl Future<Output = AtollResult<T>>,
+    ) -> AtollResult<T> {
+        match self.cancellation.as_ref() {
+            Some(cancellation) => {
+                let cancelled = async {
+                    cancellation.cancelled().await;
+
+                    Err(AtollError::Cancelled)
+                };
+
+                futures_lite::future::or(future, cancelled).await
             }
+            None => future.await,
+        }
+    }
+
+    /// The timeout of the next attempt and whether it is shortened by the deadline
+    fn timeout(&self) -> AtollResult<(Duration, bool)> {
+        if self.is_cancelled() {
+            return Err(AtollError::Cancelled);
+        }
+
+        match self.remaining() {
+            Some(remaining) if remaining.is_zero() => Err(AtollError::DeadlineExceeded),
+            Some(remaining) if remaining < self.timeout => Ok((remaining, true)),
+            _ => Ok((self.timeout, false)),
+        }
+    }
+
+    fn http_request(&self, method: &RpcMethod, timeout: Duration) -> TransportRequest {
+        TransportRequest {
+            url: self.cluster.url().to_owned(),
+            headers: self.headers.clone().add("Content-Type", "application/json"),
+            body: self.to_sent_json(method, self.id).dump(),
+            timeout,
+        }
+    }
+
+    /// Wait before the next attempt unless the deadline passes first
+    async fn pause(&self, wait: Duration) -> AtollResult<()> {
+        self.check_pause(wait)?;
+
+        self.or_cancelled(async {
+            runtime::sleep(wait).await;
+
+            Ok(())
+        })
+        .await
+    }
+
+    fn check_pause(&self, wait: Duration) -> AtollResult<()> {
+        match self.remaining() {
+            Some(remaining) if remaining <= wait => Err(AtollError::DeadlineExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    /// The time left until the deadline
+    fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// The JSON-RPC request object for the method with the given id
+    pub(crate) fn to_json(&self, method: &RpcMethod, id: u64) -> JsonValue {
+        let mut extra_parameters = json::object::Object::new();
+
+        self.extras.iter().for_each(|(key, value)| {
+            extra_parameters.insert(key, value.clone());
+        });
 
+        // Methods without a positional parameter take the configuration object first
+        let mut params = self.value.iter().cloned().collect::<Vec<JsonValue>>();
+        params.extend(self.params.iter().cloned());
 
+        if !extra_parameters.is_empty() {
+            params.push(extra_parameters.into());
         }
-        .to_string();
 
-        let http_client = minreq::post(self.cluster.url())
-            .with_header("Content-Type", "application/json")
-            .with_body(json_body)
-            .with_timeout(60);
+        json::object! {
+            jsonrpc: self.jsonrpc.as_str(),
+            id: id,
+            method: method.to_upper_camel_case(),
+            params: params
+        }
+    }
+
+    /// The JSON-RPC request object as sent, after the `before_send` hook of every middleware
+    pub(crate) fn to_sent_json(&self, method: &RpcMethod, id: u64) -> JsonValue {
+        let mut json_body = self.to_json(method, id);
+        self.middlewares
+            .iter()
+            .for_each(|middleware| middleware.before_send(&mut json_body));
 
-        let response = unblock(|| http_client.send()).await?;
+        json_body
+    }
+
+    pub(crate) fn method(&self) -> &RpcMethod {
+        &self.method
+    }
 
-        self.method.parse(response).await
+    fn is_method_not_found(response: &TransportResponse) -> bool {
+        response
+            .as_str()
+            .ok()
+            .and_then(|body| serde_json::from_str::<RpcJsonError>(body).ok())
+            .map(|json_error| json_error.error.code == METHOD_NOT_FOUND)
+            .unwrap_or(false)
     }
 }
 
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The next JSON-RPC id, unique for every request and batch entry of the process
+pub(crate) fn next_request_id() -> u64 {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Reads only the `id` of a response
+#[derive(Debug, Deserialize)]
+struct ResponseId {
+    id: Option<u64>,
+}
+
+/// The JSON-RPC error code returned for methods a node does not serve
+pub const METHOD_NOT_FOUND: i16 = -32601;
+
+/// The JSON-RPC error code returned when the node has not reached the `minContextSlot`
+pub const MIN_CONTEXT_SLOT_NOT_REACHED: i16 = -32016;
+
 /// Configures the Solana RPC cluster to connect to
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, BorshSerialize, BorshDeserialize)]
 pub enum Cluster {
     /// A locally run Solana test validator
     LocalNet,
@@ -120,23 +1047,119 @@ pub enum Cluster {
     TestNet,
     /// Connect to the production cluster
     MainNetBeta,
+    /// A private RPC provider or self-hosted node, created with [Cluster::custom]
+    Custom(CustomUrl),
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for Cluster {
+    fn default() -> Self {
+        Cluster::DevNet
+    }
+}
+
+/// The validated URL of a [Cluster::Custom] endpoint, cheap to clone
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CustomUrl(Arc<str>);
+
+impl CustomUrl {
+    /// The URL must use the `http` or `https` scheme and name a host
+    fn parse(url: &str) -> AtollResult<Self> {
+        let url = url.trim();
+
+        let host = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .ok_or_else(|| {
+                AtollError::InvalidUrl(format!(
+                    "The RPC endpoint `{url}` must use the `http` or `https` scheme"
+                ))
+            })?;
+
+        if host.is_empty() || host.starts_with('/') {
+            return Err(AtollError::InvalidUrl(format!(
+                "The RPC endpoint `{url}` has no host"
+            )));
+        }
+
+        Ok(CustomUrl(url.into()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CustomUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl BorshSerialize for CustomUrl {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        BorshSerialize::serialize(self.as_str(), writer)
+    }
+}
+
+impl BorshDeserialize for CustomUrl {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let url = <String as BorshDeserialize>::deserialize(buf)?;
+
+        CustomUrl::parse(&url).map_err(|error| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{error:?}"))
+        })
+    }
 }
 
 impl Cluster {
+    /// A custom endpoint. The URL must use the `http` or `https` scheme and name a host
+    pub fn custom(url: &str) -> AtollResult<Self> {
+        CustomUrl::parse(url).map(Cluster::Custom)
+    }
+
     /// Convert the cluster selected to a URL
-    pub fn url<'a>(&self) -> &'a str {
+    pub fn url(&self) -> &str {
         match self {
             Cluster::LocalNet => "https://127.0.0.1:8899",
             Cluster::DevNet => "https://api.devnet.solana.com",
             Cluster::TestNet => "https://api.testnet.solana.com",
             Cluster::MainNetBeta => "https://api.mainnet-beta.solana.com",
+            Cluster::Custom(url) => url.as_str(),
         }
     }
-}
 
-impl Default for Cluster {
-    fn default() -> Self {
-        Cluster::DevNet
+    /// The WebSocket URL of the pubsub endpoint. A local validator serves it on
+    /// the port after the RPC port, other endpoints on the same host and port
+    pub fn ws_url(&self) -> String {
+        match self {
+            Cluster::LocalNet => "ws://127.0.0.1:8900".to_owned(),
+            Cluster::DevNet => "wss://api.devnet.solana.com".to_owned(),
+            Cluster::TestNet => "wss://api.testnet.solana.com".to_owned(),
+            Cluster::MainNetBeta => "wss://api.mainnet-beta.solana.com".to_owned(),
+            Cluster::Custom(url) => {
+                let url = url.as_str();
+
+                if let Some(rest) = url.strip_prefix("https://") {
+                    format!("wss://{rest}")
+                } else if let Some(rest) = url.strip_prefix("http://") {
+                    format!("ws://{rest}")
+                } else {
+                    url.to_owned()
+                }
+            }
+        }
+    }
+
+    /// The base58 encoded genesis hash of the public clusters.
+    /// `None` for a local validator and custom endpoints, whose genesis is not known
+    pub fn genesis_hash(&self) -> Option<&'static str> {
+        match self {
+            Cluster::DevNet => Some("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG"),
+            Cluster::TestNet => Some("4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY"),
+            Cluster::MainNetBeta => Some("5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d"),
+            Cluster::LocalNet | Cluster::Custom(_) => None,
+        }
     }
 }
 
@@ -167,6 +1190,7 @@ pub enum Commitment {
     InvalidCommitment,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Commitment {
     fn default() -> Self {
         Commitment::Finalized
@@ -184,6 +1208,17 @@ impl From<&str> for Commitment {
     }
 }
 
+impl From<Commitment> for &str {
+    fn from(value: Commitment) -> Self {
+        match value {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+            Commitment::InvalidCommitment => "invalid",
+        }
+    }
+}
+
 /// The encoding for the data format
 #[derive(
     Debug,
@@ -200,8 +1235,10 @@ impl From<&str> for Commitment {
 )]
 pub enum Encoding {
     /// Base58 encoding
+    #[serde(rename = "base58")]
     Base58,
     /// Base64 Encoding
+    #[serde(rename = "base64")]
     Base64,
     /// The encoding provided is not supported yer
     UnsupportedEncoding,
@@ -217,6 +1254,16 @@ impl From<&str> for Encoding {
     }
 }
 
+impl From<Encoding> for &str {
+    fn from(value: Encoding) -> Self {
+        match value {
+            Encoding::Base58 => "base58",
+            Encoding::Base64 => "base64",
+            Encoding::UnsupportedEncoding => "unsupported",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HttpResponse<T> {
@@ -226,11 +1273,54 @@ pub struct HttpResponse<T> {
     pub body: RequestOutcome<T>,
 }
 
+impl<T> HttpResponse<T> {
+    /// Get the `result` of a successful response or the error returned by the node
+    pub fn into_result(self) -> AtollResult<T> {
+        self.body.into_result()
+    }
+}
+
+impl HttpResponse<serde_json::Value> {
+    /// Deserialize the untyped `result` of a successful response into `T`
+    pub fn deserialize_result<T: DeserializeOwned>(self) -> AtollResult<HttpResponse<T>> {
+        let body = match self.body {
+            RequestOutcome::Success(response) => RequestOutcome::Success(RpcResponse {
+                jsonrpc: response.jsonrpc,
+                id: response.id,
+                result: serde_path_to_error::deserialize(response.result)
+                    .map_err(|error| AtollError::SerdeJsonDeser(error.to_string()))?,
+            }),
+            RequestOutcome::InvalidJson(json_error) => RequestOutcome::InvalidJson(json_error),
+        };
+
+        Ok(HttpResponse {
+            status_code: self.status_code,
+            headers: self.headers,
+            reason_phrase: self.reason_phrase,
+            body,
+        })
+    }
+}
+
+impl<T> RequestOutcome<T> {
+    /// Get the `result` of a successful response or the error returned by the node
+    pub fn into_result(self) -> AtollResult<T> {
+        match self {
+            RequestOutcome::Success(response) => Ok(response.result),
+            RequestOutcome::InvalidJson(json_error) => Err(AtollError::Rpc {
+                code: json_error.error.code,
+                message: json_error.error.message,
+                data: json_error.error.data,
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcResponse<T> {
     pub jsonrpc: String,
-    pub id: u8,
+    pub id: u64,
     pub result: T,
 }
 
@@ -241,6 +1331,23 @@ pub struct RpcResult<U> {
     pub value: Option<U>,
 }
 
+impl<U> HttpResponse<RpcResult<U>> {
+    /// The slot the node evaluated the request at, `None` for error responses
+    pub fn context_slot(&self) -> Option<u64> {
+        match &self.body {
+            RequestOutcome::Success(response) => Some(response.result.context.slot),
+            RequestOutcome::InvalidJson(_) => None,
+        }
+    }
+
+    /// The `value` of a successful response and the slot it was read at
+    pub fn into_value_with_slot(self) -> AtollResult<(Option<U>, u64)> {
+        let result = self.into_result()?;
+
+        Ok((result.value, result.context.slot))
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Context {
@@ -251,7 +1358,8 @@ pub struct Context {
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, BorshSerialize, BorshDeserialize, Deserialize)]
 pub struct RpcJsonError {
     jsonrpc: String,
-    id: u8,
+    /// `None` when the node could not read the id of the request
+    id: Option<u64>,
     error: JsonError,
 }
 
@@ -259,9 +1367,23 @@ pub struct RpcJsonError {
 pub struct JsonError {
     code: i16,
     message: String,
+    /// Kept as JSON text since nodes send objects like `{"numSlotsBehind":42}`
+    #[serde(default, deserialize_with = "json_text")]
     data: Option<String>,
 }
 
+fn json_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let data = Option::<serde_json::Value>::deserialize(deserializer)?;
+
+    Ok(data.map(|data| match data {
+        serde_json::Value::String(text) => text,
+        data => data.to_string(),
+    }))
+}
+
 #[derive(Debug, BorshSerialize, BorshDeserialize, Deserialize)]
 pub enum RequestOutcome<T> {
     Success(RpcResponse<T>),
@@ -271,8 +1393,55 @@ pub enum RequestOutcome<T> {
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, BorshSerialize, BorshDeserialize, Deserialize)]
 pub struct MalformedRequest {
     jsonrpc: String,
-    id: u8,
+    id: Option<u64>,
     code: i16,
     message: String,
     data: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockTransport;
+
+    #[test]
+    fn retries_rpc_errors_sent_with_http_200() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .add_rpc_error("getSlot", -32005, "Node is behind by 42 slots")
+                .add_result("getSlot", 1234.into()),
+        );
+
+        let slot = RpcRequest::new()
+            .change_transport(transport.clone())
+            .change_retry_policy(
+                RetryPolicy::new()
+                    .add_retryable(ErrorClass::Rpc)
+                    .change_base_delay(Duration::from_millis(1)),
+            )
+            .add_parameterless_method(ParameterlessMethod::GetSlot)
+            .request_blocking::<u64>()
+            .and_then(HttpResponse::into_result);
+
+        assert_eq!(slot, Ok(1234));
+        assert_eq!(transport.methods(), ["getSlot", "getSlot"]);
+    }
+
+    #[test]
+    fn returns_rpc_errors_unless_retryable() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .add_rpc_error("getSlot", -32005, "Node is behind by 42 slots")
+                .add_result("getSlot", 1234.into()),
+        );
+
+        let slot = RpcRequest::new()
+            .change_transport(transport.clone())
+            .add_parameterless_method(ParameterlessMethod::GetSlot)
+            .request_blocking::<u64>()
+            .and_then(HttpResponse::into_result);
+
+        assert!(matches!(slot, Err(AtollError::Rpc { code: -32005, .. })));
+        assert_eq!(transport.methods(), ["getSlot"]);
+    }
+}
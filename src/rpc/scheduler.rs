@@ -0,0 +1,237 @@
+use crate::{
+    runtime::{self, Instant},
+    AtollError, AtollResult, CancellationToken, HttpResponse, RpcRequest,
+};
+use core::fmt;
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::Duration,
+};
+
+/// How often queued requests check whether it is their turn while no token is due
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// The priority class of a request. All queued interactive requests are
+/// sent before any backfill request
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RequestPriority {
+    /// User-facing calls where latency matters
+    #[default]
+    Interactive,
+    /// Background work like indexing and historical backfills
+    Backfill,
+}
+
+impl RequestPriority {
+    const ALL: [RequestPriority; 2] = [RequestPriority::Interactive, RequestPriority::Backfill];
+
+    fn index(&self) -> usize {
+        match self {
+            Self::Interactive => 0,
+            Self::Backfill => 1,
+        }
+    }
+}
+
+/// Queues outgoing requests and releases them at the rate limit of the provider.
+/// Within a priority class the clients are served round-robin so a single
+/// busy client cannot monopolize the rate limit of the endpoint.
+#[derive(Debug)]
+pub struct RequestScheduler {
+    requests_per_second: f64,
+    burst: f64,
+    state: Mutex<SchedulerState>,
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+    tokens: f64,
+    last_refill: Instant,
+    next_ticket: u64,
+    classes: [FairQueue; 2],
+}
+
+/// The requests of one priority class, queued per client
+#[derive(Debug, Default)]
+struct FairQueue {
+    rotation: VecDeque<String>,
+    tickets: HashMap<String, VecDeque<u64>>,
+}
+
+impl FairQueue {
+    fn push(&mut self, client: &str, ticket: u64) {
+        let queue = self.tickets.entry(client.to_owned()).or_default();
+
+        if queue.is_empty() {
+            self.rotation.push_back(client.to_owned());
+        }
+        queue.push_back(ticket);
+    }
+
+    fn head(&self) -> Option<u64> {
+        self.rotation
+            .front()
+            .and_then(|client| self.tickets.get(client))
+            .and_then(|queue| queue.front().copied())
+    }
+
+    /// Remove the head ticket and move its client to the back of the rotation
+    fn pop(&mut self) {
+        if let Some(client) = self.rotation.pop_front() {
+            if let Some(queue) = self.tickets.get_mut(&client) {
+                queue.pop_front();
+
+                if queue.is_empty() {
+                    self.tickets.remove(&client);
+                } else {
+                    self.rotation.push_back(client);
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, client: &str, ticket: u64) {
+        if let Some(queue) = self.tickets.get_mut(client) {
+            queue.retain(|queued| *queued != ticket);
+
+            if queue.is_empty() {
+                self.tickets.remove(client);
+                self.rotation.retain(|queued| queued != client);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.tickets.values().map(|queue| queue.len()).sum()
+    }
+}
+
+impl RequestScheduler {
+    /// Create a scheduler releasing at most `requests_per_second`, with a burst of one second
+    pub fn new(requests_per_second: u32) -> Self {
+        let requests_per_second = requests_per_second.max(1) as f64;
+
+        RequestScheduler {
+            requests_per_second,
+            burst: requests_per_second,
+            state: Mutex::new(SchedulerState {
+                tokens: requests_per_second,
+                last_refill: Instant::now(),
+                next_ticket: 0,
+                classes: [FairQueue::default(), FairQueue::default()],
+            }),
+        }
+    }
+
+    /// The number of requests that can be released at once after the scheduler was idle
+    pub fn change_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1) as f64;
+        self.state.get_mut().unwrap().tokens = self.burst;
+
+        self
+    }
+
+    /// Wait for the turn of the request in its priority class and send it.
+    /// A cancelled request leaves the queue with [AtollError::Cancelled]
+    pub async fn schedule<T: fmt::Debug + DeserializeOwned>(
+        &self,
+        client: &str,
+        request: RpcRequest,
+    ) -> AtollResult<HttpResponse<T>> {
+        self.acquire(client, request.priority(), request.cancellation())
+            .await?;
+
+        request.request::<T>().await
+    }
+
+    /// Wait until the scheduler releases a request for the client
+    pub async fn acquire(
+        &self,
+        client: &str,
+        priority: RequestPriority,
+        cancellation: Option<&CancellationToken>,
+    ) -> AtollResult<()> {
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.classes[priority.index()].push(client, ticket);
+
+            ticket
+        };
+
+        // Dequeue the ticket if the future is dropped before its turn
+        let mut guard = TicketGuard {
+            scheduler: self,
+            client,
+            priority,
+            ticket,
+            released: false,
+        };
+
+        loop {
+            if cancellation
+                .map(|cancellation| cancellation.is_cancelled())
+                .unwrap_or(false)
+            {
+                return Err(AtollError::Cancelled);
+            }
+
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                self.refill(&mut state);
+
+                let head = RequestPriority::ALL
+                    .iter()
+                    .find_map(|class| state.classes[class.index()].head());
+
+                if head == Some(ticket) && state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    state.classes[priority.index()].pop();
+                    guard.released = true;
+
+                    return Ok(());
+                } else if head == Some(ticket) {
+                    Duration::from_secs_f64((1.0 - state.tokens) / self.requests_per_second)
+                } else {
+                    POLL_INTERVAL
+                }
+            };
+
+            runtime::sleep(wait).await;
+        }
+    }
+
+    /// The number of requests waiting in a priority class
+    pub fn queued(&self, priority: RequestPriority) -> usize {
+        self.state.lock().unwrap().classes[priority.index()].len()
+    }
+
+    fn refill(&self, state: &mut SchedulerState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+
+        state.tokens = (state.tokens + elapsed * self.requests_per_second).min(self.burst);
+        state.last_refill = now;
+    }
+}
+
+struct TicketGuard<'a> {
+    scheduler: &'a RequestScheduler,
+    client: &'a str,
+    priority: RequestPriority,
+    ticket: u64,
+    released: bool,
+}
+
+impl Drop for TicketGuard<'_> {
+    fn drop(&mut self) {
+        if !self.released {
+            if let Ok(mut state) = self.scheduler.state.lock() {
+                state.classes[self.priority.index()].remove(self.client, self.ticket);
+            }
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{runtime, AtollError, AtollResult, Minreq, RpcHeaders};
+use core::{future::Future, pin::Pin};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The future returned by [HttpTransport::post]
+#[cfg(not(feature = "wasm"))]
+pub type TransportFuture = Pin<Box<dyn Future<Output = AtollResult<TransportResponse>> + Send>>;
+/// The future returned by [HttpTransport::post], not `Send` on wasm
+#[cfg(feature = "wasm")]
+pub type TransportFuture = Pin<Box<dyn Future<Output = AtollResult<TransportResponse>>>>;
+
+/// The HTTP client that sends the POST of a request or batch. Implement it to reuse
+/// the connection pool and TLS stack of an existing client.
+/// The default is `MinreqTransport`, or `FetchTransport` with the `wasm` feature.
+/// `ReqwestTransport` and `UreqTransport` are available with the `reqwest` and `ureq`
+/// features. Use [MockTransport] to test code that sends requests without a live cluster
+pub trait HttpTransport: core::fmt::Debug + Send + Sync + 'static {
+    /// Send the request on the current thread
+    fn post_blocking(&self, request: &TransportRequest) -> AtollResult<TransportResponse>;
+
+    /// Send the request without blocking the async runtime. The default runs
+    /// [HttpTransport::post_blocking] on the blocking thread pool of the runtime,
+    /// clients with an async API override it
+    fn post(self: Arc<Self>, request: TransportRequest) -> TransportFuture {
+        Box::pin(runtime::unblock(move || self.post_blocking(&request)))
+    }
+}
+
+/// The transport of requests that are not given one with `change_transport`
+pub(crate) fn default_transport() -> Arc<dyn HttpTransport> {
+    #[cfg(feature = "wasm")]
+    return Arc::new(crate::FetchTransport);
+
+    #[cfg(all(feature = "minreq", not(feature = "wasm")))]
+    return Arc::new(MinreqTransport);
+
+    #[cfg(not(any(feature = "minreq", feature = "wasm")))]
+    return Arc::new(MissingTransport);
+}
+
+/// Fails every request, used when no HTTP client feature is enabled
+#[cfg(not(any(feature = "minreq", feature = "wasm")))]
+#[derive(Debug)]
+struct MissingTransport;
+
+#[cfg(not(any(feature = "minreq", feature = "wasm")))]
+impl HttpTransport for MissingTransport {
+    fn post_blocking(&self, _request: &TransportRequest) -> AtollResult<TransportResponse> {
+        Err(AtollError::Transport(
+            "No HTTP client is enabled, enable the `minreq` or `wasm` feature of atoll \
+            or give the request a transport"
+                .to_owned(),
+        ))
+    }
+}
+
+/// An HTTP POST of a JSON-RPC request or batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportRequest {
+    pub url: String,
+    pub headers: RpcHeaders,
+    /// The JSON-RPC request object or batch array
+    pub body: String,
+    /// The time the request may take. `MinreqTransport` rounds it up to whole seconds
+    pub timeout: Duration,
+}
+
+/// The HTTP response to a [TransportRequest]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportResponse {
+    pub status_code: u16,
+    pub reason_phrase: String,
+    /// The headers with lowercase names
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl TransportResponse {
+    /// A response with the status and body and no headers
+    pub fn new(status_code: u16, body: impl Into<Vec<u8>>) -> Self {
+        TransportResponse {
+            status_code,
+            reason_phrase: String::default(),
+            headers: HashMap::default(),
+            body: body.into(),
+        }
+    }
+
+    /// Add a header, its name is stored in lowercase
+    pub fn add_header(mut self, key: &str, value: &str) -> Self {
+        self.headers.insert(key.to_lowercase(), value.to_owned());
+
+        self
+    }
+
+    pub fn change_reason_phrase(mut self, reason_phrase: &str) -> Self {
+        self.reason_phrase = reason_phrase.to_owned();
+
+        self
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// The body as UTF-8
+    pub fn as_str(&self) -> AtollResult<&str> {
+        core::str::from_utf8(&self.body)
+            .map_err(|error| AtollError::Http(Minreq::InvalidUtf8InBody(error.to_string())))
+    }
+}
+
+#[cfg(feature = "minreq")]
+impl From<minreq::Response> for TransportResponse {
+    fn from(response: minreq::Response) -> Self {
+        TransportResponse {
+            status_code: response.status_code as u16,
+            reason_phrase: response.reason_phrase.clone(),
+            headers: response.headers.clone(),
+            body: response.into_bytes(),
+        }
+    }
+}
+
+/// Sends requests with the blocking `minreq` HTTP client
+#[cfg(feature = "minreq")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MinreqTransport;
+
+#[cfg(feature = "minreq")]
+impl HttpTransport for MinreqTransport {
+    fn post_blocking(&self, request: &TransportRequest) -> AtollResult<TransportResponse> {
+        let response = request
+            .headers
+            .apply(minreq::post(request.url.as_str()))
+            .with_body(request.body.as_str())
+            .with_timeout(request.timeout.as_secs_f64().ceil().max(1.0) as u64)
+            .send()?;
+
+        Ok(response.into())
+    }
+}
+
+/// Answers requests with canned responses per method instead of sending them.
+/// The `id` of a canned JSON-RPC response is replaced with the `id` of the request
+/// it answers, and batches are answered entry by entry. Responses added for a method
+/// are returned in order, the last one is repeated for every further request.
+/// Every request received is kept for assertions
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Mutex<HashMap<String, VecDeque<TransportResponse>>>,
+    requests: Mutex<Vec<TransportRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        MockTransport::default()
+    }
+
+    /// Answer the method, for example `getBalance`, with HTTP 200 and the JSON body
+    pub fn add_response(self, method: &str, body: &str) -> Self {
+        self.add_http_response(method, TransportResponse::new(200, body))
+    }
+
+    /// Answer the method with `{"jsonrpc":"2.0","id":1,"result":<result>}`
+    pub fn add_result(self, method: &str, result: serde_json::Value) -> Self {
+        let body = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": result });
+
+        self.add_response(method, &body.to_string())
+    }
+
+    /// Answer the method with a JSON-RPC error
+    pub fn add_rpc_error(self, method: &str, code: i16, message: &str) -> Self {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": code, "message": message }
+        });
+
+        self.add_response(method, &body.to_string())
+    }
+
+    /// Answer the method with any HTTP response, like a 429 or 503
+    pub fn add_http_response(self, method: &str, response: TransportResponse) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(method.to_owned())
+            .or_default()
+            .push_back(response);
+
+        self
+    }
+
+    /// The requests received so far, in order
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The methods of the requests received so far, in order, batch entries included
+    pub fn methods(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|request| serde_json::from_str::<serde_json::Value>(&request.body).ok())
+            .flat_map(|body| match body {
+                serde_json::Value::Array(entries) => entries,
+                entry => vec![entry],
+            })
+            .filter_map(|entry| entry["method"].as_str().map(str::to_owned))
+            .collect()
+    }
+
+    fn next_response(&self, method: &str) -> AtollResult<TransportResponse> {
+        let mut responses = self.responses.lock().unwrap();
+
+        // The last response of a method is repeated for every later request
+        let response = responses
+            .get_mut(method)
+            .and_then(|queue| match queue.len() {
+                1 => queue.front().cloned(),
+                _ => queue.pop_front(),
+            });
+
+        response.ok_or_else(|| {
+            AtollError::NotFound(format!("The mock transport has no response for `{method}`"))
+        })
+    }
+
+    /// Answer a single JSON-RPC request object
+    fn answer(&self, entry: &serde_json::Value) -> AtollResult<TransportResponse> {
+        let method = entry["method"].as_str().unwrap_or_default();
+        let mut response = self.next_response(method)?;
+
+        if let Ok(mut body) = serde_json::from_slice::<serde_json::Value>(&response.body) {
+            if let Some(id) = body.get_mut("id") {
+                *id = entry["id"].clone();
+                response.body = body.to_string().into_bytes();
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn post_blocking(&self, request: &TransportRequest) -> AtollResult<TransportResponse> {
+        self.requests.lock().unwrap().push(request.clone());
+
+        let body = serde_json::from_str::<serde_json::Value>(&request.body)?;
+
+        match body {
+            serde_json::Value::Array(entries) => {
+                let mut responses = Vec::with_capacity(entries.len());
+
+                for entry in &entries {
+                    let response = self.answer(entry)?;
+                    responses.push(serde_json::from_slice::<serde_json::Value>(&response.body)?);
+                }
+
+                Ok(TransportResponse::new(
+                    200,
+                    serde_json::Value::Array(responses).to_string(),
+                ))
+            }
+            entry => self.answer(&entry),
+        }
+    }
+}
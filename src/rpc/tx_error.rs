@@ -1,4 +1,4 @@
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 pub type TransactionResult<T> = Result<T, TransactionError>;
@@ -8,11 +8,13 @@ pub type TransactionResult<T> = Result<T, TransactionError>;
     PartialEq,
     Eq,
     PartialOrd,
+    Ord,
     Clone,
     Deserialize,
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 pub enum TransactionError {
     AccountInUse,
@@ -23,7 +25,9 @@ pub enum TransactionError {
     InvalidAccountForFee,
     AlreadyProcessed,
     BlockhashNotFound,
-    InstructionError(u8, InstructionError),
+    // The `BorshSchema` derive of borsh 0.9 mirrors the fields of each variant in
+    // a struct that is never read
+    InstructionError(#[allow(dead_code)] u8, #[allow(dead_code)] InstructionError),
     CallChainTooDeep,
     MissingSignatureForFee,
     InvalidAccountIndex,
@@ -45,8 +49,22 @@ pub enum TransactionError {
     InvalidRentPayingAccount,
     WouldExceedMaxVoteCostLimit,
     WouldExceedAccountDataTotalLimit,
-    DuplicateInstruction(u8),
-    InsufficientFundsForRent { account_index: u8 },
+    DuplicateInstruction(#[allow(dead_code)] u8),
+    InsufficientFundsForRent {
+        #[allow(dead_code)]
+        account_index: u8,
+    },
+}
+
+impl TransactionError {
+    /// The index of the instruction that failed, `None` if the transaction failed
+    /// before or outside of its instructions
+    pub fn instruction_index(&self) -> Option<u8> {
+        match self {
+            TransactionError::InstructionError(index, _) => Some(*index),
+            _ => None,
+        }
+    }
 }
 
 #[derive(
@@ -54,11 +72,13 @@ pub enum TransactionError {
     PartialEq,
     Eq,
     PartialOrd,
+    Ord,
     Clone,
     Deserialize,
     Serialize,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
 )]
 pub enum InstructionError {
     GenericError,
@@ -86,7 +106,7 @@ pub enum InstructionError {
     AccountBorrowFailed,
     AccountBorrowOutstanding,
     DuplicateAccountOutOfSync,
-    Custom(u32),
+    Custom(#[allow(dead_code)] u32),
     InvalidError,
     ExecutableDataModified,
     ExecutableLamportChange,
@@ -105,7 +125,7 @@ pub enum InstructionError {
     ProgramFailedToCompile,
     Immutable,
     IncorrectAuthority,
-    BorshIoError(String),
+    BorshIoError(#[allow(dead_code)] String),
     AccountNotRentExempt,
     InvalidAccountOwner,
     ArithmeticOverflow,
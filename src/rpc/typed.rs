@@ -0,0 +1,2252 @@
+use crate::{
+    AtollError, AtollResult, Block, Commitment, ConfirmedTransaction, Encoding, EpochInfo,
+    EpochSchedule, GetAccountInfo, HttpResponse, InflationGovernor, InflationRate, InflationReward,
+    KeyedAccount, LargestAccount, LatestBlockhash, NodeIdentity, NodeVersion, PerfSample,
+    PrioritizationFee, Ready, RpcMethod, RpcRequest, RpcResult, SignatureInfo, SignatureStatus,
+    SimulationResult, SnapshotSlot, StakeActivation, Supply, TokenAmount, TokenLargestAccount,
+    Unconfigured, VoteAccounts, MAX_PERFORMANCE_SAMPLES, MAX_PRIORITIZATION_FEE_ACCOUNTS,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use core::{fmt, marker::PhantomData};
+use json::JsonValue;
+use serde::de::DeserializeOwned;
+
+/// The most accounts `getMultipleAccounts` returns per request
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// The most signatures `getSignatureStatuses` accepts per request
+pub const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// A request whose parameters are built for its method and whose response type
+/// is fixed by it. Send it with [RpcRequest::add_typed]
+pub trait TypedRequest {
+    type Response: fmt::Debug + DeserializeOwned;
+
+    fn method(&self) -> RpcMethod;
+
+    /// The first positional parameter, `None` for methods without one
+    fn value(&self) -> Option<JsonValue>;
+
+    /// The positional parameters following the first one, like the mint filter
+    /// of `getTokenAccountsByOwner`
+    fn params(&self) -> Vec<JsonValue> {
+        Vec::new()
+    }
+
+    /// The fields of the configuration object
+    fn config(&self) -> Vec<(&'static str, JsonValue)>;
+}
+
+/// A [RpcRequest] built from a [TypedRequest] that deserializes into its response type
+#[derive(Debug)]
+pub struct TypedRpcRequest<R> {
+    request: RpcRequest<Ready>,
+    typed: PhantomData<R>,
+}
+
+impl RpcRequest<Unconfigured> {
+    /// Select the method and parameters of the typed request. Configure the cluster,
+    /// retries and other options of the request before adding it
+    pub fn add_typed<R: TypedRequest>(self, typed: R) -> TypedRpcRequest<R> {
+        let request = self.add_method(typed.method());
+        let request = match typed.value() {
+            Some(value) => request.add_value(value),
+            None => request.without_value(),
+        };
+
+        let request = typed
+            .params()
+            .into_iter()
+            .fold(request, RpcRequest::add_param);
+
+        TypedRpcRequest {
+            request: typed
+                .config()
+                .into_iter()
+                .fold(request, |request, (key, value)| {
+                    request.add_extra(key, value)
+                }),
+            typed: PhantomData,
+        }
+    }
+}
+
+impl<R: TypedRequest> TypedRpcRequest<R> {
+    pub async fn request(self) -> AtollResult<HttpResponse<R::Response>> {
+        self.request.request().await
+    }
+
+    pub fn request_blocking(self) -> AtollResult<HttpResponse<R::Response>> {
+        self.request.request_blocking()
+    }
+
+    /// The untyped request, for example to add it to a [crate::RpcBatch]
+    pub fn into_inner(self) -> RpcRequest<Ready> {
+        self.request
+    }
+}
+
+/// The configuration fields shared by most methods
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct CommonConfig {
+    commitment: Option<Commitment>,
+    min_context_slot: Option<u64>,
+}
+
+impl CommonConfig {
+    fn fields(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = Vec::new();
+
+        if let Some(commitment) = self.commitment {
+            fields.push(("commitment", <&str>::from(commitment).into()));
+        }
+
+        if let Some(slot) = self.min_context_slot {
+            fields.push(("minContextSlot", slot.into()));
+        }
+
+        fields
+    }
+}
+
+/// `getAccountInfo` with base64 encoded account data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetAccountInfoRequest {
+    address: String,
+    config: CommonConfig,
+}
+
+impl GetAccountInfoRequest {
+    /// The account at the base58 encoded address
+    pub fn new(address: &str) -> Self {
+        GetAccountInfoRequest {
+            address: address.to_owned(),
+            config: CommonConfig::default(),
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetAccountInfoRequest {
+    type Response = RpcResult<GetAccountInfo>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetAccountInfo
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.address.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = self.config.fields();
+        fields.push(("encoding", "base64".into()));
+
+        fields
+    }
+}
+
+/// `getBalance` in lamports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetBalanceRequest {
+    address: String,
+    config: CommonConfig,
+}
+
+impl GetBalanceRequest {
+    /// The balance of the base58 encoded address
+    pub fn new(address: &str) -> Self {
+        GetBalanceRequest {
+            address: address.to_owned(),
+            config: CommonConfig::default(),
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetBalanceRequest {
+    type Response = RpcResult<u64>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetBalance
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.address.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.config.fields()
+    }
+}
+
+/// `getBlock` with full base64 encoded transactions, including versioned transactions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetBlockRequest {
+    slot: u64,
+    commitment: Option<Commitment>,
+}
+
+impl GetBlockRequest {
+    pub fn new(slot: u64) -> Self {
+        GetBlockRequest {
+            slot,
+            commitment: None,
+        }
+    }
+
+    /// `confirmed` or `finalized`, the node rejects `processed`
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetBlockRequest {
+    type Response = Block;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetBlock
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.slot.into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = vec![
+            ("encoding", "base64".into()),
+            ("transactionDetails", "full".into()),
+            ("maxSupportedTransactionVersion", 0.into()),
+        ];
+
+        if let Some(commitment) = self.commitment {
+            fields.push(("commitment", <&str>::from(commitment).into()));
+        }
+
+        fields
+    }
+}
+
+/// `getTransaction` with the base64 encoded transaction, `None` if it is unknown to the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetTransactionRequest {
+    signature: String,
+    commitment: Option<Commitment>,
+}
+
+impl GetTransactionRequest {
+    /// The transaction with the base58 encoded signature
+    pub fn new(signature: &str) -> Self {
+        GetTransactionRequest {
+            signature: signature.to_owned(),
+            commitment: None,
+        }
+    }
+
+    /// `confirmed` or `finalized`, the node rejects `processed`
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetTransactionRequest {
+    type Response = Option<ConfirmedTransaction>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetTransaction
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.signature.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = vec![
+            ("encoding", "base64".into()),
+            ("maxSupportedTransactionVersion", 0.into()),
+        ];
+
+        if let Some(commitment) = self.commitment {
+            fields.push(("commitment", <&str>::from(commitment).into()));
+        }
+
+        fields
+    }
+}
+
+/// `getSlot`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetSlotRequest {
+    config: CommonConfig,
+}
+
+impl GetSlotRequest {
+    pub fn new() -> Self {
+        GetSlotRequest::default()
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetSlotRequest {
+    type Response = u64;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetSlot
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.config.fields()
+    }
+}
+
+/// `getBlockHeight`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetBlockHeightRequest {
+    config: CommonConfig,
+}
+
+impl GetBlockHeightRequest {
+    pub fn new() -> Self {
+        GetBlockHeightRequest::default()
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetBlockHeightRequest {
+    type Response = u64;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetBlockHeight
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.config.fields()
+    }
+}
+
+/// `getMinimumBalanceForRentExemption` in lamports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetMinimumBalanceForRentExemptionRequest {
+    data_len: usize,
+    commitment: Option<Commitment>,
+}
+
+impl GetMinimumBalanceForRentExemptionRequest {
+    /// The balance an account holding `data_len` bytes needs to be rent exempt
+    pub fn new(data_len: usize) -> Self {
+        GetMinimumBalanceForRentExemptionRequest {
+            data_len,
+            commitment: None,
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetMinimumBalanceForRentExemptionRequest {
+    type Response = u64;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetMinimumBalanceForRentExemption
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.data_len.into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.commitment
+            .map(|commitment| vec![("commitment", <&str>::from(commitment).into())])
+            .unwrap_or_default()
+    }
+}
+
+/// `sendTransaction` of a signed wire encoded transaction, answered with its base58
+/// encoded signature. The node only forwards the transaction to the leaders,
+/// the signature does not mean the transaction was processed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendTransactionRequest {
+    transaction: Vec<u8>,
+    encoding: Encoding,
+    skip_preflight: Option<bool>,
+    preflight_commitment: Option<Commitment>,
+    max_retries: Option<usize>,
+    min_context_slot: Option<u64>,
+}
+
+impl SendTransactionRequest {
+    /// Send the bytes of the signed transaction in its wire format, base64 encoded
+    pub fn new(transaction: &[u8]) -> Self {
+        SendTransactionRequest {
+            transaction: transaction.to_vec(),
+            encoding: Encoding::Base64,
+            skip_preflight: None,
+            preflight_commitment: None,
+            max_retries: None,
+            min_context_slot: None,
+        }
+    }
+
+    /// Encode the transaction as base58 or base64, any other encoding sends base64
+    pub fn change_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+
+        self
+    }
+
+    /// Skip the simulation the node runs before forwarding the transaction
+    pub fn change_skip_preflight(mut self, skip_preflight: bool) -> Self {
+        self.skip_preflight = Some(skip_preflight);
+
+        self
+    }
+
+    /// The commitment the preflight simulation runs at
+    pub fn change_preflight_commitment(mut self, commitment: Commitment) -> Self {
+        self.preflight_commitment = Some(commitment);
+
+        self
+    }
+
+    /// How often the node retries forwarding the transaction, the node decides if unset
+    pub fn change_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = Some(max_retries);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for SendTransactionRequest {
+    type Response = String;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::SendTransaction
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        let encoded = match self.encoding {
+            Encoding::Base58 => bs58::encode(&self.transaction).into_string(),
+            Encoding::Base64 | Encoding::UnsupportedEncoding => BASE64.encode(&self.transaction),
+        };
+
+        Some(encoded.into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let encoding = match self.encoding {
+            Encoding::Base58 => "base58",
+            Encoding::Base64 | Encoding::UnsupportedEncoding => "base64",
+        };
+        let mut fields = vec![("encoding", encoding.into())];
+
+        if let Some(skip_preflight) = self.skip_preflight {
+            fields.push(("skipPreflight", skip_preflight.into()));
+        }
+
+        if let Some(commitment) = self.preflight_commitment {
+            fields.push(("preflightCommitment", <&str>::from(commitment).into()));
+        }
+
+        if let Some(max_retries) = self.max_retries {
+            fields.push(("maxRetries", max_retries.into()));
+        }
+
+        if let Some(slot) = self.min_context_slot {
+            fields.push(("minContextSlot", slot.into()));
+        }
+
+        fields
+    }
+}
+
+/// `simulateTransaction` of a wire encoded transaction, returning its logs, compute
+/// units and error without paying fees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulateTransactionRequest {
+    transaction: Vec<u8>,
+    sig_verify: bool,
+    replace_recent_blockhash: bool,
+    accounts: Vec<String>,
+    config: CommonConfig,
+}
+
+impl SimulateTransactionRequest {
+    /// Simulate the bytes of the transaction in its wire format. Signatures are not
+    /// verified so the transaction does not need to be signed
+    pub fn new(transaction: &[u8]) -> Self {
+        SimulateTransactionRequest {
+            transaction: transaction.to_vec(),
+            sig_verify: false,
+            replace_recent_blockhash: false,
+            accounts: Vec::new(),
+            config: CommonConfig::default(),
+        }
+    }
+
+    /// Verify the signatures, which conflicts with replacing the blockhash
+    pub fn change_sig_verify(mut self, sig_verify: bool) -> Self {
+        self.sig_verify = sig_verify;
+
+        self
+    }
+
+    /// Simulate with the latest blockhash in place of the blockhash of the transaction
+    pub fn change_replace_recent_blockhash(mut self, replace: bool) -> Self {
+        self.replace_recent_blockhash = replace;
+
+        self
+    }
+
+    /// Return the state of the base58 encoded account after the simulation
+    pub fn add_account(mut self, address: &str) -> Self {
+        self.accounts.push(address.to_owned());
+
+        self
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for SimulateTransactionRequest {
+    type Response = RpcResult<SimulationResult>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::SimulateTransaction
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(BASE64.encode(&self.transaction).into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = self.config.fields();
+        fields.push(("encoding", "base64".into()));
+        fields.push(("sigVerify", self.sig_verify.into()));
+        fields.push((
+            "replaceRecentBlockhash",
+            self.replace_recent_blockhash.into(),
+        ));
+
+        if !self.accounts.is_empty() {
+            fields.push((
+                "accounts",
+                json::object! {
+                    encoding: "base64",
+                    addresses: self.accounts.clone(),
+                },
+            ));
+        }
+
+        fields
+    }
+}
+
+/// `getSignaturesForAddress`, newest first, at most 1000 signatures per request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetSignaturesForAddressRequest {
+    address: String,
+    limit: Option<usize>,
+    before: Option<String>,
+    until: Option<String>,
+    config: CommonConfig,
+}
+
+impl GetSignaturesForAddressRequest {
+    /// The signatures of transactions that involve the base58 encoded address
+    pub fn new(address: &str) -> Self {
+        GetSignaturesForAddressRequest {
+            address: address.to_owned(),
+            limit: None,
+            before: None,
+            until: None,
+            config: CommonConfig::default(),
+        }
+    }
+
+    /// The maximum number of signatures, from 1 to 1000, 1000 if unset
+    pub fn change_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+
+        self
+    }
+
+    /// Start searching backwards from the transaction before this signature
+    pub fn change_before(mut self, signature: &str) -> Self {
+        self.before = Some(signature.to_owned());
+
+        self
+    }
+
+    /// Stop searching at this signature, which is not included
+    pub fn change_until(mut self, signature: &str) -> Self {
+        self.until = Some(signature.to_owned());
+
+        self
+    }
+
+    /// `confirmed` or `finalized`, the node rejects `processed`
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetSignaturesForAddressRequest {
+    type Response = Vec<SignatureInfo>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetSignaturesForAddress
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.address.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = self.config.fields();
+
+        if let Some(limit) = self.limit {
+            fields.push(("limit", limit.into()));
+        }
+
+        if let Some(before) = self.before.as_deref() {
+            fields.push(("before", before.into()));
+        }
+
+        if let Some(until) = self.until.as_deref() {
+            fields.push(("until", until.into()));
+        }
+
+        fields
+    }
+}
+
+/// `getSignatureStatuses` of base58 encoded signatures, answered in the same
+/// order with `None` for transactions unknown to the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetSignatureStatusesRequest {
+    signatures: Vec<String>,
+    search_transaction_history: bool,
+}
+
+impl GetSignatureStatusesRequest {
+    /// The statuses of up to [MAX_SIGNATURE_STATUSES] signatures
+    pub fn new(signatures: &[&str]) -> AtollResult<Self> {
+        if signatures.len() > MAX_SIGNATURE_STATUSES {
+            return Err(AtollError::TooManyAccounts {
+                max: MAX_SIGNATURE_STATUSES,
+                received: signatures.len(),
+            });
+        }
+
+        Ok(GetSignatureStatusesRequest {
+            signatures: signatures
+                .iter()
+                .map(|signature| signature.to_string())
+                .collect(),
+            search_transaction_history: false,
+        })
+    }
+
+    /// Search the ledger for transactions older than the recent status cache
+    pub fn change_search_transaction_history(mut self, search: bool) -> Self {
+        self.search_transaction_history = search;
+
+        self
+    }
+}
+
+impl TypedRequest for GetSignatureStatusesRequest {
+    type Response = RpcResult<Vec<Option<SignatureStatus>>>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetSignatureStatuses
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.signatures.clone().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        if self.search_transaction_history {
+            vec![("searchTransactionHistory", true.into())]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// `getLatestBlockhash` with the last block height it is valid for
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetLatestBlockhashRequest {
+    config: CommonConfig,
+}
+
+impl GetLatestBlockhashRequest {
+    pub fn new() -> Self {
+        GetLatestBlockhashRequest::default()
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetLatestBlockhashRequest {
+    type Response = RpcResult<LatestBlockhash>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetLatestBlockhash
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.config.fields()
+    }
+}
+
+/// `isBlockhashValid`, whether a transaction using the blockhash is still accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsBlockhashValidRequest {
+    blockhash: String,
+    config: CommonConfig,
+}
+
+impl IsBlockhashValidRequest {
+    /// Check the base58 encoded blockhash
+    pub fn new(blockhash: &str) -> Self {
+        IsBlockhashValidRequest {
+            blockhash: blockhash.to_owned(),
+            config: CommonConfig::default(),
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for IsBlockhashValidRequest {
+    type Response = RpcResult<bool>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::IsBlockhashValid
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.blockhash.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.config.fields()
+    }
+}
+
+/// `getMultipleAccounts` with base64 encoded account data, answered in the same
+/// order as the addresses with `None` for accounts that do not exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetMultipleAccountsRequest {
+    addresses: Vec<String>,
+    config: CommonConfig,
+}
+
+impl GetMultipleAccountsRequest {
+    /// The accounts at up to [MAX_MULTIPLE_ACCOUNTS] base58 encoded addresses
+    pub fn new(addresses: &[&str]) -> AtollResult<Self> {
+        if addresses.len() > MAX_MULTIPLE_ACCOUNTS {
+            return Err(AtollError::TooManyAccounts {
+                max: MAX_MULTIPLE_ACCOUNTS,
+                received: addresses.len(),
+            });
+        }
+
+        Ok(GetMultipleAccountsRequest {
+            addresses: addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect(),
+            config: CommonConfig::default(),
+        })
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetMultipleAccountsRequest {
+    type Response = RpcResult<Vec<Option<GetAccountInfo>>>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetMultipleAccounts
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.addresses.clone().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = self.config.fields();
+        fields.push(("encoding", "base64".into()));
+
+        fields
+    }
+}
+
+/// A filter of `getProgramAccounts`, an account has to match all filters of a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramAccountsFilter {
+    /// The account data is exactly this many bytes long
+    DataSize(u64),
+    /// The account data contains the base58 encoded bytes at the offset
+    Memcmp { offset: usize, bytes: String },
+}
+
+impl ProgramAccountsFilter {
+    pub fn data_size(data_size: u64) -> Self {
+        ProgramAccountsFilter::DataSize(data_size)
+    }
+
+    /// The account data contains the bytes at the offset
+    pub fn memcmp(offset: usize, bytes: &[u8]) -> Self {
+        ProgramAccountsFilter::Memcmp {
+            offset,
+            bytes: bs58::encode(bytes).into_string(),
+        }
+    }
+
+    /// The account data contains the base58 encoded bytes, like an address, at the offset
+    pub fn memcmp_base58(offset: usize, bytes: &str) -> Self {
+        ProgramAccountsFilter::Memcmp {
+            offset,
+            bytes: bytes.to_owned(),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> JsonValue {
+        match self {
+            ProgramAccountsFilter::DataSize(data_size) => json::object! { dataSize: *data_size },
+            ProgramAccountsFilter::Memcmp { offset, bytes } => json::object! {
+                memcmp: { offset: *offset, bytes: bytes.as_str() }
+            },
+        }
+    }
+}
+
+/// `getProgramAccounts` with base64 encoded account data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetProgramAccountsRequest {
+    program_id: String,
+    filters: Vec<ProgramAccountsFilter>,
+    data_slice: Option<(usize, usize)>,
+    config: CommonConfig,
+}
+
+impl GetProgramAccountsRequest {
+    /// The accounts owned by the base58 encoded program
+    pub fn new(program_id: &str) -> Self {
+        GetProgramAccountsRequest {
+            program_id: program_id.to_owned(),
+            filters: Vec::new(),
+            data_slice: None,
+            config: CommonConfig::default(),
+        }
+    }
+
+    /// Only return accounts matching the filter as well as the filters added before
+    pub fn add_filter(mut self, filter: ProgramAccountsFilter) -> Self {
+        self.filters.push(filter);
+
+        self
+    }
+
+    /// Only return `length` bytes of the account data starting at `offset`
+    pub fn change_data_slice(mut self, offset: usize, length: usize) -> Self {
+        self.data_slice = Some((offset, length));
+
+        self
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+
+    /// Answer with the slot the accounts were read at
+    pub fn with_context(self) -> GetProgramAccountsWithContextRequest {
+        GetProgramAccountsWithContextRequest { request: self }
+    }
+}
+
+impl TypedRequest for GetProgramAccountsRequest {
+    type Response = Vec<KeyedAccount>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetProgramAccounts
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.program_id.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = self.config.fields();
+        fields.push(("encoding", "base64".into()));
+
+        if let Some((offset, length)) = self.data_slice {
+            fields.push((
+                "dataSlice",
+                json::object! { offset: offset, length: length },
+            ));
+        }
+
+        if !self.filters.is_empty() {
+            fields.push((
+                "filters",
+                self.filters
+                    .iter()
+                    .map(ProgramAccountsFilter::to_json)
+                    .collect::<Vec<JsonValue>>()
+                    .into(),
+            ));
+        }
+
+        fields
+    }
+}
+
+/// A [GetProgramAccountsRequest] answered with the context slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetProgramAccountsWithContextRequest {
+    request: GetProgramAccountsRequest,
+}
+
+impl TypedRequest for GetProgramAccountsWithContextRequest {
+    type Response = RpcResult<Vec<KeyedAccount>>;
+
+    fn method(&self) -> RpcMethod {
+        self.request.method()
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        self.request.value()
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = self.request.config();
+        fields.push(("withContext", true.into()));
+
+        fields
+    }
+}
+
+/// Selects the token accounts of `getTokenAccountsByOwner` and `getTokenAccountsByDelegate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenAccountsFilter {
+    /// The token accounts of the base58 encoded mint
+    Mint(String),
+    /// The token accounts owned by the base58 encoded token program,
+    /// SPL Token or Token-2022
+    ProgramId(String),
+}
+
+impl TokenAccountsFilter {
+    fn to_json(&self) -> JsonValue {
+        match self {
+            TokenAccountsFilter::Mint(mint) => json::object! { mint: mint.as_str() },
+            TokenAccountsFilter::ProgramId(program_id) => {
+                json::object! { programId: program_id.as_str() }
+            }
+        }
+    }
+}
+
+/// `getTokenAccountsByOwner` with base64 encoded account data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetTokenAccountsByOwnerRequest {
+    owner: String,
+    filter: TokenAccountsFilter,
+    config: CommonConfig,
+}
+
+impl GetTokenAccountsByOwnerRequest {
+    /// The token accounts of the base58 encoded owner selected by the filter
+    pub fn new(owner: &str, filter: TokenAccountsFilter) -> Self {
+        GetTokenAccountsByOwnerRequest {
+            owner: owner.to_owned(),
+            filter,
+            config: CommonConfig::default(),
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetTokenAccountsByOwnerRequest {
+    type Response = RpcResult<Vec<KeyedAccount>>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetTokenAccountsByOwner
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.owner.as_str().into())
+    }
+
+    fn params(&self) -> Vec<JsonValue> {
+        vec![self.filter.to_json()]
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        token_accounts_config(&self.config)
+    }
+}
+
+/// `getTokenAccountsByDelegate` with base64 encoded account data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetTokenAccountsByDelegateRequest {
+    delegate: String,
+    filter: TokenAccountsFilter,
+    config: CommonConfig,
+}
+
+impl GetTokenAccountsByDelegateRequest {
+    /// The token accounts approved to the base58 encoded delegate selected by the filter
+    pub fn new(delegate: &str, filter: TokenAccountsFilter) -> Self {
+        GetTokenAccountsByDelegateRequest {
+            delegate: delegate.to_owned(),
+            filter,
+            config: CommonConfig::default(),
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetTokenAccountsByDelegateRequest {
+    type Response = RpcResult<Vec<KeyedAccount>>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetTokenAccountsByDelegate
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.delegate.as_str().into())
+    }
+
+    fn params(&self) -> Vec<JsonValue> {
+        vec![self.filter.to_json()]
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        token_accounts_config(&self.config)
+    }
+}
+
+fn token_accounts_config(config: &CommonConfig) -> Vec<(&'static str, JsonValue)> {
+    let mut fields = config.fields();
+    fields.push(("encoding", "base64".into()));
+
+    fields
+}
+
+/// `getTokenAccountBalance` of a token account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetTokenAccountBalanceRequest {
+    token_account: String,
+    commitment: Option<Commitment>,
+}
+
+impl GetTokenAccountBalanceRequest {
+    /// The balance of the base58 encoded token account
+    pub fn new(token_account: &str) -> Self {
+        GetTokenAccountBalanceRequest {
+            token_account: token_account.to_owned(),
+            commitment: None,
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetTokenAccountBalanceRequest {
+    type Response = RpcResult<TokenAmount>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetTokenAccountBalance
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.token_account.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.commitment
+            .map(|commitment| vec![("commitment", <&str>::from(commitment).into())])
+            .unwrap_or_default()
+    }
+}
+
+/// `getTokenSupply`, the total supply of a mint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetTokenSupplyRequest {
+    mint: String,
+    commitment: Option<Commitment>,
+}
+
+impl GetTokenSupplyRequest {
+    /// The supply of the base58 encoded mint
+    pub fn new(mint: &str) -> Self {
+        GetTokenSupplyRequest {
+            mint: mint.to_owned(),
+            commitment: None,
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetTokenSupplyRequest {
+    type Response = RpcResult<TokenAmount>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetTokenSupply
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.mint.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.commitment
+            .map(|commitment| vec![("commitment", <&str>::from(commitment).into())])
+            .unwrap_or_default()
+    }
+}
+
+/// `getTokenLargestAccounts`, the 20 largest token accounts of a mint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetTokenLargestAccountsRequest {
+    mint: String,
+    commitment: Option<Commitment>,
+}
+
+impl GetTokenLargestAccountsRequest {
+    /// The largest accounts of the base58 encoded mint
+    pub fn new(mint: &str) -> Self {
+        GetTokenLargestAccountsRequest {
+            mint: mint.to_owned(),
+            commitment: None,
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetTokenLargestAccountsRequest {
+    type Response = RpcResult<Vec<TokenLargestAccount>>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetTokenLargestAccounts
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.mint.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.commitment
+            .map(|commitment| vec![("commitment", <&str>::from(commitment).into())])
+            .unwrap_or_default()
+    }
+}
+
+/// `getEpochInfo`, the progress of the current epoch
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetEpochInfoRequest {
+    config: CommonConfig,
+}
+
+impl GetEpochInfoRequest {
+    pub fn new() -> Self {
+        GetEpochInfoRequest::default()
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetEpochInfoRequest {
+    type Response = EpochInfo;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetEpochInfo
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.config.fields()
+    }
+}
+
+/// `getEpochSchedule`, cached by a default [crate::ResponseCache]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetEpochScheduleRequest;
+
+impl TypedRequest for GetEpochScheduleRequest {
+    type Response = EpochSchedule;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetEpochSchedule
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getSlotLeader`, the base58 encoded identity of the current leader
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetSlotLeaderRequest {
+    config: CommonConfig,
+}
+
+impl GetSlotLeaderRequest {
+    pub fn new() -> Self {
+        GetSlotLeaderRequest::default()
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetSlotLeaderRequest {
+    type Response = String;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetSlotLeader
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.config.fields()
+    }
+}
+
+/// `getSlotLeaders`, the base58 encoded identities of the leaders of consecutive slots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetSlotLeadersRequest {
+    start_slot: u64,
+    limit: u64,
+}
+
+impl GetSlotLeadersRequest {
+    /// The leaders of `limit` slots from `start_slot`, the node accepts 1 to 5000 slots
+    pub fn new(start_slot: u64, limit: u64) -> Self {
+        GetSlotLeadersRequest { start_slot, limit }
+    }
+}
+
+impl TypedRequest for GetSlotLeadersRequest {
+    type Response = Vec<String>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetSlotLeaders
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.start_slot.into())
+    }
+
+    fn params(&self) -> Vec<JsonValue> {
+        vec![self.limit.into()]
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getBlocks`, the confirmed slots between two slots, both included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetBlocksRequest {
+    start_slot: u64,
+    end_slot: Option<u64>,
+    commitment: Option<Commitment>,
+}
+
+impl GetBlocksRequest {
+    /// The confirmed slots from `start_slot` up to the latest confirmed slot,
+    /// the node returns at most 500,000 slots
+    pub fn new(start_slot: u64) -> Self {
+        GetBlocksRequest {
+            start_slot,
+            end_slot: None,
+            commitment: None,
+        }
+    }
+
+    pub fn change_end_slot(mut self, end_slot: u64) -> Self {
+        self.end_slot = Some(end_slot);
+
+        self
+    }
+
+    /// `confirmed` or `finalized`, the node rejects `processed`
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetBlocksRequest {
+    type Response = Vec<u64>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetBlocks
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.start_slot.into())
+    }
+
+    fn params(&self) -> Vec<JsonValue> {
+        self.end_slot.map(JsonValue::from).into_iter().collect()
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.commitment
+            .map(|commitment| vec![("commitment", <&str>::from(commitment).into())])
+            .unwrap_or_default()
+    }
+}
+
+/// `getBlocksWithLimit`, a number of confirmed slots starting at a slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetBlocksWithLimitRequest {
+    start_slot: u64,
+    limit: u64,
+    commitment: Option<Commitment>,
+}
+
+impl GetBlocksWithLimitRequest {
+    /// At most `limit` confirmed slots from `start_slot`, the node accepts up to 500,000
+    pub fn new(start_slot: u64, limit: u64) -> Self {
+        GetBlocksWithLimitRequest {
+            start_slot,
+            limit,
+            commitment: None,
+        }
+    }
+
+    /// `confirmed` or `finalized`, the node rejects `processed`
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetBlocksWithLimitRequest {
+    type Response = Vec<u64>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetBlocksWithLimit
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.start_slot.into())
+    }
+
+    fn params(&self) -> Vec<JsonValue> {
+        vec![self.limit.into()]
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.commitment
+            .map(|commitment| vec![("commitment", <&str>::from(commitment).into())])
+            .unwrap_or_default()
+    }
+}
+
+/// `getBlockTime`, the estimated production time of a block as a Unix timestamp,
+/// `None` if the node does not know it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetBlockTimeRequest {
+    slot: u64,
+}
+
+impl GetBlockTimeRequest {
+    pub fn new(slot: u64) -> Self {
+        GetBlockTimeRequest { slot }
+    }
+}
+
+impl TypedRequest for GetBlockTimeRequest {
+    type Response = Option<i64>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetBlockTime
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.slot.into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getVoteAccounts`, the current and delinquent vote accounts with their stake
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetVoteAccountsRequest {
+    vote_pubkey: Option<String>,
+    keep_unstaked_delinquents: Option<bool>,
+    delinquent_slot_distance: Option<u64>,
+    commitment: Option<Commitment>,
+}
+
+impl GetVoteAccountsRequest {
+    pub fn new() -> Self {
+        GetVoteAccountsRequest::default()
+    }
+
+    /// Only return the base58 encoded vote account
+    pub fn change_vote_pubkey(mut self, vote_pubkey: &str) -> Self {
+        self.vote_pubkey = Some(vote_pubkey.to_owned());
+
+        self
+    }
+
+    /// Include delinquent vote accounts without stake
+    pub fn change_keep_unstaked_delinquents(mut self, keep: bool) -> Self {
+        self.keep_unstaked_delinquents = Some(keep);
+
+        self
+    }
+
+    /// The number of slots a validator has to fall behind to be delinquent
+    pub fn change_delinquent_slot_distance(mut self, distance: u64) -> Self {
+        self.delinquent_slot_distance = Some(distance);
+
+        self
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetVoteAccountsRequest {
+    type Response = VoteAccounts;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetVoteAccounts
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = Vec::new();
+
+        if let Some(commitment) = self.commitment {
+            fields.push(("commitment", <&str>::from(commitment).into()));
+        }
+
+        if let Some(vote_pubkey) = self.vote_pubkey.as_deref() {
+            fields.push(("votePubkey", vote_pubkey.into()));
+        }
+
+        if let Some(keep) = self.keep_unstaked_delinquents {
+            fields.push(("keepUnstakedDelinquents", keep.into()));
+        }
+
+        if let Some(distance) = self.delinquent_slot_distance {
+            fields.push(("delinquentSlotDistance", distance.into()));
+        }
+
+        fields
+    }
+}
+
+/// `getSupply`, the total, circulating and non-circulating supply
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetSupplyRequest {
+    exclude_non_circulating_accounts_list: bool,
+    commitment: Option<Commitment>,
+}
+
+impl GetSupplyRequest {
+    pub fn new() -> Self {
+        GetSupplyRequest::default()
+    }
+
+    /// Leave out the list of non-circulating accounts, which is large on mainnet
+    pub fn change_exclude_non_circulating_accounts_list(mut self, exclude: bool) -> Self {
+        self.exclude_non_circulating_accounts_list = exclude;
+
+        self
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetSupplyRequest {
+    type Response = RpcResult<Supply>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetSupply
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = Vec::new();
+
+        if let Some(commitment) = self.commitment {
+            fields.push(("commitment", <&str>::from(commitment).into()));
+        }
+
+        if self.exclude_non_circulating_accounts_list {
+            fields.push(("excludeNonCirculatingAccountsList", true.into()));
+        }
+
+        fields
+    }
+}
+
+/// Selects the accounts of `getLargestAccounts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargestAccountsFilter {
+    Circulating,
+    NonCirculating,
+}
+
+/// `getLargestAccounts`, the 20 accounts holding the most lamports.
+/// The node may answer from a cache up to two hours old
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetLargestAccountsRequest {
+    filter: Option<LargestAccountsFilter>,
+    commitment: Option<Commitment>,
+}
+
+impl GetLargestAccountsRequest {
+    pub fn new() -> Self {
+        GetLargestAccountsRequest::default()
+    }
+
+    /// Only return circulating or non-circulating accounts
+    pub fn change_filter(mut self, filter: LargestAccountsFilter) -> Self {
+        self.filter = Some(filter);
+
+        self
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetLargestAccountsRequest {
+    type Response = RpcResult<Vec<LargestAccount>>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetLargestAccounts
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = Vec::new();
+
+        if let Some(commitment) = self.commitment {
+            fields.push(("commitment", <&str>::from(commitment).into()));
+        }
+
+        match self.filter {
+            Some(LargestAccountsFilter::Circulating) => {
+                fields.push(("filter", "circulating".into()))
+            }
+            Some(LargestAccountsFilter::NonCirculating) => {
+                fields.push(("filter", "nonCirculating".into()))
+            }
+            None => (),
+        }
+
+        fields
+    }
+}
+
+/// `getInflationGovernor`, the parameters of the inflation schedule
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetInflationGovernorRequest {
+    commitment: Option<Commitment>,
+}
+
+impl GetInflationGovernorRequest {
+    pub fn new() -> Self {
+        GetInflationGovernorRequest::default()
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetInflationGovernorRequest {
+    type Response = InflationGovernor;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetInflationGovernor
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.commitment
+            .map(|commitment| vec![("commitment", <&str>::from(commitment).into())])
+            .unwrap_or_default()
+    }
+}
+
+/// `getInflationRate`, the inflation rates of the current epoch
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetInflationRateRequest;
+
+impl TypedRequest for GetInflationRateRequest {
+    type Response = InflationRate;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetInflationRate
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getInflationReward`, the reward of each address for an epoch. The response
+/// holds `None` for addresses that were not rewarded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetInflationRewardRequest {
+    addresses: Vec<String>,
+    epoch: Option<u64>,
+    config: CommonConfig,
+}
+
+impl GetInflationRewardRequest {
+    /// The rewards of the base58 encoded addresses in the previous epoch
+    pub fn new(addresses: &[&str]) -> Self {
+        GetInflationRewardRequest {
+            addresses: addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect(),
+            epoch: None,
+            config: CommonConfig::default(),
+        }
+    }
+
+    /// The rewards of an earlier epoch instead of the previous one
+    pub fn change_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Some(epoch);
+
+        self
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetInflationRewardRequest {
+    type Response = Vec<Option<InflationReward>>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetInflationReward
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.addresses.clone().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = self.config.fields();
+
+        if let Some(epoch) = self.epoch {
+            fields.push(("epoch", epoch.into()));
+        }
+
+        fields
+    }
+}
+
+/// `getStakeActivation`, the activation of a stake account at an epoch.
+/// Nodes running solana-core 2.0 or newer no longer serve it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetStakeActivationRequest {
+    address: String,
+    epoch: Option<u64>,
+    config: CommonConfig,
+}
+
+impl GetStakeActivationRequest {
+    /// The activation of the base58 encoded stake account in the current epoch
+    pub fn new(address: &str) -> Self {
+        GetStakeActivationRequest {
+            address: address.to_owned(),
+            epoch: None,
+            config: CommonConfig::default(),
+        }
+    }
+
+    /// The activation at an earlier epoch instead of the current one
+    pub fn change_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Some(epoch);
+
+        self
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.config.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_min_context_slot(mut self, slot: u64) -> Self {
+        self.config.min_context_slot = Some(slot);
+
+        self
+    }
+}
+
+impl TypedRequest for GetStakeActivationRequest {
+    type Response = StakeActivation;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetStakeActivation
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        Some(self.address.as_str().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        let mut fields = self.config.fields();
+
+        if let Some(epoch) = self.epoch {
+            fields.push(("epoch", epoch.into()));
+        }
+
+        fields
+    }
+}
+
+/// `getStakeMinimumDelegation`, the smallest delegation a stake account accepts in lamports
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetStakeMinimumDelegationRequest {
+    commitment: Option<Commitment>,
+}
+
+impl GetStakeMinimumDelegationRequest {
+    pub fn new() -> Self {
+        GetStakeMinimumDelegationRequest::default()
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+}
+
+impl TypedRequest for GetStakeMinimumDelegationRequest {
+    type Response = RpcResult<u64>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetStakeMinimumDelegation
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        self.commitment
+            .map(|commitment| vec![("commitment", <&str>::from(commitment).into())])
+            .unwrap_or_default()
+    }
+}
+
+/// `getRecentPerformanceSamples`, the most recent samples first
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetRecentPerformanceSamplesRequest {
+    limit: Option<usize>,
+}
+
+impl GetRecentPerformanceSamplesRequest {
+    pub fn new() -> Self {
+        GetRecentPerformanceSamplesRequest::default()
+    }
+
+    /// Return at most `limit` samples, capped at [crate::MAX_PERFORMANCE_SAMPLES]
+    pub fn change_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit.min(MAX_PERFORMANCE_SAMPLES));
+
+        self
+    }
+}
+
+impl TypedRequest for GetRecentPerformanceSamplesRequest {
+    type Response = Vec<PerfSample>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetRecentPerformanceSamples
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        self.limit.map(|limit| limit.into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getHealth`, `"ok"` on a healthy node. An unhealthy node answers with an
+/// [AtollError::Rpc], interpret the response with [crate::NodeHealth::from_response]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetHealthRequest;
+
+impl TypedRequest for GetHealthRequest {
+    type Response = String;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetHealth
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getIdentity`, the identity of the node
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetIdentityRequest;
+
+impl TypedRequest for GetIdentityRequest {
+    type Response = NodeIdentity;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetIdentity
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getGenesisHash`, the base58 encoded genesis hash of the cluster the node belongs to
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetGenesisHashRequest;
+
+impl TypedRequest for GetGenesisHashRequest {
+    type Response = String;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetGenesisHash
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getVersion`, the `solana-core` version and feature set of the node
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetVersionRequest;
+
+impl TypedRequest for GetVersionRequest {
+    type Response = NodeVersion;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetVersion
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getFirstAvailableBlock`, the lowest confirmed block the node has not purged
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetFirstAvailableBlockRequest;
+
+impl TypedRequest for GetFirstAvailableBlockRequest {
+    type Response = u64;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetFirstAvailableBlock
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `minimumLedgerSlot`, the lowest slot the node has information about in its ledger
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MinimumLedgerSlotRequest;
+
+impl TypedRequest for MinimumLedgerSlotRequest {
+    type Response = u64;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::MinimumLedgerSlot
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getHighestSnapshotSlot`, the highest full and incremental snapshots of the node.
+/// A node without snapshots answers with an [AtollError::Rpc]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetHighestSnapshotSlotRequest;
+
+impl TypedRequest for GetHighestSnapshotSlotRequest {
+    type Response = SnapshotSlot;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetHighestSnapshotSlot
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getMaxRetransmitSlot`, the highest slot the node has seen from retransmit stage
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetMaxRetransmitSlotRequest;
+
+impl TypedRequest for GetMaxRetransmitSlotRequest {
+    type Response = u64;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetMaxRetransmitSlot
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getMaxShredInsertSlot`, the highest slot the node has seen after shred insert
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetMaxShredInsertSlotRequest;
+
+impl TypedRequest for GetMaxShredInsertSlotRequest {
+    type Response = u64;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetMaxShredInsertSlot
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        None
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
+
+/// `getRecentPrioritizationFees`, the fees of the recent slots the node still
+/// caches, around 150. With accounts each fee is the lowest one a transaction
+/// locking all of them for writing paid
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetRecentPrioritizationFeesRequest {
+    addresses: Vec<String>,
+}
+
+impl GetRecentPrioritizationFeesRequest {
+    pub fn new() -> Self {
+        GetRecentPrioritizationFeesRequest::default()
+    }
+
+    /// The fees for up to [MAX_PRIORITIZATION_FEE_ACCOUNTS] base58 encoded addresses
+    pub fn change_addresses(mut self, addresses: &[&str]) -> AtollResult<Self> {
+        if addresses.len() > MAX_PRIORITIZATION_FEE_ACCOUNTS {
+            return Err(AtollError::TooManyAccounts {
+                max: MAX_PRIORITIZATION_FEE_ACCOUNTS,
+                received: addresses.len(),
+            });
+        }
+
+        self.addresses = addresses
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+
+        Ok(self)
+    }
+}
+
+impl TypedRequest for GetRecentPrioritizationFeesRequest {
+    type Response = Vec<PrioritizationFee>;
+
+    fn method(&self) -> RpcMethod {
+        RpcMethod::GetRecentPrioritizationFees
+    }
+
+    fn value(&self) -> Option<JsonValue> {
+        (!self.addresses.is_empty()).then(|| self.addresses.clone().into())
+    }
+
+    fn config(&self) -> Vec<(&'static str, JsonValue)> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{AtollError, AtollResult, HttpTransport, TransportRequest, TransportResponse};
+use std::io::Read;
+use web3utilities::{IoErrorKind, UtilitiesError};
+
+/// Sends requests with a blocking `ureq` agent, sharing its connection pool and TLS settings
+#[derive(Debug, Clone)]
+pub struct UreqTransport {
+    agent: ureq::Agent,
+}
+
+impl Default for UreqTransport {
+    fn default() -> Self {
+        UreqTransport::new(ureq::Agent::new())
+    }
+}
+
+impl UreqTransport {
+    /// Send requests with an existing agent
+    pub fn new(agent: ureq::Agent) -> Self {
+        UreqTransport { agent }
+    }
+
+    fn error(error: ureq::Transport) -> AtollError {
+        match error.kind() {
+            ureq::ErrorKind::Dns | ureq::ErrorKind::ConnectionFailed => {
+                AtollError::Utilities(UtilitiesError::Io(IoErrorKind::ConnectionRefused))
+            }
+            ureq::ErrorKind::Io => {
+                match std::error::Error::source(&error)
+                    .and_then(|source| source.downcast_ref::<std::io::Error>())
+                {
+                    Some(io_error) => {
+                        AtollError::Utilities(std::io::Error::from(io_error.kind()).into())
+                    }
+                    None => AtollError::Transport(error.to_string()),
+                }
+            }
+            _ => AtollError::Transport(error.to_string()),
+        }
+    }
+}
+
+impl HttpTransport for UreqTransport {
+    fn post_blocking(&self, request: &TransportRequest) -> AtollResult<TransportResponse> {
+        let outcome = request
+            .headers
+            .iter()
+            .fold(
+                self.agent.post(&request.url).timeout(request.timeout),
+                |builder, (key, value)| builder.set(key, value),
+            )
+            .send_string(&request.body);
+
+        // Error statuses are responses too, the retry policy decides what to do with them
+        let response = match outcome {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(error)) => return Err(Self::error(error)),
+        };
+
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|key| {
+                let value = response.header(&key)?.to_owned();
+
+                Some((key.to_lowercase(), value))
+            })
+            .collect();
+        let status_code = response.status();
+        let reason_phrase = response.status_text().to_owned();
+
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(|error| AtollError::Utilities(error.into()))?;
+
+        Ok(TransportResponse {
+            status_code,
+            reason_phrase,
+            headers,
+            body,
+        })
+    }
+}
@@ -0,0 +1,117 @@
+//! The async runtime used to run blocking HTTP calls, timers and background tasks,
+//! selected with the `rt-smol` (default), `rt-tokio` or `wasm` cargo features.
+//! When several are enabled `wasm` is used first, then tokio
+
+use core::{future::Future, pin::Pin};
+use std::time::Duration;
+
+#[cfg(not(any(feature = "rt-smol", feature = "rt-tokio", feature = "wasm")))]
+compile_error!("Enable either the `rt-smol`, `rt-tokio` or `wasm` feature of atoll");
+
+/// `std::time` panics on `wasm32-unknown-unknown`, `web-time` reads the clock of the browser
+#[cfg(not(feature = "wasm"))]
+pub use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "wasm")]
+pub use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// A boxed future, `Send` except on wasm where futures of JavaScript promises are not
+#[cfg(not(feature = "wasm"))]
+pub(crate) type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+#[cfg(feature = "wasm")]
+pub(crate) type BoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Run a blocking closure, like a `minreq` call, on the blocking thread pool of the runtime
+#[cfg(all(feature = "rt-tokio", not(feature = "wasm")))]
+pub(crate) async fn unblock<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(value) => value,
+        Err(error) => std::panic::resume_unwind(error.into_panic()),
+    }
+}
+
+/// Run a blocking closure, like a `minreq` call, on the blocking thread pool of the runtime
+#[cfg(all(feature = "rt-smol", not(any(feature = "rt-tokio", feature = "wasm"))))]
+pub(crate) async fn unblock<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    smol::unblock(f).await
+}
+
+#[cfg(all(feature = "rt-tokio", not(feature = "wasm")))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(all(feature = "rt-smol", not(any(feature = "rt-tokio", feature = "wasm"))))]
+pub(crate) async fn sleep(duration: Duration) {
+    smol::Timer::after(duration).await;
+}
+
+/// Run a future in the background, the returned future resolves to its output
+#[cfg(all(feature = "rt-tokio", not(feature = "wasm")))]
+pub(crate) fn spawn<T, F>(future: F) -> impl Future<Output = T>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    let task = tokio::spawn(future);
+
+    async move {
+        match task.await {
+            Ok(value) => value,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+}
+
+/// Run a future in the background, the returned future resolves to its output
+#[cfg(all(feature = "rt-smol", not(any(feature = "rt-tokio", feature = "wasm"))))]
+pub(crate) fn spawn<T, F>(future: F) -> impl Future<Output = T>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    smol::spawn(future)
+}
+
+/// Run a closure inline, wasm has no threads to move blocking calls to
+#[cfg(feature = "wasm")]
+pub(crate) async fn unblock<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    f()
+}
+
+#[cfg(feature = "wasm")]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+/// Run a future in the background on the JavaScript event loop
+#[cfg(feature = "wasm")]
+pub(crate) fn spawn<T, F>(future: F) -> impl Future<Output = T>
+where
+    T: 'static,
+    F: Future<Output = T> + 'static,
+{
+    let (sender, receiver) = async_channel::bounded(1);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        sender.send(future.await).await.ok();
+    });
+
+    async move {
+        match receiver.recv().await {
+            Ok(value) => value,
+            Err(_) => panic!("The spawned task was dropped before it completed"),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{
+    AccountLifecycleEvent, Block, GetAccountInfo, KeyedAccount, Pubkey, SupplyChangeEvent,
+    TransferEvent, TxWithMeta,
+};
+use borsh::{schema::BorshSchemaContainer, BorshDeserialize, BorshSchema, BorshSerialize};
+
+/// The version of the Borsh layout of the types listed in [AtollSchema].
+/// Within a layout version fields are never reordered, removed or retyped
+/// and enum variants are only ever appended, so bytes written by an older
+/// release decode with a newer one. Any other change bumps this version
+pub const BORSH_LAYOUT_VERSION: u32 = 1;
+
+/// Describes the Borsh layout of the blocks, accounts and events serialized by Atoll
+/// so that services written in other languages can decode them.
+/// The schema is itself Borsh serializable, see [AtollSchema::to_bytes]
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct AtollSchema {
+    pub layout_version: u32,
+    /// The schema of each exported type with the definitions of the types it contains
+    pub types: Vec<BorshSchemaContainer>,
+}
+
+impl AtollSchema {
+    /// The schema of every type with a stable Borsh layout
+    pub fn export() -> Self {
+        AtollSchema {
+            layout_version: BORSH_LAYOUT_VERSION,
+            types: vec![
+                Block::schema_container(),
+                TxWithMeta::schema_container(),
+                GetAccountInfo::schema_container(),
+                KeyedAccount::schema_container(),
+                Pubkey::schema_container(),
+                TransferEvent::schema_container(),
+                SupplyChangeEvent::schema_container(),
+                AccountLifecycleEvent::schema_container(),
+            ],
+        }
+    }
+
+    /// The schema of a single type by the name of its declaration, like `Block`
+    pub fn get(&self, declaration: &str) -> Option<&BorshSchemaContainer> {
+        self.types
+            .iter()
+            .find(|container| container.declaration == declaration)
+    }
+
+    /// The Borsh encoded schema, to be published next to serialized data
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Serializing into a `Vec` cannot fail
+        self.try_to_vec().unwrap_or_default()
+    }
+}
+
+/// Serialize a value prefixed with the Borsh schema of its type,
+/// making the bytes self-describing
+pub fn to_vec_with_schema<T: BorshSerialize + BorshSchema>(value: &T) -> std::io::Result<Vec<u8>> {
+    borsh::try_to_vec_with_schema(value)
+}
+
+/// Deserialize bytes written by [to_vec_with_schema], checking that the embedded
+/// schema matches the schema of `T`
+pub fn from_slice_with_schema<T: BorshDeserialize + BorshSchema>(
+    bytes: &[u8],
+) -> std::io::Result<T> {
+    borsh::try_from_slice_with_schema(bytes)
+}
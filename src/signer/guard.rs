@@ -0,0 +1,104 @@
+use crate::{AtollError, AtollResult, Commitment, WireMessage};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// The default maximum number of slots between fetching a blockhash and signing with it.
+/// A blockhash expires after 150 blocks, this leaves room for the transaction to land.
+pub const DEFAULT_MAX_BLOCKHASH_SLOT_DISTANCE: u64 = 100;
+
+/// A blockhash together with how and when it was fetched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchedBlockhash {
+    /// The base58 encoded blockhash
+    pub blockhash: String,
+    /// The commitment used to fetch the blockhash
+    pub commitment: Commitment,
+    /// The `context.slot` of the response the blockhash was fetched from
+    pub slot: u64,
+}
+
+/// Refuses to sign messages whose blockhash is guaranteed or likely to expire before
+/// the transaction lands, because it was fetched at a lower commitment than configured
+/// or too many slots have passed since it was fetched
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockhashGuard {
+    min_commitment: Commitment,
+    max_slot_distance: u64,
+}
+
+impl Default for BlockhashGuard {
+    fn default() -> Self {
+        BlockhashGuard::new()
+    }
+}
+
+impl BlockhashGuard {
+    /// Require at least `Confirmed` commitment and at most
+    /// [DEFAULT_MAX_BLOCKHASH_SLOT_DISTANCE] slots since the blockhash was fetched
+    pub fn new() -> Self {
+        BlockhashGuard {
+            min_commitment: Commitment::Confirmed,
+            max_slot_distance: DEFAULT_MAX_BLOCKHASH_SLOT_DISTANCE,
+        }
+    }
+
+    /// Change the lowest commitment a blockhash may have been fetched at
+    pub fn change_min_commitment(mut self, commitment: Commitment) -> Self {
+        self.min_commitment = commitment;
+
+        self
+    }
+
+    /// Change the maximum number of slots since the blockhash was fetched
+    pub fn change_max_slot_distance(mut self, slots: u64) -> Self {
+        self.max_slot_distance = slots;
+
+        self
+    }
+
+    /// Check the blockhash before signing a serialized message. `current_slot` is the
+    /// latest slot known to the signer, for example from `getSlot`
+    pub fn check_message(
+        &self,
+        message: &[u8],
+        fetched: &FetchedBlockhash,
+        current_slot: u64,
+    ) -> AtollResult<()> {
+        let message = WireMessage::from_message_bytes(message)?;
+
+        if message.recent_blockhash != fetched.blockhash {
+            return Err(AtollError::BlockhashRejected(format!(
+                "The message uses blockhash {} but {} was fetched",
+                message.recent_blockhash, fetched.blockhash
+            )));
+        }
+
+        self.check(fetched, current_slot)
+    }
+
+    /// Check a fetched blockhash without a message
+    pub fn check(&self, fetched: &FetchedBlockhash, current_slot: u64) -> AtollResult<()> {
+        if fetched.commitment == Commitment::InvalidCommitment
+            || fetched.commitment < self.min_commitment
+        {
+            return Err(AtollError::BlockhashRejected(format!(
+                "The blockhash was fetched at {:?} commitment, at least {:?} is required",
+                fetched.commitment, self.min_commitment
+            )));
+        }
+
+        let distance = current_slot.saturating_sub(fetched.slot);
+        if distance > self.max_slot_distance {
+            return Err(AtollError::BlockhashRejected(format!(
+                "The blockhash was fetched {} slots ago, the maximum allowed is {}",
+                distance, self.max_slot_distance
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{AtollError, AtollResult, Pubkey};
+use core::fmt;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use web3utilities::{
+    ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey},
+    Utilities,
+};
+use zeroize::Zeroize;
+
+/// Secret bytes that are zeroized when dropped and never printed by `fmt::Debug`.
+/// All secret buffers of the signer subsystem are wrapped in this type.
+pub struct SecretBytes<const N: usize>([u8; N]);
+
+impl<const N: usize> SecretBytes<N> {
+    /// Take ownership of secret bytes
+    pub fn new(bytes: [u8; N]) -> Self {
+        SecretBytes(bytes)
+    }
+
+    /// Copy secret bytes from a slice. The caller remains responsible for zeroizing the slice
+    pub fn from_slice(bytes: &[u8]) -> AtollResult<Self> {
+        if bytes.len() != N {
+            return Err(AtollError::InvalidKeypair(format!(
+                "Expected {} secret bytes but got {}",
+                N,
+                bytes.len()
+            )));
+        }
+
+        let mut secret = [0u8; N];
+        secret.copy_from_slice(bytes);
+
+        Ok(SecretBytes(secret))
+    }
+
+    /// Access the secret bytes. Avoid copying them out of the returned reference
+    pub fn expose_secret(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> Zeroize for SecretBytes<N> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<const N: usize> Drop for SecretBytes<N> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<const N: usize> fmt::Debug for SecretBytes<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes<{}>(REDACTED)", N)
+    }
+}
+
+/// An Ed25519 keypair. The secret key is zeroized when the keypair is dropped
+/// and only the public key is shown by `fmt::Debug`
+pub struct Keypair {
+    secret: SecretBytes<32>,
+    public: Pubkey,
+}
+
+impl Keypair {
+    /// Generate a new random keypair
+    pub fn generate() -> Self {
+        let secret = SecretBytes::new(Utilities::rand32_chacha20());
+
+        Keypair::from_secret(secret).unwrap() // Never fails since any 32 bytes are a valid secret key
+    }
+
+    /// Create a keypair from a 32 byte secret key (seed)
+    pub fn from_secret(secret: SecretBytes<32>) -> AtollResult<Self> {
+        let secret_key = SecretKey::from_bytes(secret.expose_secret())
+            .map_err(|error| AtollError::InvalidKeypair(error.to_string()))?;
+        let public = Pubkey(PublicKey::from(&secret_key).to_bytes());
+
+        Ok(Keypair { secret, public })
+    }
+
+    /// Create a keypair from 64 bytes containing the secret key followed by the public key.
+    /// Returns an error if the public key does not belong to the secret key
+    pub fn from_bytes(bytes: &[u8]) -> AtollResult<Self> {
+        if bytes.len() != 64 {
+            return Err(AtollError::InvalidKeypair(format!(
+                "Expected 64 keypair bytes but got {}",
+                bytes.len()
+            )));
+        }
+
+        let keypair = Keypair::from_secret(SecretBytes::from_slice(&bytes[..32])?)?;
+
+        if keypair.public.0 != bytes[32..] {
+            return Err(AtollError::InvalidKeypair(
+                "The public key does not match the secret key".to_owned(),
+            ));
+        }
+
+        Ok(keypair)
+    }
+
+    /// The secret key followed by the public key
+    pub fn to_bytes(&self) -> SecretBytes<64> {
+        let mut bytes = SecretBytes::new([0u8; 64]);
+        bytes.0[..32].copy_from_slice(self.secret.expose_secret());
+        bytes.0[32..].copy_from_slice(&self.public.0);
+
+        bytes
+    }
+
+    /// The 32 byte secret key (seed)
+    pub fn secret(&self) -> &SecretBytes<32> {
+        &self.secret
+    }
+
+    /// The public key
+    pub fn pubkey(&self) -> Pubkey {
+        self.public
+    }
+
+    /// Sign a message, returning the 64 byte Ed25519 signature
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        let secret_key = SecretKey::from_bytes(self.secret.expose_secret()).unwrap(); // Never fails since the secret was validated on creation
+        let public_key = PublicKey::from(&secret_key);
+
+        ExpandedSecretKey::from(&secret_key)
+            .sign(message, &public_key)
+            .to_bytes()
+    }
+
+    /// Parse the JSON array of the 64 keypair bytes written by `solana-keygen`
+    pub fn from_json(json: &str) -> AtollResult<Self> {
+        let mut bytes = serde_json::from_str::<Vec<u8>>(json).map_err(|_| {
+            AtollError::InvalidKeypair("Expected a JSON array of 64 bytes".to_owned())
+        })?;
+        let keypair = Keypair::from_bytes(&bytes);
+        bytes.zeroize();
+
+        keypair
+    }
+
+    /// The keypair bytes as the JSON array `solana-keygen` writes. The returned
+    /// string holds the secret key, zeroize it once written
+    pub fn to_json(&self) -> String {
+        let bytes = self.to_bytes();
+
+        format!(
+            "[{}]",
+            bytes
+                .expose_secret()
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<String>>()
+                .join(",")
+        )
+    }
+
+    /// Read a keypair file in the format of `solana-keygen`, like `~/.config/solana/id.json`
+    pub fn read_from_file(path: impl AsRef<Path>) -> AtollResult<Self> {
+        let mut json = fs::read_to_string(path).map_err(io_error)?;
+        let keypair = Keypair::from_json(&json);
+        json.zeroize();
+
+        keypair
+    }
+
+    /// Write the keypair in the format of `solana-keygen`, creating the parent
+    /// directories. Fails if the file exists so a keypair is never overwritten by
+    /// accident, see [Self::overwrite_file]. On Unix the file is only readable by its owner
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> AtollResult<()> {
+        self.write(path.as_ref(), false)
+    }
+
+    /// [Self::write_to_file] replacing the file if it exists, like `solana-keygen --force`
+    pub fn overwrite_file(&self, path: impl AsRef<Path>) -> AtollResult<()> {
+        self.write(path.as_ref(), true)
+    }
+
+    fn write(&self, path: &Path, overwrite: bool) -> AtollResult<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true);
+        match overwrite {
+            true => options.create(true).truncate(true),
+            false => options.create_new(true),
+        };
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut json = self.to_json();
+        let written = options
+            .open(path)
+            .and_then(|mut file| file.write_all(json.as_bytes()))
+            .map_err(io_error);
+        json.zeroize();
+
+        written
+    }
+}
+
+/// The keypair the Solana CLI uses unless configured otherwise,
+/// `~/.config/solana/id.json`. `None` if the home directory is unknown
+pub fn default_keypair_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| {
+            PathBuf::from(home)
+                .join(".config")
+                .join("solana")
+                .join("id.json")
+        })
+}
+
+fn io_error(error: std::io::Error) -> AtollError {
+    AtollError::Io(error.to_string())
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair")
+            .field("pubkey", &self.public)
+            .field("secret", &self.secret)
+            .finish()
+    }
+}
@@ -0,0 +1,353 @@
+use crate::{AtollError, AtollResult, Keypair, SecretBytes};
+use core::{fmt, str::FromStr};
+use sha2::{Digest, Sha256, Sha512};
+use std::sync::OnceLock;
+use zeroize::Zeroize;
+
+/// The BIP44 coin type of Solana
+pub const SOLANA_COIN_TYPE: u32 = 501;
+
+/// The PBKDF2 rounds BIP39 stretches a mnemonic with
+const BIP39_ROUNDS: u32 = 2048;
+
+/// Marks an index as hardened, the only kind SLIP-0010 derives for ed25519
+const HARDENED: u32 = 0x8000_0000;
+
+/// The BIP39 English wordlist, one word per line in sorted order
+const ENGLISH_WORDLIST: &str = include_str!("bip39_english.txt");
+
+/// The bits of the wordlist index a word encodes
+const BITS_PER_WORD: usize = 11;
+
+/// A SLIP-0010 derivation path like `m/44'/501'/0'/0'`. Every index is hardened
+/// since ed25519 keys cannot be derived otherwise
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// The path of the account in Phantom and most wallets, `m/44'/501'/account'/0'`
+    pub fn solana(account: u32) -> Self {
+        DerivationPath(vec![44, SOLANA_COIN_TYPE, account, 0])
+    }
+
+    /// The path `solana-keygen --derivation-path` uses, `m/44'/501'` followed
+    /// by the account and change indexes given
+    pub fn solana_keygen(account: Option<u32>, change: Option<u32>) -> Self {
+        let mut indexes = vec![44, SOLANA_COIN_TYPE];
+        indexes.extend(account);
+        indexes.extend(account.and(change));
+
+        DerivationPath(indexes)
+    }
+
+    /// The indexes without the hardened bit
+    pub fn indexes(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = AtollError;
+
+    /// Parse a path like `m/44'/501'/0'/0'`, `h` or `H` may mark hardened indexes
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            AtollError::InvalidKeypair(format!("Invalid derivation path `{value}`, {reason}"))
+        };
+
+        let mut parts = value.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid("it must start with `m`"));
+        }
+
+        parts
+            .map(|part| {
+                let index = part
+                    .strip_suffix(['\'', 'h', 'H'])
+                    .ok_or_else(|| invalid("ed25519 only derives hardened indexes like `0'`"))?;
+
+                index
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|index| *index < HARDENED)
+                    .ok_or_else(|| invalid("an index is not a number below 2^31"))
+            })
+            .collect::<AtollResult<Vec<u32>>>()
+            .map(DerivationPath)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        self.0.iter().try_for_each(|index| write!(f, "/{index}'"))
+    }
+}
+
+impl fmt::Debug for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DerivationPath")
+            .field(&self.to_string())
+            .finish()
+    }
+}
+
+/// The BIP39 seed of a mnemonic and optional passphrase. The mnemonic is checked
+/// with [validate_mnemonic] first. Only ASCII mnemonics and passphrases are accepted
+/// since the Unicode normalization BIP39 requires for other text is not performed
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> AtollResult<SecretBytes<64>> {
+    if !mnemonic.is_ascii() || !passphrase.is_ascii() {
+        return Err(AtollError::InvalidKeypair(
+            "Only ASCII mnemonics and passphrases are supported".to_owned(),
+        ));
+    }
+
+    validate_mnemonic(mnemonic)?;
+
+    let mut words = mnemonic.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let mut salt = format!("mnemonic{passphrase}").into_bytes();
+    let seed = pbkdf2_sha512(words.as_bytes(), &salt, BIP39_ROUNDS);
+    words.zeroize();
+    salt.zeroize();
+
+    Ok(seed)
+}
+
+/// Check that every word of the mnemonic is in the BIP39 English wordlist and
+/// that the checksum in its last bits matches the entropy, which catches most
+/// mistyped or swapped words
+pub fn validate_mnemonic(mnemonic: &str) -> AtollResult<()> {
+    let words = mnemonic.split_whitespace().collect::<Vec<&str>>();
+    if ![12, 15, 18, 21, 24].contains(&words.len()) {
+        return Err(AtollError::InvalidKeypair(
+            "A mnemonic has 12, 15, 18, 21 or 24 words".to_owned(),
+        ));
+    }
+
+    let mut bits = Vec::with_capacity(words.len() * BITS_PER_WORD);
+    for (position, word) in words.iter().enumerate() {
+        let Ok(index) = english_wordlist().binary_search(word) else {
+            bits.zeroize();
+
+            return Err(AtollError::InvalidKeypair(format!(
+                "Word {} of the mnemonic is not in the BIP39 English wordlist",
+                position + 1
+            )));
+        };
+
+        bits.extend((0..BITS_PER_WORD).rev().map(|bit| (index >> bit) & 1 == 1));
+    }
+
+    // Every 32 bits of entropy carry one bit of checksum
+    let checksum_bits = bits.len() / 33;
+    let (entropy_bits, checksum) = bits.split_at(bits.len() - checksum_bits);
+
+    let mut entropy = entropy_bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |byte, bit| byte << 1 | *bit as u8))
+        .collect::<Vec<u8>>();
+    let mut hash = Sha256::digest(&entropy);
+
+    let valid = checksum
+        .iter()
+        .enumerate()
+        .all(|(index, bit)| (hash[index / 8] >> (7 - index % 8)) & 1 == *bit as u8);
+    entropy.zeroize();
+    hash.zeroize();
+    bits.zeroize();
+
+    match valid {
+        true => Ok(()),
+        false => Err(AtollError::InvalidKeypair(
+            "The checksum of the mnemonic does not match, a word may be mistyped".to_owned(),
+        )),
+    }
+}
+
+fn english_wordlist() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+    WORDS.get_or_init(|| ENGLISH_WORDLIST.lines().collect())
+}
+
+impl Keypair {
+    /// Derive the keypair of a BIP39 mnemonic at the SLIP-0010 path, matching
+    /// Phantom with [DerivationPath::solana] and `solana-keygen --derivation-path`
+    /// with [DerivationPath::solana_keygen]. See [mnemonic_to_seed] for the
+    /// mnemonics accepted
+    pub fn from_mnemonic(
+        mnemonic: &str,
+        passphrase: &str,
+        path: &DerivationPath,
+    ) -> AtollResult<Self> {
+        let seed = mnemonic_to_seed(mnemonic, passphrase)?;
+
+        Keypair::from_secret(derive_ed25519(seed.expose_secret(), path))
+    }
+
+    /// The keypair `solana-keygen` recovers from a mnemonic without a derivation
+    /// path, the first 32 bytes of the seed
+    pub fn from_mnemonic_seed(mnemonic: &str, passphrase: &str) -> AtollResult<Self> {
+        let seed = mnemonic_to_seed(mnemonic, passphrase)?;
+
+        Keypair::from_secret(SecretBytes::from_slice(&seed.expose_secret()[..32])?)
+    }
+}
+
+/// SLIP-0010 derivation of the ed25519 secret key at the path
+fn derive_ed25519(seed: &[u8], path: &DerivationPath) -> SecretBytes<32> {
+    let mut node = hmac_sha512(b"ed25519 seed", &[seed]);
+
+    for index in path.indexes() {
+        let (key, chain_code) = node.expose_secret().split_at(32);
+        node = hmac_sha512(chain_code, &[&[0], key, &(index | HARDENED).to_be_bytes()]);
+    }
+
+    let mut secret = [0u8; 32];
+    secret.copy_from_slice(&node.expose_secret()[..32]);
+
+    SecretBytes::new(secret)
+}
+
+/// HMAC-SHA512, RFC 2104, of the concatenated data
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> SecretBytes<64> {
+    let mut block = [0u8; 128];
+    if key.len() > block.len() {
+        block[..64].copy_from_slice(&Sha512::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha512::new();
+    inner.update(block.map(|byte| byte ^ 0x36));
+    data.iter().for_each(|data| inner.update(data));
+    let mut inner_hash = inner.finalize();
+
+    let mut outer = Sha512::new();
+    outer.update(block.map(|byte| byte ^ 0x5c));
+    outer.update(inner_hash);
+
+    let mut mac = [0u8; 64];
+    mac.copy_from_slice(&outer.finalize());
+    block.zeroize();
+    inner_hash.zeroize();
+
+    SecretBytes::new(mac)
+}
+
+/// PBKDF2-HMAC-SHA512, RFC 8018, of a single 64 byte block
+fn pbkdf2_sha512(password: &[u8], salt: &[u8], rounds: u32) -> SecretBytes<64> {
+    let mut round = hmac_sha512(password, &[salt, &1u32.to_be_bytes()]);
+    let mut output = *round.expose_secret();
+
+    for _ in 1..rounds {
+        round = hmac_sha512(password, &[round.expose_secret()]);
+        output
+            .iter_mut()
+            .zip(round.expose_secret())
+            .for_each(|(output, byte)| *output ^= byte);
+    }
+
+    let seed = SecretBytes::new(output);
+    output.zeroize();
+
+    seed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(value: &str) -> Vec<u8> {
+        (0..value.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&value[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn english_wordlist_is_complete_and_sorted() {
+        let words = english_wordlist();
+
+        assert_eq!(words.len(), 2048);
+        assert!(words.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    /// The vectors of the BIP39 reference implementation, with the passphrase `TREZOR`
+    #[test]
+    fn seeds_match_trezor_vectors() {
+        let vectors = [
+            (
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+                "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            ),
+            (
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+                "d71de856f81a8acc65e6fc851a38d4d7ec216fd0796d0a6827a3ad6ed5511a30fa280f12eb2e47ed2ac03b5c462a0358d18d69fe4f985ec81778c1b370b652a8",
+            ),
+            (
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+                "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+            ),
+            (
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+                "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+            ),
+        ];
+
+        for (mnemonic, seed) in vectors {
+            assert_eq!(
+                mnemonic_to_seed(mnemonic, "TREZOR")
+                    .unwrap()
+                    .expose_secret()
+                    .to_vec(),
+                hex(seed)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_mnemonics() {
+        // Twelve valid words with a wrong checksum
+        assert!(validate_mnemonic(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon"
+        )
+        .is_err());
+        // A word that is not in the wordlist
+        assert!(validate_mnemonic(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abaft"
+        )
+        .is_err());
+        assert!(validate_mnemonic("abandon abandon about").is_err());
+    }
+
+    /// SLIP-0010 test vector 1 for ed25519
+    #[test]
+    fn derives_slip10_vector_1() {
+        let seed = hex("000102030405060708090a0b0c0d0e0f");
+        let vectors = [
+            (
+                DerivationPath(Vec::new()),
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+            ),
+            (
+                DerivationPath(vec![0, 1, 2, 2, 1_000_000_000]),
+                "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+                "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+            ),
+        ];
+
+        for (path, secret, public) in vectors {
+            let derived = derive_ed25519(&seed, &path);
+            assert_eq!(derived.expose_secret().to_vec(), hex(secret));
+
+            let keypair = Keypair::from_secret(derived).unwrap();
+            assert_eq!(keypair.pubkey().to_bytes().to_vec(), hex(public));
+        }
+    }
+}
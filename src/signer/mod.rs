@@ -0,0 +1,20 @@
+mod policy;
+pub use policy::*;
+
+mod preview;
+pub use preview::*;
+
+mod guard;
+pub use guard::*;
+
+mod keypair;
+pub use keypair::*;
+
+mod verify;
+pub use verify::*;
+
+mod mnemonic;
+pub use mnemonic::*;
+
+mod signing;
+pub use signing::*;
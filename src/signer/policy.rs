@@ -0,0 +1,197 @@
+use crate::{AtollError, AtollResult, Pubkey, SignFuture, Signer, WireMessage};
+use std::collections::BTreeSet;
+
+/// A rule about the shape of instructions invoking a program
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InstructionRule {
+    /// Refuse instructions of `program_id` whose data starts with `discriminator`,
+    /// for example `[6]` to refuse SPL Token `SetAuthority`
+    Deny {
+        program_id: String,
+        discriminator: Vec<u8>,
+    },
+    /// Refuse instructions of `program_id` that reference more than `max` accounts
+    MaxAccounts { program_id: String, max: usize },
+    /// Refuse instructions of `program_id` whose data is longer than `max` bytes
+    MaxDataLength { program_id: String, max: usize },
+}
+
+/// A safety net for automated signers. The program ids and instruction shapes of a
+/// message are checked against allow and deny rules before the message is signed
+/// and signing is refused with [AtollError::PolicyViolation] if any rule is violated.
+/// Wrap the signer in a [PolicySigner] to enforce it on every signature
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SigningPolicy {
+    allowed_programs: Option<BTreeSet<String>>,
+    denied_programs: BTreeSet<String>,
+    instruction_rules: Vec<InstructionRule>,
+    max_instructions: Option<usize>,
+}
+
+impl SigningPolicy {
+    /// Create a policy that allows everything
+    pub fn new() -> Self {
+        SigningPolicy::default()
+    }
+
+    /// Only allow the programs added to the allowlist. Once a program has been added,
+    /// any program that is not in the allowlist is refused
+    pub fn add_allowed_program(mut self, program_id: &str) -> Self {
+        self.allowed_programs
+            .get_or_insert_with(BTreeSet::new)
+            .insert(program_id.to_owned());
+
+        self
+    }
+
+    /// Refuse any message invoking this program. The denylist takes precedence over the allowlist
+    pub fn add_denied_program(mut self, program_id: &str) -> Self {
+        self.denied_programs.insert(program_id.to_owned());
+
+        self
+    }
+
+    /// Add a rule about the shape of instructions
+    pub fn add_instruction_rule(mut self, rule: InstructionRule) -> Self {
+        self.instruction_rules.push(rule);
+
+        self
+    }
+
+    /// Refuse messages with more than `max` top-level instructions
+    pub fn change_max_instructions(mut self, max: usize) -> Self {
+        self.max_instructions = Some(max);
+
+        self
+    }
+
+    /// Check the serialized message, which is the data that will be signed
+    pub fn check_message(&self, message: &[u8]) -> AtollResult<()> {
+        self.check(&WireMessage::from_message_bytes(message)?)
+    }
+
+    /// Check a wire encoded transaction
+    pub fn check_transaction(&self, transaction: &[u8]) -> AtollResult<()> {
+        self.check(&WireMessage::from_transaction_bytes(transaction)?)
+    }
+
+    pub(crate) fn check(&self, message: &WireMessage) -> AtollResult<()> {
+        if let Some(max) = self.max_instructions {
+            if message.instructions.len() > max {
+                return Err(AtollError::PolicyViolation(format!(
+                    "The message has {} instructions, the maximum allowed is {}",
+                    message.instructions.len(),
+                    max
+                )));
+            }
+        }
+
+        for (index, instruction) in message.instructions.iter().enumerate() {
+            let program_id = message
+                .account_keys
+                .get(instruction.program_id_index as usize)
+                .ok_or(AtollError::MalformedTransaction(format!(
+                    "Program id index {} of instruction {} is out of bounds",
+                    instruction.program_id_index, index
+                )))?;
+
+            if self.denied_programs.contains(program_id) {
+                return Err(AtollError::PolicyViolation(format!(
+                    "Instruction {} invokes the denied program {}",
+                    index, program_id
+                )));
+            }
+
+            if let Some(allowed) = self.allowed_programs.as_ref() {
+                if !allowed.contains(program_id) {
+                    return Err(AtollError::PolicyViolation(format!(
+                        "Instruction {} invokes {} which is not in the allowlist",
+                        index, program_id
+                    )));
+                }
+            }
+
+            let data = bs58::decode(&instruction.data)
+                .into_vec()
+                .map_err(|error| AtollError::MalformedTransaction(error.to_string()))?;
+
+            for rule in &self.instruction_rules {
+                match rule {
+                    InstructionRule::Deny {
+                        program_id: rule_program_id,
+                        discriminator,
+                    } if rule_program_id == program_id && data.starts_with(discriminator) => {
+                        return Err(AtollError::PolicyViolation(format!(
+                            "Instruction {} of program {} matches the denied discriminator {:?}",
+                            index, program_id, discriminator
+                        )));
+                    }
+                    InstructionRule::MaxAccounts {
+                        program_id: rule_program_id,
+                        max,
+                    } if rule_program_id == program_id && instruction.accounts.len() > *max => {
+                        return Err(AtollError::PolicyViolation(format!(
+                            "Instruction {} of program {} references {} accounts, the maximum allowed is {}",
+                            index,
+                            program_id,
+                            instruction.accounts.len(),
+                            max
+                        )));
+                    }
+                    InstructionRule::MaxDataLength {
+                        program_id: rule_program_id,
+                        max,
+                    } if rule_program_id == program_id && data.len() > *max => {
+                        return Err(AtollError::PolicyViolation(format!(
+                            "Instruction {} of program {} has {} bytes of data, the maximum allowed is {}",
+                            index,
+                            program_id,
+                            data.len(),
+                            max
+                        )));
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A [Signer] that checks every message against a [SigningPolicy] before the
+/// wrapped signer sees it, so a message violating the policy is never signed
+#[derive(Debug)]
+pub struct PolicySigner<S> {
+    signer: S,
+    policy: SigningPolicy,
+}
+
+impl<S: Signer> PolicySigner<S> {
+    pub fn new(signer: S, policy: SigningPolicy) -> Self {
+        PolicySigner { signer, policy }
+    }
+
+    pub fn policy(&self) -> &SigningPolicy {
+        &self.policy
+    }
+
+    /// Unwrap the signer
+    pub fn into_inner(self) -> S {
+        self.signer
+    }
+}
+
+impl<S: Signer> Signer for PolicySigner<S> {
+    fn pubkey(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        Box::pin(async move {
+            self.policy.check_message(message)?;
+
+            self.signer.sign_message(message).await
+        })
+    }
+}
@@ -0,0 +1,279 @@
+use crate::{
+    tx::AddressLookupTableState, AtollError, AtollResult, GetAccountInfo,
+    GetMultipleAccountsRequest, Pubkey, RpcMethod, RpcRequest, RpcResult, SimulationResult,
+    TransactionError, Unconfigured, WireMessage, MAX_MULTIPLE_ACCOUNTS, TOKEN_2022_PROGRAM,
+    TOKEN_PROGRAM,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The change of the lamports held by an account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LamportChange {
+    pub address: String,
+    pub pre_lamports: u64,
+    pub post_lamports: u64,
+}
+
+impl LamportChange {
+    /// The signed change in lamports, negative for outflows
+    pub fn delta(&self) -> i128 {
+        self.post_lamports as i128 - self.pre_lamports as i128
+    }
+}
+
+/// The change of the tokens held by a token account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenChange {
+    pub token_account: String,
+    pub mint: String,
+    pub owner: String,
+    pub pre_amount: u64,
+    pub post_amount: u64,
+}
+
+impl TokenChange {
+    /// The signed change in base units, negative for outflows
+    pub fn delta(&self) -> i128 {
+        self.post_amount as i128 - self.pre_amount as i128
+    }
+}
+
+/// Limits on how much value a transaction may move out of the signer's accounts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutflowLimits {
+    max_lamports: Option<u64>,
+    max_tokens: BTreeMap<String, u64>,
+}
+
+impl OutflowLimits {
+    /// Create limits that allow any outflow
+    pub fn new() -> Self {
+        OutflowLimits::default()
+    }
+
+    /// The maximum lamports, including fees, that may leave a single signer account
+    pub fn change_max_lamports(mut self, lamports: u64) -> Self {
+        self.max_lamports = Some(lamports);
+
+        self
+    }
+
+    /// The maximum amount in base units of `mint` that may leave the signers' token accounts
+    pub fn add_token_limit(mut self, mint: &str, amount: u64) -> Self {
+        self.max_tokens.insert(mint.to_owned(), amount);
+
+        self
+    }
+}
+
+/// A preview of the SOL and token balance changes of the signer's accounts,
+/// obtained by simulating a transaction before it is signed and sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalancePreview {
+    /// The lamport changes of accounts that sign the transaction
+    pub lamport_changes: Vec<LamportChange>,
+    /// The changes of token accounts owned by accounts that sign the transaction
+    pub token_changes: Vec<TokenChange>,
+    /// The error the transaction would fail with
+    pub simulation_error: Option<TransactionError>,
+}
+
+impl BalancePreview {
+    /// Simulate a wire encoded transaction and compute the balance changes of
+    /// the writable accounts of its signers, including the writable accounts a v0
+    /// message loads from lookup tables. The transaction does not need to be
+    /// signed since signature verification is skipped and the blockhash is replaced.
+    /// The balances before the transaction are fetched at or after the slot of the
+    /// simulation, so they can only be newer than the state the simulation ran on
+    pub async fn simulate(
+        client: &RpcRequest<Unconfigured>,
+        transaction: &[u8],
+    ) -> AtollResult<Self> {
+        let message = WireMessage::from_transaction_bytes(transaction)?;
+        let signers = &message.account_keys
+            [..(message.num_required_signatures as usize).min(message.account_keys.len())];
+
+        let mut writable = message
+            .account_keys
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| message.is_writable(*index))
+            .map(|(_, address)| address.clone())
+            .collect::<Vec<String>>();
+
+        for lookup in &message.address_table_lookups {
+            let table =
+                AddressLookupTableState::fetch(client, &Pubkey::from_base58(&lookup.account_key)?)
+                    .await?;
+
+            for index in &lookup.writable_indexes {
+                let address = table.addresses.get(*index as usize).ok_or_else(|| {
+                    AtollError::MalformedTransaction(format!(
+                        "Index {} is out of bounds of the lookup table {}",
+                        index, lookup.account_key
+                    ))
+                })?;
+
+                writable.push(address.to_base58());
+            }
+        }
+
+        let simulated = client
+            .template()
+            .add_method(RpcMethod::SimulateTransaction)
+            .add_value(BASE64.encode(transaction).into())
+            .add_extra("encoding", "base64".into())
+            .add_extra("sigVerify", false.into())
+            .add_extra("replaceRecentBlockhash", true.into())
+            .add_extra(
+                "accounts",
+                json::object! {
+                    encoding: "base64",
+                    addresses: writable.clone(),
+                },
+            )
+            .request::<RpcResult<SimulationResult>>()
+            .await?
+            .into_result()?;
+        let simulation_slot = simulated.context.slot;
+        let simulated = simulated.value.ok_or(AtollError::SerdeJsonDeser(
+            "The simulation result is missing".to_owned(),
+        ))?;
+
+        let mut pre_states = Vec::with_capacity(writable.len());
+        for chunk in writable.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let addresses = chunk.iter().map(String::as_str).collect::<Vec<&str>>();
+
+            pre_states.extend(
+                client
+                    .template()
+                    .add_typed(
+                        GetMultipleAccountsRequest::new(&addresses)?
+                            .change_min_context_slot(simulation_slot),
+                    )
+                    .request()
+                    .await?
+                    .into_result()?
+                    .value
+                    .unwrap_or_default(),
+            );
+        }
+
+        let post_states = simulated.accounts.unwrap_or_default();
+
+        let mut preview = BalancePreview {
+            lamport_changes: Vec::new(),
+            token_changes: Vec::new(),
+            simulation_error: simulated.err,
+        };
+
+        for (index, address) in writable.iter().enumerate() {
+            let pre = pre_states.get(index).and_then(Option::as_ref);
+            let post = post_states.get(index).and_then(Option::as_ref);
+
+            if signers.contains(address) {
+                preview.lamport_changes.push(LamportChange {
+                    address: address.clone(),
+                    pre_lamports: pre.map(|account| account.lamports).unwrap_or_default(),
+                    post_lamports: post.map(|account| account.lamports).unwrap_or_default(),
+                });
+            }
+
+            let pre_token = pre.map(TokenAccountState::decode).transpose()?.flatten();
+            let post_token = post.map(TokenAccountState::decode).transpose()?.flatten();
+
+            if let Some(state) = post_token.as_ref().or(pre_token.as_ref()) {
+                if signers.contains(&state.owner) {
+                    preview.token_changes.push(TokenChange {
+                        token_account: address.clone(),
+                        mint: state.mint.clone(),
+                        owner: state.owner.clone(),
+                        pre_amount: pre_token
+                            .as_ref()
+                            .map(|state| state.amount)
+                            .unwrap_or_default(),
+                        post_amount: post_token
+                            .as_ref()
+                            .map(|state| state.amount)
+                            .unwrap_or_default(),
+                    });
+                }
+            }
+        }
+
+        Ok(preview)
+    }
+
+    /// Reject the transaction with [AtollError::OutflowLimitExceeded] if the
+    /// previewed outflows exceed the limits
+    pub fn enforce(&self, limits: &OutflowLimits) -> AtollResult<()> {
+        if let Some(max_lamports) = limits.max_lamports {
+            for change in &self.lamport_changes {
+                let outflow = -change.delta();
+
+                if outflow > max_lamports as i128 {
+                    return Err(AtollError::OutflowLimitExceeded(format!(
+                        "{} would send {} lamports, the limit is {}",
+                        change.address, outflow, max_lamports
+                    )));
+                }
+            }
+        }
+
+        for (mint, max_amount) in &limits.max_tokens {
+            let outflow = -self
+                .token_changes
+                .iter()
+                .filter(|change| &change.mint == mint)
+                .map(TokenChange::delta)
+                .sum::<i128>();
+
+            if outflow > *max_amount as i128 {
+                return Err(AtollError::OutflowLimitExceeded(format!(
+                    "The signers would send {} of mint {}, the limit is {}",
+                    outflow, mint, max_amount
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct TokenAccountState {
+    mint: String,
+    owner: String,
+    amount: u64,
+}
+
+impl TokenAccountState {
+    fn decode(account: &GetAccountInfo) -> AtollResult<Option<Self>> {
+        let token_program = bs58::encode(TOKEN_PROGRAM).into_string();
+        let token_2022_program = bs58::encode(TOKEN_2022_PROGRAM).into_string();
+
+        if account.owner != token_program && account.owner != token_2022_program {
+            return Ok(None);
+        }
+
+        let Some(data) = account.data.0.as_ref() else {
+            return Ok(None);
+        };
+        let data = BASE64.decode(data)?;
+
+        if data.len() < 165 {
+            return Ok(None);
+        }
+
+        Ok(Some(TokenAccountState {
+            mint: bs58::encode(&data[0..32]).into_string(),
+            owner: bs58::encode(&data[32..64]).into_string(),
+            amount: u64::from_le_bytes(data[64..72].try_into().unwrap()), // Never fails since the slice is 8 bytes
+        }))
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{AtollResult, Keypair, Pubkey};
+use core::{fmt, future::Future, pin::Pin};
+
+/// The future returned by [Signer::sign_message]
+#[cfg(not(feature = "wasm"))]
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = AtollResult<[u8; 64]>> + Send + 'a>>;
+/// The future returned by [Signer::sign_message], not `Send` on wasm
+#[cfg(feature = "wasm")]
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = AtollResult<[u8; 64]>> + 'a>>;
+
+/// Produces Ed25519 signatures for one public key. [Keypair] signs in memory,
+/// implement it to sign with a KMS, an HSM or a remote signing service
+pub trait Signer: fmt::Debug + Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+
+    /// Sign the serialized message, returning the 64 byte Ed25519 signature
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a>;
+}
+
+impl Signer for Keypair {
+    fn pubkey(&self) -> Pubkey {
+        Keypair::pubkey(self)
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        Box::pin(async move { Ok(self.sign(message)) })
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{AtollError, AtollResult, Pubkey, WireMessage, WireReader};
+use web3utilities::ed25519_dalek::{PublicKey, Signature};
+
+/// Verify an Ed25519 signature of a message. Uses strict verification which rejects
+/// weak public keys and malleable signatures
+pub fn verify_signature(pubkey: &Pubkey, message: &[u8], signature: &[u8; 64]) -> AtollResult<()> {
+    let public_key = PublicKey::from_bytes(&pubkey.0).map_err(|_| {
+        AtollError::InvalidSignature(format!("{} is not a valid public key", pubkey))
+    })?;
+    let signature = Signature::from_bytes(signature)
+        .map_err(|_| AtollError::InvalidSignature(format!("Malformed signature for {}", pubkey)))?;
+
+    public_key.verify_strict(message, &signature).map_err(|_| {
+        AtollError::InvalidSignature(format!("Signature verification failed for {}", pubkey))
+    })
+}
+
+/// Verify many signatures, each over its own message. Returns the index
+/// of the first signature that fails verification in the error
+pub fn verify_signatures(items: &[(Pubkey, &[u8], [u8; 64])]) -> AtollResult<()> {
+    items
+        .iter()
+        .enumerate()
+        .try_for_each(|(index, (pubkey, message, signature))| {
+            verify_signature(pubkey, message, signature).map_err(|error| match error {
+                AtollError::InvalidSignature(reason) => {
+                    AtollError::InvalidSignature(format!("Item {}: {}", index, reason))
+                }
+                error => error,
+            })
+        })
+}
+
+/// Verify that a wire encoded transaction carries a valid signature
+/// from every account required to sign its message
+pub fn verify_transaction(transaction: &[u8]) -> AtollResult<()> {
+    let mut reader = WireReader::new(transaction);
+
+    let signatures = (0..reader.read_compact_u16()?)
+        .map(|_| reader.read_array::<64>())
+        .collect::<AtollResult<Vec<[u8; 64]>>>()?;
+    let message_bytes = reader.remaining();
+    let message = WireMessage::from_message_bytes(message_bytes)?;
+
+    if signatures.len() != message.num_required_signatures as usize {
+        return Err(AtollError::InvalidSignature(format!(
+            "The transaction has {} signatures but its message requires {}",
+            signatures.len(),
+            message.num_required_signatures
+        )));
+    }
+
+    if message.account_keys.len() < signatures.len() {
+        return Err(AtollError::InvalidSignature(format!(
+            "The message requires {} signatures but lists only {} account keys",
+            message.num_required_signatures,
+            message.account_keys.len()
+        )));
+    }
+
+    signatures
+        .iter()
+        .enumerate()
+        .try_for_each(|(index, signature)| {
+            verify_signature(
+                &Pubkey::from_base58(&message.account_keys[index])?,
+                message_bytes,
+                signature,
+            )
+        })
+}
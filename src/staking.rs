@@ -0,0 +1,118 @@
+use crate::{
+    AtollError, AtollResult, GetInflationRateRequest, GetSupplyRequest, GetVoteAccountsRequest,
+    RpcRequest, Unconfigured,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// The approximate number of epochs per year on mainnet, 432,000 slots of about 400ms each
+pub const DEFAULT_EPOCHS_PER_YEAR: f64 = 182.5;
+
+/// The network parameters used to project staking yield for a validator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StakingYieldInputs {
+    /// The yearly inflation paid to validators and their stakers as a fraction of the supply
+    pub validator_inflation_rate: f64,
+    /// The total supply in lamports
+    pub total_supply: u64,
+    /// The total activated stake of all voting validators in lamports
+    pub total_active_stake: u64,
+    /// The commission of the validator as a percentage
+    pub commission: u8,
+    pub epochs_per_year: f64,
+}
+
+/// A projection of the yearly staking yield with a validator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StakingYieldProjection {
+    /// The yearly rate paid to stakers before the validator commission
+    pub network_apr: f64,
+    /// The yearly rate paid to stakers after the validator commission, without compounding
+    pub apr: f64,
+    /// The yearly yield when rewards are compounded every epoch
+    pub apy: f64,
+    /// The stake the projection was made for in lamports
+    pub stake: u64,
+    /// The projected rewards after one year in lamports, including compounding
+    pub projected_yearly_rewards: u64,
+}
+
+impl StakingYieldInputs {
+    /// Fetch the inflation rate, supply and vote accounts to project the yield of staking with `vote_pubkey`
+    pub async fn fetch(client: &RpcRequest<Unconfigured>, vote_pubkey: &str) -> AtollResult<Self> {
+        let inflation = client
+            .template()
+            .add_typed(GetInflationRateRequest)
+            .request()
+            .await?
+            .into_result()?;
+
+        let total_supply = client
+            .template()
+            .add_typed(GetSupplyRequest::new().change_exclude_non_circulating_accounts_list(true))
+            .request()
+            .await?
+            .into_result()?
+            .value
+            .map(|supply| supply.total)
+            .unwrap_or_default();
+
+        let vote_accounts = client
+            .template()
+            .add_typed(GetVoteAccountsRequest::new())
+            .request()
+            .await?
+            .into_result()?;
+
+        let total_active_stake = vote_accounts
+            .current
+            .iter()
+            .map(|vote_account| vote_account.activated_stake)
+            .sum();
+
+        let commission = vote_accounts
+            .current
+            .iter()
+            .chain(vote_accounts.delinquent.iter())
+            .find(|vote_account| vote_account.vote_pubkey == vote_pubkey)
+            .map(|vote_account| vote_account.commission)
+            .ok_or(AtollError::NotFound(format!(
+                "Vote account {} was not found",
+                vote_pubkey
+            )))?;
+
+        Ok(StakingYieldInputs {
+            validator_inflation_rate: inflation.validator,
+            total_supply,
+            total_active_stake,
+            commission,
+            epochs_per_year: DEFAULT_EPOCHS_PER_YEAR,
+        })
+    }
+
+    /// Project the yearly yield of staking `stake` lamports with the validator.
+    /// The projection assumes the inflation rate, supply and total stake stay constant
+    /// and that the validator earns all its vote credits
+    pub fn project(&self, stake: u64) -> StakingYieldProjection {
+        let network_apr = if self.total_active_stake == 0 {
+            0.0
+        } else {
+            self.validator_inflation_rate * self.total_supply as f64
+                / self.total_active_stake as f64
+        };
+
+        let apr = network_apr * (1.0 - self.commission.min(100) as f64 / 100.0);
+        let epochs_per_year = self.epochs_per_year.max(1.0);
+        let apy = (1.0 + apr / epochs_per_year).powf(epochs_per_year) - 1.0;
+
+        StakingYieldProjection {
+            network_apr,
+            apr,
+            apy,
+            stake,
+            projected_yearly_rewards: (stake as f64 * apy) as u64,
+        }
+    }
+}
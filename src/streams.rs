@@ -0,0 +1,583 @@
+use crate::{
+    runtime, AtollError, AtollResult, Block, Commitment, GetSignaturesForAddressRequest, RpcMethod,
+    RpcRequest, SignatureDedup, SignatureInfo, Unconfigured,
+};
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::Stream;
+use std::{collections::VecDeque, time::Duration};
+
+/// The default time between two `getSlot` polls of a [SlotFollower]
+pub const DEFAULT_SLOT_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// The most signatures `getSignaturesForAddress` returns per request
+pub const SIGNATURES_PAGE_LIMIT: usize = 1000;
+
+/// JSON-RPC error returned by `getBlock` for a slot without a block
+const SLOT_SKIPPED: i16 = -32007;
+/// JSON-RPC error returned by `getBlock` for a slot missing from long-term storage
+const LONG_TERM_STORAGE_SLOT_SKIPPED: i16 = -32009;
+
+/// Polls `getSlot` and yields every new slot observed, as a `futures::Stream`
+pub struct SlotFollower {
+    client: RpcRequest<Unconfigured>,
+    commitment: Commitment,
+    interval: Duration,
+    last_slot: Option<u64>,
+    /// Set once a poll finished, with a slot or an error
+    polled: bool,
+    pending: Option<runtime::BoxFuture<AtollResult<u64>>>,
+}
+
+impl SlotFollower {
+    pub fn new(client: &RpcRequest<Unconfigured>) -> Self {
+        SlotFollower {
+            client: client.template(),
+            commitment: Commitment::default(),
+            interval: DEFAULT_SLOT_POLL_INTERVAL,
+            last_slot: None,
+            polled: false,
+            pending: None,
+        }
+    }
+
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = commitment;
+
+        self
+    }
+
+    pub fn change_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+
+        self
+    }
+
+    fn poll_slot(&self) -> runtime::BoxFuture<AtollResult<u64>> {
+        let client = self.client.template();
+        let commitment = self.commitment;
+        // The first poll is sent right away, every later one including the
+        // retries after an error waits for the interval
+        let delay = self.polled.then_some(self.interval);
+
+        Box::pin(async move {
+            if let Some(delay) = delay {
+                runtime::sleep(delay).await;
+            }
+
+            client
+                .add_method(RpcMethod::GetSlot)
+                .add_value(json::object! { commitment: <&str>::from(commitment) })
+                .request::<u64>()
+                .await?
+                .into_result()
+        })
+    }
+}
+
+impl Stream for SlotFollower {
+    type Item = AtollResult<u64>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.pending.is_none() {
+                self.pending = Some(self.poll_slot());
+            }
+
+            let outcome = match self.pending.as_mut() {
+                Some(pending) => match pending.as_mut().poll(cx) {
+                    Poll::Ready(outcome) => outcome,
+                    Poll::Pending => return Poll::Pending,
+                },
+                None => continue,
+            };
+            self.pending = None;
+            self.polled = true;
+
+            match outcome {
+                Ok(slot) if self.last_slot.map(|last| slot > last).unwrap_or(true) => {
+                    self.last_slot = Some(slot);
+
+                    return Poll::Ready(Some(Ok(slot)));
+                }
+                Ok(_) => continue,
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+    }
+}
+
+/// Fetches the blocks of a range of slots in order, as a `futures::Stream`.
+/// Skipped slots are not yielded
+pub struct BlockBackfill {
+    client: RpcRequest<Unconfigured>,
+    next_slot: u64,
+    end_slot: u64,
+    /// Set once the slot `u64::MAX` was fetched, since `next_slot` cannot pass it
+    finished: bool,
+    dedup: Option<SignatureDedup>,
+    pending: Option<runtime::BoxFuture<AtollResult<Option<Block>>>>,
+}
+
+impl BlockBackfill {
+    /// Backfill the blocks from `start_slot` to `end_slot` inclusive
+    pub fn new(client: &RpcRequest<Unconfigured>, start_slot: u64, end_slot: u64) -> Self {
+        BlockBackfill {
+            client: client.template(),
+            next_slot: start_slot,
+            end_slot,
+            finished: false,
+            dedup: None,
+            pending: None,
+        }
+    }
+
+    /// Drop the transactions of each block whose signature the dedup set has
+    /// already seen, so that overlapping or restarted backfills do not yield a
+    /// transaction twice
+    pub fn change_dedup(mut self, dedup: SignatureDedup) -> Self {
+        self.dedup = Some(dedup);
+
+        self
+    }
+
+    /// The dedup set, to [SignatureDedup::persist] it
+    pub fn dedup(&self) -> Option<&SignatureDedup> {
+        self.dedup.as_ref()
+    }
+
+    fn retain_new(&mut self, block: &mut Block) {
+        if let Some(dedup) = self.dedup.as_mut() {
+            // Transactions that fail to decode have no signature to compare and are kept
+            block.transactions.retain(|transaction| {
+                match transaction
+                    .signatures()
+                    .ok()
+                    .and_then(|s| s.into_iter().next())
+                {
+                    Some(signature) => dedup.insert(&signature),
+                    None => true,
+                }
+            });
+        }
+    }
+
+    fn fetch_block(&self, slot: u64) -> runtime::BoxFuture<AtollResult<Option<Block>>> {
+        let client = self.client.template();
+
+        Box::pin(async move {
+            let outcome = client
+                .get_block(slot)
+                .add_extra("encoding", "base64".into())
+                .add_extra("maxSupportedTransactionVersion", 0.into())
+                .request::<Block>()
+                .await?
+                .into_result();
+
+            match outcome {
+                Ok(block) => Ok(Some(block)),
+                Err(AtollError::Rpc { code, .. })
+                    if code == SLOT_SKIPPED || code == LONG_TERM_STORAGE_SLOT_SKIPPED =>
+                {
+                    Ok(None)
+                }
+                Err(error) => Err(error),
+            }
+        })
+    }
+}
+
+impl Stream for BlockBackfill {
+    type Item = AtollResult<(u64, Block)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.finished || self.next_slot > self.end_slot {
+                return Poll::Ready(None);
+            }
+
+            let slot = self.next_slot;
+            if self.pending.is_none() {
+                self.pending = Some(self.fetch_block(slot));
+            }
+
+            let outcome = match self.pending.as_mut() {
+                Some(pending) => match pending.as_mut().poll(cx) {
+                    Poll::Ready(outcome) => outcome,
+                    Poll::Pending => return Poll::Pending,
+                },
+                None => continue,
+            };
+            self.pending = None;
+            match self.next_slot.checked_add(1) {
+                Some(next_slot) => self.next_slot = next_slot,
+                None => self.finished = true,
+            }
+
+            match outcome {
+                Ok(Some(mut block)) => {
+                    self.retain_new(&mut block);
+
+                    return Poll::Ready(Some(Ok((slot, block))));
+                }
+                Ok(None) => continue,
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished || self.next_slot > self.end_slot {
+            return (0, Some(0));
+        }
+
+        let remaining = (self.end_slot - self.next_slot).saturating_add(1);
+
+        (0, usize::try_from(remaining).ok())
+    }
+}
+
+/// Yields the signatures of an address newest first as a `futures::Stream`, following
+/// the `before` cursor of `getSignaturesForAddress` one page at a time
+pub struct SignaturesStream {
+    client: RpcRequest<Unconfigured>,
+    address: String,
+    before: Option<String>,
+    until: Option<String>,
+    commitment: Option<Commitment>,
+    page_size: usize,
+    page: VecDeque<SignatureInfo>,
+    exhausted: bool,
+    dedup: Option<SignatureDedup>,
+    pending: Option<runtime::BoxFuture<AtollResult<Vec<SignatureInfo>>>>,
+}
+
+impl SignaturesStream {
+    /// All signatures of the base58 encoded address
+    pub fn new(client: &RpcRequest<Unconfigured>, address: &str) -> Self {
+        SignaturesStream {
+            client: client.template(),
+            address: address.to_owned(),
+            before: None,
+            until: None,
+            commitment: None,
+            page_size: SIGNATURES_PAGE_LIMIT,
+            page: VecDeque::new(),
+            exhausted: false,
+            dedup: None,
+            pending: None,
+        }
+    }
+
+    /// Start with the signature before this one
+    pub fn change_before(mut self, signature: &str) -> Self {
+        self.before = Some(signature.to_owned());
+
+        self
+    }
+
+    /// Stop at this signature, for example the newest one seen by an earlier run
+    pub fn change_until(mut self, signature: &str) -> Self {
+        self.until = Some(signature.to_owned());
+
+        self
+    }
+
+    /// `confirmed` or `finalized`, the node rejects `processed`
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+
+    /// The number of signatures requested per page, at most [SIGNATURES_PAGE_LIMIT]
+    pub fn change_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, SIGNATURES_PAGE_LIMIT);
+
+        self
+    }
+
+    /// Skip the signatures the dedup set has already seen, so that overlapping
+    /// or restarted runs do not yield a signature twice
+    pub fn change_dedup(mut self, dedup: SignatureDedup) -> Self {
+        self.dedup = Some(dedup);
+
+        self
+    }
+
+    /// The dedup set, to [SignatureDedup::persist] it
+    pub fn dedup(&self) -> Option<&SignatureDedup> {
+        self.dedup.as_ref()
+    }
+
+    fn fetch_page(&self) -> runtime::BoxFuture<AtollResult<Vec<SignatureInfo>>> {
+        let mut typed =
+            GetSignaturesForAddressRequest::new(&self.address).change_limit(self.page_size);
+
+        if let Some(before) = self.before.as_deref() {
+            typed = typed.change_before(before);
+        }
+
+        if let Some(until) = self.until.as_deref() {
+            typed = typed.change_until(until);
+        }
+
+        if let Some(commitment) = self.commitment {
+            typed = typed.change_commitment(commitment);
+        }
+
+        let request = self.client.template().add_typed(typed);
+
+        Box::pin(async move { request.request().await?.into_result() })
+    }
+}
+
+impl Stream for SignaturesStream {
+    type Item = AtollResult<SignatureInfo>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(signature) = self.page.pop_front() {
+                return Poll::Ready(Some(Ok(signature)));
+            }
+
+            if self.exhausted {
+                return Poll::Ready(None);
+            }
+
+            if self.pending.is_none() {
+                self.pending = Some(self.fetch_page());
+            }
+
+            let outcome = match self.pending.as_mut() {
+                Some(pending) => match pending.as_mut().poll(cx) {
+                    Poll::Ready(outcome) => outcome,
+                    Poll::Pending => return Poll::Pending,
+                },
+                None => continue,
+            };
+            self.pending = None;
+
+            match outcome {
+                Ok(page) => {
+                    // A short page is the last one
+                    self.exhausted = page.len() < self.page_size;
+                    self.before = page.last().map(|last| last.signature.clone());
+                    self.page = match self.dedup.as_mut() {
+                        Some(dedup) => dedup
+                            .retain_new(page, |info| info.signature.as_str())
+                            .into(),
+                        None => page.into(),
+                    };
+                }
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+    }
+}
+
+/// Polls `getSignaturesForAddress` and yields every new signature of an address
+/// oldest first, as a `futures::Stream`. Only signatures newer than the newest one
+/// at the first poll are yielded, unless a starting signature is set with
+/// [AddressMonitor::change_until]
+pub struct AddressMonitor {
+    client: RpcRequest<Unconfigured>,
+    address: String,
+    commitment: Option<Commitment>,
+    interval: Duration,
+    until: Option<String>,
+    polled: bool,
+    ready: VecDeque<SignatureInfo>,
+    dedup: Option<SignatureDedup>,
+    pending: Option<runtime::BoxFuture<AtollResult<Vec<SignatureInfo>>>>,
+}
+
+impl AddressMonitor {
+    /// Monitor the base58 encoded address
+    pub fn new(client: &RpcRequest<Unconfigured>, address: &str) -> Self {
+        AddressMonitor {
+            client: client.template(),
+            address: address.to_owned(),
+            commitment: None,
+            interval: DEFAULT_SLOT_POLL_INTERVAL,
+            until: None,
+            polled: false,
+            ready: VecDeque::new(),
+            dedup: None,
+            pending: None,
+        }
+    }
+
+    /// `confirmed` or `finalized`, the node rejects `processed`
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = Some(commitment);
+
+        self
+    }
+
+    pub fn change_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+
+        self
+    }
+
+    /// Yield the signatures after this one, for example the newest one seen by an earlier run
+    pub fn change_until(mut self, signature: &str) -> Self {
+        self.until = Some(signature.to_owned());
+
+        self
+    }
+
+    /// Skip the signatures the dedup set has already seen, so that overlapping
+    /// or restarted monitors do not yield a signature twice
+    pub fn change_dedup(mut self, dedup: SignatureDedup) -> Self {
+        self.dedup = Some(dedup);
+
+        self
+    }
+
+    /// The dedup set, to [SignatureDedup::persist] it
+    pub fn dedup(&self) -> Option<&SignatureDedup> {
+        self.dedup.as_ref()
+    }
+
+    /// The first poll without a starting signature only finds the newest signature
+    fn is_starting_point(&self) -> bool {
+        !self.polled && self.until.is_none()
+    }
+
+    /// The signatures newer than `until`, newest first
+    fn poll_signatures(&self) -> runtime::BoxFuture<AtollResult<Vec<SignatureInfo>>> {
+        let client = self.client.template();
+        let address = self.address.clone();
+        let commitment = self.commitment;
+        let until = self.until.clone();
+        let starting_point = self.is_starting_point();
+        // The first poll is sent right away
+        let delay = self.polled.then_some(self.interval);
+
+        Box::pin(async move {
+            if let Some(delay) = delay {
+                runtime::sleep(delay).await;
+            }
+
+            let page_size = match starting_point {
+                true => 1,
+                false => SIGNATURES_PAGE_LIMIT,
+            };
+
+            let mut signatures = Vec::<SignatureInfo>::new();
+            loop {
+                let mut typed =
+                    GetSignaturesForAddressRequest::new(&address).change_limit(page_size);
+                if let Some(before) = signatures.last() {
+                    typed = typed.change_before(&before.signature);
+                }
+                if let Some(until) = until.as_deref() {
+                    typed = typed.change_until(until);
+                }
+                if let Some(commitment) = commitment {
+                    typed = typed.change_commitment(commitment);
+                }
+
+                let page = client
+                    .template()
+                    .add_typed(typed)
+                    .request()
+                    .await?
+                    .into_result()?;
+                // A short page is the last one
+                let last_page = starting_point || page.len() < page_size;
+                signatures.extend(page);
+
+                if last_page {
+                    return Ok(signatures);
+                }
+            }
+        })
+    }
+}
+
+impl Stream for AddressMonitor {
+    type Item = AtollResult<SignatureInfo>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(signature) = self.ready.pop_front() {
+                return Poll::Ready(Some(Ok(signature)));
+            }
+
+            if self.pending.is_none() {
+                self.pending = Some(self.poll_signatures());
+            }
+
+            let outcome = match self.pending.as_mut() {
+                Some(pending) => match pending.as_mut().poll(cx) {
+                    Poll::Ready(outcome) => outcome,
+                    Poll::Pending => return Poll::Pending,
+                },
+                None => continue,
+            };
+            self.pending = None;
+
+            let signatures = match outcome {
+                Ok(signatures) => signatures,
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            };
+
+            let starting_point = self.is_starting_point();
+            self.polled = true;
+            if let Some(newest) = signatures.first() {
+                self.until = Some(newest.signature.clone());
+            }
+
+            if !starting_point {
+                let signatures = signatures.into_iter().rev().collect::<Vec<_>>();
+
+                self.ready = match self.dedup.as_mut() {
+                    Some(dedup) => dedup
+                        .retain_new(signatures, |info| info.signature.as_str())
+                        .into(),
+                    None => signatures.into(),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(feature = "rt-smol", not(any(feature = "rt-tokio", feature = "wasm"))))]
+    #[test]
+    fn slot_follower_waits_the_interval_after_errors() {
+        use super::*;
+        use crate::MockTransport;
+        use futures_lite::StreamExt;
+        use std::sync::Arc;
+
+        let transport = Arc::new(
+            MockTransport::new()
+                .add_rpc_error("getSlot", -32005, "Node is behind by 42 slots")
+                .add_result("getSlot", 1234.into()),
+        );
+        let interval = Duration::from_millis(50);
+        let mut follower =
+            SlotFollower::new(&RpcRequest::new().change_transport(transport.clone()))
+                .change_interval(interval);
+
+        futures_lite::future::block_on(async {
+            let started = std::time::Instant::now();
+            assert!(matches!(
+                follower.next().await,
+                Some(Err(AtollError::Rpc { .. }))
+            ));
+            assert!(started.elapsed() < interval);
+
+            assert_eq!(follower.next().await, Some(Ok(1234)));
+            assert!(started.elapsed() >= interval);
+        });
+        assert_eq!(transport.methods(), ["getSlot", "getSlot"]);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{
+    AtollResult, ClusterNode, GetVoteAccountsRequest, ParameterlessMethod, RpcRequest,
+    Unconfigured, VoteAccounts,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+};
+
+/// A node of the cluster as seen through gossip, with the stake delegated to its vote accounts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopologyNode {
+    /// The identity public key of the node
+    pub pubkey: String,
+    pub gossip: Option<String>,
+    pub tpu: Option<String>,
+    /// The JSON RPC address, `None` if the node does not expose RPC
+    pub rpc: Option<String>,
+    /// The `solana-core` version
+    pub version: Option<String>,
+    pub feature_set: Option<u32>,
+    pub shred_version: Option<u16>,
+    /// The vote accounts whose node is this identity
+    pub vote_accounts: Vec<String>,
+    /// The stake activated on the vote accounts of the node in lamports
+    pub activated_stake: u64,
+    /// All vote accounts of the node are delinquent
+    pub delinquent: bool,
+}
+
+/// The nodes running a `solana-core` version and the stake they hold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDistribution {
+    pub version: String,
+    pub nodes: u64,
+    pub activated_stake: u64,
+    /// The share of the total activated stake from `0.0` to `1.0`
+    pub stake_share: f64,
+}
+
+/// A typed map of the cluster built from `getClusterNodes` and `getVoteAccounts`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterTopology {
+    /// The nodes sorted by activated stake, largest first
+    pub nodes: Vec<TopologyNode>,
+    /// The stake activated on all vote accounts, unmatched ones included
+    pub total_activated_stake: u64,
+    /// The vote accounts whose node is missing from gossip
+    pub unmatched_vote_accounts: Vec<String>,
+    /// The stake activated on the unmatched vote accounts, which no node holds
+    pub unmatched_activated_stake: u64,
+    /// Versions sorted by activated stake, largest first
+    pub versions: Vec<VersionDistribution>,
+}
+
+impl ClusterTopology {
+    /// Fetch the gossip and vote account data of a cluster
+    pub async fn fetch(client: &RpcRequest<Unconfigured>) -> AtollResult<Self> {
+        let cluster_nodes = client
+            .template()
+            .add_parameterless_method(ParameterlessMethod::GetClusterNodes)
+            .request::<Vec<ClusterNode>>()
+            .await?
+            .into_result()?;
+
+        let vote_accounts = client
+            .template()
+            .add_typed(GetVoteAccountsRequest::new())
+            .request()
+            .await?
+            .into_result()?;
+
+        Ok(Self::build(cluster_nodes, &vote_accounts))
+    }
+
+    pub(crate) fn build(cluster_nodes: Vec<ClusterNode>, vote_accounts: &VoteAccounts) -> Self {
+        let mut nodes = cluster_nodes
+            .into_iter()
+            .map(|node| {
+                (
+                    node.pubkey.clone(),
+                    TopologyNode {
+                        pubkey: node.pubkey,
+                        gossip: node.gossip,
+                        tpu: node.tpu,
+                        rpc: node.rpc,
+                        version: node.version,
+                        feature_set: node.feature_set,
+                        shred_version: node.shred_version,
+                        vote_accounts: Vec::new(),
+                        activated_stake: 0,
+                        delinquent: false,
+                    },
+                )
+            })
+            .collect::<HashMap<String, TopologyNode>>();
+
+        let current = vote_accounts.current.iter().map(|account| (account, false));
+        let delinquent = vote_accounts
+            .delinquent
+            .iter()
+            .map(|account| (account, true));
+
+        let mut unmatched_vote_accounts = Vec::new();
+        let mut unmatched_activated_stake = 0u64;
+
+        for (vote_account, is_delinquent) in current.chain(delinquent) {
+            match nodes.get_mut(&vote_account.node_pubkey) {
+                Some(node) => {
+                    node.delinquent = if node.vote_accounts.is_empty() {
+                        is_delinquent
+                    } else {
+                        node.delinquent && is_delinquent
+                    };
+                    node.vote_accounts.push(vote_account.vote_pubkey.clone());
+                    node.activated_stake += vote_account.activated_stake;
+                }
+                None => {
+                    unmatched_vote_accounts.push(vote_account.vote_pubkey.clone());
+                    unmatched_activated_stake += vote_account.activated_stake;
+                }
+            }
+        }
+
+        let mut nodes = nodes.into_values().collect::<Vec<TopologyNode>>();
+        nodes.sort_by(|a, b| {
+            b.activated_stake
+                .cmp(&a.activated_stake)
+                .then_with(|| a.pubkey.cmp(&b.pubkey))
+        });
+
+        let total_activated_stake =
+            nodes.iter().map(|node| node.activated_stake).sum::<u64>() + unmatched_activated_stake;
+
+        let mut by_version = BTreeMap::<String, (u64, u64)>::new();
+        for node in &nodes {
+            let version = node.version.clone().unwrap_or_else(|| "unknown".to_owned());
+            let entry = by_version.entry(version).or_default();
+            entry.0 += 1;
+            entry.1 += node.activated_stake;
+        }
+
+        let mut versions = by_version
+            .into_iter()
+            .map(|(version, (nodes, activated_stake))| VersionDistribution {
+                version,
+                nodes,
+                activated_stake,
+                stake_share: if total_activated_stake == 0 {
+                    0.0
+                } else {
+                    activated_stake as f64 / total_activated_stake as f64
+                },
+            })
+            .collect::<Vec<VersionDistribution>>();
+        versions.sort_by_key(|version| Reverse(version.activated_stake));
+
+        ClusterTopology {
+            nodes,
+            total_activated_stake,
+            unmatched_vote_accounts,
+            unmatched_activated_stake,
+            versions,
+        }
+    }
+
+    /// The nodes that expose a JSON RPC address
+    pub fn rpc_nodes(&self) -> impl Iterator<Item = &TopologyNode> {
+        self.nodes.iter().filter(|node| node.rpc.is_some())
+    }
+
+    /// The nodes with at least one vote account
+    pub fn validators(&self) -> impl Iterator<Item = &TopologyNode> {
+        self.nodes
+            .iter()
+            .filter(|node| !node.vote_accounts.is_empty())
+    }
+
+    /// The smallest number of nodes whose combined stake exceeds one third of the total,
+    /// enough to halt the cluster (the Nakamoto coefficient). The unmatched stake counts
+    /// towards the total but not towards any node
+    pub fn nakamoto_coefficient(&self) -> u64 {
+        let mut stake = 0u128;
+        let threshold = self.total_activated_stake as u128 / 3;
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            stake += node.activated_stake as u128;
+
+            if stake > threshold {
+                return index as u64 + 1;
+            }
+        }
+
+        self.nodes.len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VoteAccount;
+
+    fn node(pubkey: &str, version: &str) -> ClusterNode {
+        ClusterNode {
+            pubkey: pubkey.to_owned(),
+            gossip: None,
+            tpu: None,
+            rpc: None,
+            version: Some(version.to_owned()),
+            feature_set: None,
+            shred_version: None,
+        }
+    }
+
+    fn vote_account(vote_pubkey: &str, node_pubkey: &str, activated_stake: u64) -> VoteAccount {
+        VoteAccount {
+            vote_pubkey: vote_pubkey.to_owned(),
+            node_pubkey: node_pubkey.to_owned(),
+            activated_stake,
+            commission: 10,
+            epoch_vote_account: true,
+            epoch_credits: Vec::new(),
+            last_vote: 0,
+            root_slot: 0,
+        }
+    }
+
+    #[test]
+    fn reports_stake_of_vote_accounts_missing_from_gossip() {
+        let topology = ClusterTopology::build(
+            vec![node("a", "2.0.0"), node("b", "1.18.0")],
+            &VoteAccounts {
+                current: vec![
+                    vote_account("vote-a", "a", 500),
+                    vote_account("vote-c", "c", 300),
+                ],
+                delinquent: vec![
+                    vote_account("vote-b", "b", 100),
+                    vote_account("vote-d", "d", 100),
+                ],
+            },
+        );
+
+        assert_eq!(topology.total_activated_stake, 1000);
+        assert_eq!(topology.unmatched_activated_stake, 400);
+        assert_eq!(topology.unmatched_vote_accounts, ["vote-c", "vote-d"]);
+        assert_eq!(
+            topology
+                .nodes
+                .iter()
+                .map(|node| (node.pubkey.as_str(), node.activated_stake, node.delinquent))
+                .collect::<Vec<_>>(),
+            [("a", 500, false), ("b", 100, true)]
+        );
+        assert_eq!(topology.versions[0].version, "2.0.0");
+        assert_eq!(topology.versions[0].stake_share, 0.5);
+        // Node `a` alone holds more than a third of all stake
+        assert_eq!(topology.nakamoto_coefficient(), 1);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    tx::{
+        send_and_confirm_transaction, AddressLookupTableAccount, Blockhash, ConfirmationConfig,
+        Instruction, Message, MessageV0, Transaction, TransactionConfirmation, VersionedMessage,
+    },
+    AtollError, AtollResult, BalancePreview, BlockhashGuard, Commitment, FetchedBlockhash,
+    GetLatestBlockhashRequest, GetSlotRequest, LatestBlockhash, OutflowLimits, Pubkey, RpcRequest,
+    SendTransactionRequest, Signer, SimulationResult, Unconfigured,
+};
+
+/// Collects instructions into a transaction and sends it, fetching the
+/// blockhash and signing on the way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionBuilder {
+    instructions: Vec<Instruction>,
+    fee_payer: Option<Pubkey>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    commitment: Commitment,
+    skip_preflight: bool,
+    outflow_limits: Option<OutflowLimits>,
+    blockhash_guard: Option<BlockhashGuard>,
+}
+
+impl Default for TransactionBuilder {
+    fn default() -> Self {
+        TransactionBuilder::new()
+    }
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        TransactionBuilder {
+            instructions: Vec::new(),
+            fee_payer: None,
+            lookup_tables: Vec::new(),
+            commitment: Commitment::Confirmed,
+            skip_preflight: false,
+            outflow_limits: None,
+            blockhash_guard: None,
+        }
+    }
+
+    pub fn add_instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+
+        self
+    }
+
+    /// The account paying the fees, the first signer if unset
+    pub fn change_fee_payer(mut self, fee_payer: Pubkey) -> Self {
+        self.fee_payer = Some(fee_payer);
+
+        self
+    }
+
+    /// Compile a v0 message loading accounts from the table, a legacy message
+    /// is compiled without tables
+    pub fn add_lookup_table(mut self, lookup_table: AddressLookupTableAccount) -> Self {
+        self.lookup_tables.push(lookup_table);
+
+        self
+    }
+
+    /// The commitment the blockhash is fetched and the preflight simulation runs at,
+    /// [Commitment::Confirmed] by default
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = commitment;
+
+        self
+    }
+
+    /// Skip the simulation the node runs before forwarding the transaction
+    pub fn change_skip_preflight(mut self, skip_preflight: bool) -> Self {
+        self.skip_preflight = skip_preflight;
+
+        self
+    }
+
+    /// Preview the balance changes of the signers before sending and refuse to send
+    /// with [AtollError::OutflowLimitExceeded] if they exceed the limits
+    pub fn change_outflow_limits(mut self, outflow_limits: OutflowLimits) -> Self {
+        self.outflow_limits = Some(outflow_limits);
+
+        self
+    }
+
+    /// Check the fetched blockhash with the guard before signing, refusing to sign
+    /// with [AtollError::BlockhashRejected] if it is likely to expire
+    pub fn change_blockhash_guard(mut self, blockhash_guard: BlockhashGuard) -> Self {
+        self.blockhash_guard = Some(blockhash_guard);
+
+        self
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Compile the message for the fee payer with the blockhash
+    pub fn build(
+        &self,
+        fee_payer: &Pubkey,
+        recent_blockhash: Blockhash,
+    ) -> AtollResult<VersionedMessage> {
+        let fee_payer = self.fee_payer.as_ref().unwrap_or(fee_payer);
+
+        let message: VersionedMessage = match self.lookup_tables.is_empty() {
+            true => Message::new(&self.instructions, fee_payer)?.into(),
+            false => MessageV0::new(&self.instructions, fee_payer, &self.lookup_tables)?.into(),
+        };
+
+        Ok(message.change_recent_blockhash(recent_blockhash))
+    }
+
+    /// Fetch the latest blockhash, compile the transaction and sign it with every
+    /// signer of the message. Returns the signed transaction and the blockhash
+    /// with the last block height the transaction is accepted at
+    pub async fn sign(
+        &self,
+        client: &RpcRequest<Unconfigured>,
+        signers: &[&dyn Signer],
+    ) -> AtollResult<(Transaction, LatestBlockhash)> {
+        let fee_payer = match (self.fee_payer, signers.first()) {
+            (Some(fee_payer), _) => fee_payer,
+            (None, Some(signer)) => signer.pubkey(),
+            (None, None) => {
+                return Err(AtollError::InvalidTransaction(
+                    "A transaction needs a fee payer or at least one signer".to_owned(),
+                ))
+            }
+        };
+
+        let response = client
+            .template()
+            .add_typed(GetLatestBlockhashRequest::new().change_commitment(self.commitment))
+            .request()
+            .await?
+            .into_result()?;
+        let fetched_slot = response.context.slot;
+        let latest_blockhash = response.value.ok_or(AtollError::SerdeJsonDeser(
+            "The latest blockhash is missing".to_owned(),
+        ))?;
+        let recent_blockhash = Blockhash::from_base58(&latest_blockhash.blockhash)?;
+
+        let mut transaction = Transaction::new(self.build(&fee_payer, recent_blockhash)?);
+
+        if let Some(guard) = &self.blockhash_guard {
+            let current_slot = client
+                .template()
+                .add_typed(GetSlotRequest::new().change_commitment(Commitment::Processed))
+                .request()
+                .await?
+                .into_result()?;
+            let fetched = FetchedBlockhash {
+                blockhash: latest_blockhash.blockhash.clone(),
+                commitment: self.commitment,
+                slot: fetched_slot,
+            };
+
+            guard.check_message(&transaction.message.serialize(), &fetched, current_slot)?;
+        }
+
+        transaction.sign(signers, recent_blockhash).await?;
+
+        Ok((transaction, latest_blockhash))
+    }
+
+    /// Fetch the latest blockhash, set the fee payer, sign with the signers and
+    /// submit the transaction. Returns the base58 encoded signature, which only
+    /// means the node forwarded the transaction
+    pub async fn send(
+        &self,
+        client: &RpcRequest<Unconfigured>,
+        signers: &[&dyn Signer],
+    ) -> AtollResult<String> {
+        let (transaction, _) = self.sign(client, signers).await?;
+
+        send_transaction(
+            client,
+            &transaction,
+            self.commitment,
+            self.skip_preflight,
+            self.outflow_limits.as_ref(),
+        )
+        .await
+    }
+
+    /// [Self::send] and wait for the transaction with [send_and_confirm_transaction].
+    /// Once the blockhash expires without the transaction landing, a new blockhash
+    /// is fetched and the transaction signed and sent again up to the maximum
+    /// blockhash refreshes of the config. The preflight and outflow limits follow
+    /// the config rather than the builder
+    pub async fn send_and_confirm(
+        &self,
+        client: &RpcRequest<Unconfigured>,
+        signers: &[&dyn Signer],
+        config: &ConfirmationConfig,
+    ) -> AtollResult<TransactionConfirmation> {
+        let mut refreshes = 0;
+
+        loop {
+            let (transaction, latest_blockhash) = self.sign(client, signers).await?;
+
+            let outcome = send_and_confirm_transaction(
+                client,
+                &transaction,
+                latest_blockhash.last_valid_block_height,
+                config,
+            )
+            .await;
+
+            match outcome {
+                Err(AtollError::TransactionExpired { .. })
+                    if refreshes < config.max_blockhash_refreshes() =>
+                {
+                    refreshes += 1;
+                }
+                outcome => return outcome,
+            }
+        }
+    }
+}
+
+/// The JSON-RPC error code of a transaction that failed its preflight simulation
+const PREFLIGHT_FAILURE_CODE: i16 = -32002;
+
+/// Submit a signed transaction with `sendTransaction` if it fits in a packet and
+/// its previewed outflows are within the limits. A failed preflight is returned
+/// as [AtollError::PreflightFailure]
+pub(crate) async fn send_transaction(
+    client: &RpcRequest<Unconfigured>,
+    transaction: &Transaction,
+    preflight_commitment: Commitment,
+    skip_preflight: bool,
+    outflow_limits: Option<&OutflowLimits>,
+) -> AtollResult<String> {
+    transaction.check_size()?;
+
+    if let Some(outflow_limits) = outflow_limits {
+        BalancePreview::simulate(client, &transaction.serialize())
+            .await?
+            .enforce(outflow_limits)?;
+    }
+
+    client
+        .template()
+        .add_typed(
+            SendTransactionRequest::new(&transaction.serialize())
+                .change_preflight_commitment(preflight_commitment)
+                .change_skip_preflight(skip_preflight),
+        )
+        .request()
+        .await
+        .and_then(|response| response.into_result())
+        .map_err(preflight_failure)
+}
+
+/// Parse the simulation result the node attaches to a preflight failure
+fn preflight_failure(error: AtollError) -> AtollError {
+    let AtollError::Rpc {
+        code: PREFLIGHT_FAILURE_CODE,
+        data: Some(data),
+        ..
+    } = &error
+    else {
+        return error;
+    };
+
+    match serde_json::from_str::<SimulationResult>(data) {
+        Ok(SimulationResult {
+            err: Some(err),
+            logs,
+            units_consumed,
+            ..
+        }) => AtollError::PreflightFailure {
+            err,
+            logs: logs.unwrap_or_default(),
+            units_consumed,
+        },
+        _ => error,
+    }
+}
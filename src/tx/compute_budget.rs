@@ -0,0 +1,25 @@
+use crate::{tx::Instruction, Pubkey, COMPUTE_BUDGET_PROGRAM};
+
+/// The compute units a transaction may use when it does not request a limit,
+/// per instruction that is not a compute budget instruction
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// The most compute units a transaction can request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Request a compute unit limit for the transaction, which also lowers the
+/// priority fee since it is charged on the requested units
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![2u8];
+    data.extend_from_slice(&units.to_le_bytes());
+
+    Instruction::new(Pubkey::new(COMPUTE_BUDGET_PROGRAM)).change_data(&data)
+}
+
+/// Pay a priority fee of the price in micro-lamports for each requested compute unit
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let mut data = vec![3u8];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+
+    Instruction::new(Pubkey::new(COMPUTE_BUDGET_PROGRAM)).change_data(&data)
+}
@@ -0,0 +1,475 @@
+use crate::{
+    runtime::{self, Instant},
+    tx::{send_transaction, Transaction},
+    AtollError, AtollResult, Commitment, ConfirmationStatus, GetBlockHeightRequest,
+    GetSignatureStatusesRequest, OutflowLimits, RpcRequest, SendTransactionRequest,
+    SignatureStatus, Unconfigured,
+};
+#[cfg(not(feature = "wasm"))]
+use crate::{Notification, PubsubClient, SignatureNotification, Subscription};
+use futures_lite::future;
+use std::time::Duration;
+
+/// The default time between two `getSignatureStatuses` polls
+pub const DEFAULT_CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// The default time to wait for a confirmation, longer than a blockhash stays valid
+pub const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(120);
+/// How often the block height is checked for expiry while a subscription waits
+/// for the outcome of the transaction
+#[cfg(not(feature = "wasm"))]
+const SUBSCRIPTION_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How [send_and_confirm_transaction] waits for a transaction
+#[derive(Debug, Clone)]
+pub struct ConfirmationConfig {
+    commitment: Commitment,
+    poll_interval: Duration,
+    timeout: Duration,
+    skip_preflight: bool,
+    resend_interval: Option<Duration>,
+    max_blockhash_refreshes: u32,
+    outflow_limits: Option<OutflowLimits>,
+    #[cfg(not(feature = "wasm"))]
+    pubsub: Option<PubsubClient>,
+}
+
+impl Default for ConfirmationConfig {
+    fn default() -> Self {
+        ConfirmationConfig::new()
+    }
+}
+
+impl ConfirmationConfig {
+    /// Wait for [Commitment::Confirmed]
+    pub fn new() -> Self {
+        ConfirmationConfig {
+            commitment: Commitment::Confirmed,
+            poll_interval: DEFAULT_CONFIRMATION_POLL_INTERVAL,
+            timeout: DEFAULT_CONFIRMATION_TIMEOUT,
+            skip_preflight: false,
+            resend_interval: None,
+            max_blockhash_refreshes: 0,
+            outflow_limits: None,
+            #[cfg(not(feature = "wasm"))]
+            pubsub: None,
+        }
+    }
+
+    /// The commitment to wait for, also used for the preflight simulation
+    pub fn change_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = commitment;
+
+        self
+    }
+
+    pub fn change_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+
+        self
+    }
+
+    /// Give up with [AtollError::DeadlineExceeded] after this long even if the
+    /// blockhash did not expire yet
+    pub fn change_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// Skip the simulation the node runs before forwarding the transaction
+    pub fn change_skip_preflight(mut self, skip_preflight: bool) -> Self {
+        self.skip_preflight = skip_preflight;
+
+        self
+    }
+
+    /// Send the same signed transaction again at this interval until it is
+    /// confirmed or expires, since nodes drop transactions under congestion.
+    /// The transaction is only sent once when unset
+    pub fn change_resend_interval(mut self, resend_interval: Duration) -> Self {
+        self.resend_interval = Some(resend_interval).filter(|interval| !interval.is_zero());
+
+        self
+    }
+
+    /// How often [crate::tx::TransactionBuilder::send_and_confirm] fetches a new
+    /// blockhash, signs again and retries after the transaction expired
+    pub fn change_max_blockhash_refreshes(mut self, max_blockhash_refreshes: u32) -> Self {
+        self.max_blockhash_refreshes = max_blockhash_refreshes;
+
+        self
+    }
+
+    /// Preview the balance changes of the signers before sending and refuse to send
+    /// with [AtollError::OutflowLimitExceeded] if they exceed the limits
+    pub fn change_outflow_limits(mut self, outflow_limits: OutflowLimits) -> Self {
+        self.outflow_limits = Some(outflow_limits);
+
+        self
+    }
+
+    /// Wait for `signatureSubscribe` instead of polling, falling back to polling
+    /// if the subscription fails or the connection drops
+    #[cfg(not(feature = "wasm"))]
+    pub fn change_pubsub(mut self, pubsub: PubsubClient) -> Self {
+        self.pubsub = Some(pubsub);
+
+        self
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        self.commitment
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn skip_preflight(&self) -> bool {
+        self.skip_preflight
+    }
+
+    pub fn resend_interval(&self) -> Option<Duration> {
+        self.resend_interval
+    }
+
+    pub fn max_blockhash_refreshes(&self) -> u32 {
+        self.max_blockhash_refreshes
+    }
+
+    pub fn outflow_limits(&self) -> Option<&OutflowLimits> {
+        self.outflow_limits.as_ref()
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    pub fn pubsub(&self) -> Option<&PubsubClient> {
+        self.pubsub.as_ref()
+    }
+
+    /// The status a transaction has to reach
+    pub(crate) fn target_status(&self) -> ConfirmationStatus {
+        match self.commitment {
+            Commitment::Processed => ConfirmationStatus::Processed,
+            Commitment::Confirmed => ConfirmationStatus::Confirmed,
+            Commitment::Finalized | Commitment::InvalidCommitment => ConfirmationStatus::Finalized,
+        }
+    }
+}
+
+/// A transaction that reached the commitment it was waited for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionConfirmation {
+    /// The base58 encoded signature of the fee payer
+    pub signature: String,
+    /// The slot the transaction was processed in
+    pub slot: u64,
+    pub confirmation_status: ConfirmationStatus,
+}
+
+/// Send the signed transaction and wait until it reaches the commitment, with
+/// `signatureSubscribe` if the config has a pubsub client and by polling
+/// `getSignatureStatuses` otherwise. Fails with [AtollError::TransactionFailed] if
+/// it failed and with [AtollError::TransactionExpired] once the block height passes
+/// the last valid block height of its blockhash before the cluster processed it.
+/// The transaction is sent again at the resend interval of the config if it has one
+pub async fn send_and_confirm_transaction(
+    client: &RpcRequest<Unconfigured>,
+    transaction: &Transaction,
+    last_valid_block_height: u64,
+    config: &ConfirmationConfig,
+) -> AtollResult<TransactionConfirmation> {
+    let confirmed = send_and_wait(client, transaction, last_valid_block_height, config);
+
+    match config.resend_interval {
+        Some(resend_interval) => {
+            future::or(confirmed, resend(client, transaction, resend_interval)).await
+        }
+        None => confirmed.await,
+    }
+}
+
+async fn send_and_wait(
+    client: &RpcRequest<Unconfigured>,
+    transaction: &Transaction,
+    last_valid_block_height: u64,
+    config: &ConfirmationConfig,
+) -> AtollResult<TransactionConfirmation> {
+    #[cfg(not(feature = "wasm"))]
+    if let Some(pubsub) = &config.pubsub {
+        return send_and_subscribe(client, pubsub, transaction, last_valid_block_height, config)
+            .await;
+    }
+
+    let signature = send_transaction(
+        client,
+        transaction,
+        config.commitment,
+        config.skip_preflight,
+        config.outflow_limits.as_ref(),
+    )
+    .await?;
+
+    confirm_transaction(client, &signature, last_valid_block_height, config).await
+}
+
+/// Send the transaction at the interval until the future is dropped, skipping the
+/// preflight since it already passed and leaving the retries to this loop
+async fn resend(
+    client: &RpcRequest<Unconfigured>,
+    transaction: &Transaction,
+    resend_interval: Duration,
+) -> AtollResult<TransactionConfirmation> {
+    let transaction = transaction.serialize();
+
+    loop {
+        runtime::sleep(resend_interval).await;
+
+        // Failures are expected, like the transaction being processed meanwhile
+        client
+            .template()
+            .add_typed(
+                SendTransactionRequest::new(&transaction)
+                    .change_skip_preflight(true)
+                    .change_max_retries(0),
+            )
+            .request()
+            .await
+            .ok();
+    }
+}
+
+/// Poll `getSignatureStatuses` until the transaction with the base58 encoded
+/// signature reaches the commitment, see [send_and_confirm_transaction]
+pub async fn confirm_transaction(
+    client: &RpcRequest<Unconfigured>,
+    signature: &str,
+    last_valid_block_height: u64,
+    config: &ConfirmationConfig,
+) -> AtollResult<TransactionConfirmation> {
+    let started = Instant::now();
+
+    loop {
+        let status = signature_status(client, signature).await?;
+        if let Some(confirmation) = check_status(signature, status.as_ref(), config)? {
+            return Ok(confirmation);
+        }
+
+        // A processed transaction can no longer expire, it only has to reach the commitment
+        if status.is_none() {
+            let block_height = client
+                .template()
+                .add_typed(GetBlockHeightRequest::new().change_commitment(config.commitment))
+                .request()
+                .await?
+                .into_result()?;
+
+            if block_height > last_valid_block_height {
+                // The transaction may have landed between the status and the block height query
+                let status = signature_status(client, signature).await?;
+                if status.is_none() {
+                    return Err(AtollError::TransactionExpired {
+                        signature: signature.to_owned(),
+                        last_valid_block_height,
+                    });
+                }
+
+                if let Some(confirmation) = check_status(signature, status.as_ref(), config)? {
+                    return Ok(confirmation);
+                }
+            }
+        }
+
+        if started.elapsed() >= config.timeout {
+            return Err(AtollError::DeadlineExceeded);
+        }
+
+        runtime::sleep(config.poll_interval).await;
+    }
+}
+
+/// The status of the transaction with the base58 encoded signature, `None` until
+/// the node processed it
+pub(crate) async fn signature_status(
+    client: &RpcRequest<Unconfigured>,
+    signature: &str,
+) -> AtollResult<Option<SignatureStatus>> {
+    Ok(client
+        .template()
+        .add_typed(GetSignatureStatusesRequest::new(&[signature])?)
+        .request()
+        .await?
+        .into_result()?
+        .value
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .flatten())
+}
+
+/// The confirmation once the status reached the commitment, an error if the transaction failed
+pub(crate) fn check_status(
+    signature: &str,
+    status: Option<&SignatureStatus>,
+    config: &ConfirmationConfig,
+) -> AtollResult<Option<TransactionConfirmation>> {
+    let Some(status) = status else {
+        return Ok(None);
+    };
+
+    if let Some(err) = &status.err {
+        return Err(AtollError::TransactionFailed {
+            signature: signature.to_owned(),
+            err: err.clone(),
+        });
+    }
+
+    Ok(status
+        .is_at_least(config.target_status())
+        .then(|| TransactionConfirmation {
+            signature: signature.to_owned(),
+            slot: status.slot,
+            confirmation_status: config.target_status(),
+        }))
+}
+
+/// Subscribe to the signature before sending the transaction so that a
+/// transaction landing right away is not missed, polling if subscribing fails
+#[cfg(not(feature = "wasm"))]
+async fn send_and_subscribe(
+    client: &RpcRequest<Unconfigured>,
+    pubsub: &PubsubClient,
+    transaction: &Transaction,
+    last_valid_block_height: u64,
+    config: &ConfirmationConfig,
+) -> AtollResult<TransactionConfirmation> {
+    let subscription = match transaction.signature() {
+        Some(signature) => pubsub
+            .signature_subscription(&signature, config.commitment)
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let signature = send_transaction(
+        client,
+        transaction,
+        config.commitment,
+        config.skip_preflight,
+        config.outflow_limits.as_ref(),
+    )
+    .await?;
+
+    match subscription {
+        Some(subscription) => {
+            confirm_with_subscription(
+                client,
+                &signature,
+                subscription,
+                last_valid_block_height,
+                config,
+            )
+            .await
+        }
+        None => confirm_transaction(client, &signature, last_valid_block_height, config).await,
+    }
+}
+
+/// What ended the wait on a `signatureSubscribe` subscription
+#[cfg(not(feature = "wasm"))]
+enum SubscriptionOutcome {
+    Notified(SignatureNotification),
+    /// The subscription can no longer be relied on, like after the connection dropped
+    Lost,
+    Finished(AtollResult<TransactionConfirmation>),
+}
+
+/// Wait for the notification of the subscription while checking the block height
+/// for expiry, polling instead once the subscription is lost
+#[cfg(not(feature = "wasm"))]
+async fn confirm_with_subscription(
+    client: &RpcRequest<Unconfigured>,
+    signature: &str,
+    subscription: Subscription<SignatureNotification>,
+    last_valid_block_height: u64,
+    config: &ConfirmationConfig,
+) -> AtollResult<TransactionConfirmation> {
+    let started = Instant::now();
+
+    let notified = async {
+        match subscription.recv().await {
+            Some(Notification::Value(notification)) => SubscriptionOutcome::Notified(notification),
+            // A notification may have been missed while the connection was down
+            Some(Notification::Gap) | None => SubscriptionOutcome::Lost,
+        }
+    };
+    let expired = async {
+        loop {
+            runtime::sleep(SUBSCRIPTION_EXPIRY_CHECK_INTERVAL.min(config.timeout)).await;
+
+            if started.elapsed() >= config.timeout {
+                return SubscriptionOutcome::Finished(Err(AtollError::DeadlineExceeded));
+            }
+
+            let block_height = client
+                .template()
+                .add_typed(GetBlockHeightRequest::new().change_commitment(config.commitment))
+                .request()
+                .await
+                .and_then(|response| response.into_result());
+            match block_height {
+                Ok(block_height) if block_height > last_valid_block_height => break,
+                Ok(_) => continue,
+                Err(error) => return SubscriptionOutcome::Finished(Err(error)),
+            }
+        }
+
+        // The transaction may have landed in the last valid block
+        let status = match signature_status(client, signature).await {
+            Ok(status) => status,
+            Err(error) => return SubscriptionOutcome::Finished(Err(error)),
+        };
+        match (check_status(signature, status.as_ref(), config), status) {
+            (Ok(None), Some(_)) => SubscriptionOutcome::Lost,
+            (Ok(None), None) => {
+                SubscriptionOutcome::Finished(Err(AtollError::TransactionExpired {
+                    signature: signature.to_owned(),
+                    last_valid_block_height,
+                }))
+            }
+            (Ok(Some(confirmation)), _) => SubscriptionOutcome::Finished(Ok(confirmation)),
+            (Err(error), _) => SubscriptionOutcome::Finished(Err(error)),
+        }
+    };
+
+    match future::or(notified, expired).await {
+        SubscriptionOutcome::Notified(notification) => {
+            // The node ends the subscription after its only notification
+            subscription.forget();
+
+            match notification.value.err {
+                Some(err) => Err(AtollError::TransactionFailed {
+                    signature: signature.to_owned(),
+                    err,
+                }),
+                None => Ok(TransactionConfirmation {
+                    signature: signature.to_owned(),
+                    slot: notification.context.slot,
+                    confirmation_status: config.target_status(),
+                }),
+            }
+        }
+        SubscriptionOutcome::Lost => {
+            drop(subscription);
+
+            let config = config
+                .clone()
+                .change_timeout(config.timeout.saturating_sub(started.elapsed()));
+            confirm_transaction(client, signature, last_valid_block_height, &config).await
+        }
+        SubscriptionOutcome::Finished(result) => result,
+    }
+}
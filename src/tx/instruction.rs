@@ -0,0 +1,98 @@
+use crate::{AtollError, AtollResult, Pubkey};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// An account an instruction uses, whether it must sign the transaction and
+/// whether the instruction may change it
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AccountMeta {
+    /// An account the instruction may change
+    pub fn new(pubkey: Pubkey, is_signer: bool) -> Self {
+        AccountMeta {
+            pubkey,
+            is_signer,
+            is_writable: true,
+        }
+    }
+
+    /// An account the instruction only reads
+    pub fn new_readonly(pubkey: Pubkey, is_signer: bool) -> Self {
+        AccountMeta {
+            pubkey,
+            is_signer,
+            is_writable: false,
+        }
+    }
+}
+
+/// A call of a program with the accounts it uses and its input data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl Instruction {
+    /// Call the program without accounts or data
+    pub fn new(program_id: Pubkey) -> Self {
+        Instruction {
+            program_id,
+            accounts: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Pass an account to the program, in the order the program expects them
+    pub fn add_account(mut self, account: AccountMeta) -> Self {
+        self.accounts.push(account);
+
+        self
+    }
+
+    pub fn change_data(mut self, data: &[u8]) -> Self {
+        self.data = data.to_vec();
+
+        self
+    }
+
+    /// Borsh encode the data, the format most native and Anchor programs read
+    pub fn change_borsh_data<T: BorshSerialize>(mut self, data: &T) -> AtollResult<Self> {
+        self.data = data
+            .try_to_vec()
+            .map_err(|error| AtollError::InvalidTransaction(error.to_string()))?;
+
+        Ok(self)
+    }
+}
+
+/// An instruction whose program and accounts are indexes into the account keys
+/// of its message, the form it takes in the wire format
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct CompiledInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    pub data: Vec<u8>,
+}
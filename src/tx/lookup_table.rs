@@ -0,0 +1,357 @@
+use crate::{
+    tx::{AccountMeta, Instruction},
+    AtollError, AtollResult, GetAccountInfoRequest, Pubkey, RpcRequest, Unconfigured,
+    ADDRESS_LOOKUP_TABLE_PROGRAM, SYSTEM_PROGRAM_ID,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+/// The most addresses a lookup table holds
+pub const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+
+/// The size of the metadata in front of the addresses of a lookup table account
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+/// The addresses [create_lookup_table_with_addresses] adds per transaction, few
+/// enough for a transaction that also creates the table with a separate payer
+pub const LOOKUP_TABLE_EXTEND_BATCH: usize = 20;
+
+/// The slots after deactivation before a table can be closed, the length of the
+/// `SlotHashes` sysvar plus the deactivation slot itself
+const DEACTIVATION_COOLDOWN_SLOTS: u64 = 513;
+
+/// An address lookup table and the addresses it holds, which v0 messages
+/// reference by their one byte index instead of the 32 byte address
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AddressLookupTableAccount {
+    /// The address of the table account
+    pub key: Pubkey,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl AddressLookupTableAccount {
+    pub fn new(key: Pubkey, addresses: Vec<Pubkey>) -> Self {
+        AddressLookupTableAccount { key, addresses }
+    }
+
+    /// Fetch the addresses of the table, ready for [crate::tx::MessageV0::new]
+    pub async fn fetch(client: &RpcRequest<Unconfigured>, key: &Pubkey) -> AtollResult<Self> {
+        let state = AddressLookupTableState::fetch(client, key).await?;
+
+        Ok(AddressLookupTableAccount::new(*key, state.addresses))
+    }
+}
+
+/// Whether a lookup table can be used or closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupTableStatus {
+    Activated,
+    /// Deactivated but still usable until it cools down, an estimate counting slots
+    Deactivating {
+        remaining_slots: u64,
+    },
+    /// The table can be closed
+    Deactivated,
+}
+
+/// The decoded account of an address lookup table
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AddressLookupTableState {
+    /// `u64::MAX` until the table is deactivated
+    pub deactivation_slot: u64,
+    pub last_extended_slot: u64,
+    /// The number of addresses before the last extension, which only become
+    /// usable in the slot after it
+    pub last_extended_slot_start_index: u8,
+    /// The account allowed to change the table, `None` once frozen
+    pub authority: Option<Pubkey>,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl AddressLookupTableState {
+    /// Decode the data of a lookup table account
+    pub fn decode(data: &[u8]) -> AtollResult<Self> {
+        let malformed = |reason: &str| {
+            AtollError::MalformedTransaction(format!("Invalid lookup table account, {reason}"))
+        };
+
+        if data.len() < LOOKUP_TABLE_META_SIZE
+            || !(data.len() - LOOKUP_TABLE_META_SIZE).is_multiple_of(32)
+        {
+            return Err(malformed("the data has an unexpected length"));
+        }
+        // The `ProgramState` discriminator, `1` for an initialized table
+        if data[..4] != 1u32.to_le_bytes() {
+            return Err(malformed("the table is not initialized"));
+        }
+
+        let u64_at = |offset: usize| {
+            u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap()) // Never fails since the slice is 8 bytes
+        };
+        let pubkey_at = |offset: usize| {
+            Pubkey::new(data[offset..offset + 32].try_into().unwrap()) // Never fails since the slice is 32 bytes
+        };
+
+        Ok(AddressLookupTableState {
+            deactivation_slot: u64_at(4),
+            last_extended_slot: u64_at(12),
+            last_extended_slot_start_index: data[20],
+            authority: (data[21] == 1).then(|| pubkey_at(22)),
+            addresses: data[LOOKUP_TABLE_META_SIZE..]
+                .chunks_exact(32)
+                .map(pubkey_at_chunk)
+                .collect(),
+        })
+    }
+
+    /// Fetch and decode the lookup table account
+    pub async fn fetch(client: &RpcRequest<Unconfigured>, key: &Pubkey) -> AtollResult<Self> {
+        let account = client
+            .template()
+            .add_typed(GetAccountInfoRequest::new(&key.to_base58()))
+            .request()
+            .await?
+            .into_result()?
+            .value
+            .ok_or_else(|| {
+                AtollError::NotFound(format!("The lookup table {key} does not exist"))
+            })?;
+
+        if account.owner != Pubkey::new(ADDRESS_LOOKUP_TABLE_PROGRAM).to_base58() {
+            return Err(AtollError::NotFound(format!(
+                "The account {key} is not an address lookup table"
+            )));
+        }
+
+        AddressLookupTableState::decode(&BASE64.decode(account.data.0.unwrap_or_default())?)
+    }
+
+    /// The status of the table at the current slot
+    pub fn status(&self, current_slot: u64) -> LookupTableStatus {
+        if self.deactivation_slot == u64::MAX {
+            return LookupTableStatus::Activated;
+        }
+
+        match self
+            .deactivation_slot
+            .saturating_add(DEACTIVATION_COOLDOWN_SLOTS)
+            .saturating_sub(current_slot)
+        {
+            0 => LookupTableStatus::Deactivated,
+            remaining_slots => LookupTableStatus::Deactivating { remaining_slots },
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.authority.is_none()
+    }
+}
+
+fn pubkey_at_chunk(chunk: &[u8]) -> Pubkey {
+    Pubkey::new(chunk.try_into().unwrap()) // Never fails since chunks are 32 bytes
+}
+
+/// The address of the table the authority creates at the recent slot and its bump seed
+pub fn lookup_table_address(authority: &Pubkey, recent_slot: u64) -> AtollResult<(Pubkey, u8)> {
+    Pubkey::find_program_address(
+        &[&authority.0, &recent_slot.to_le_bytes()],
+        &Pubkey::new(ADDRESS_LOOKUP_TABLE_PROGRAM),
+    )
+}
+
+/// Create a lookup table owned by the authority, paid by the payer. The recent
+/// slot must be a slot the node has seen recently, like the current `getSlot`.
+/// Returns the instruction and the address of the table
+pub fn create_lookup_table(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+) -> AtollResult<(Instruction, Pubkey)> {
+    let (table, bump_seed) = lookup_table_address(authority, recent_slot)?;
+
+    let mut data = 0u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&recent_slot.to_le_bytes());
+    data.push(bump_seed);
+
+    let instruction = lookup_table_instruction(data)
+        .add_account(AccountMeta::new(table, false))
+        .add_account(AccountMeta::new_readonly(*authority, false))
+        .add_account(AccountMeta::new(*payer, true))
+        .add_account(AccountMeta::new_readonly(
+            Pubkey::new(SYSTEM_PROGRAM_ID),
+            false,
+        ));
+
+    Ok((instruction, table))
+}
+
+/// Make the table immutable by removing its authority. Only tables with
+/// addresses can be frozen
+pub fn freeze_lookup_table(table: &Pubkey, authority: &Pubkey) -> Instruction {
+    lookup_table_instruction(1u32.to_le_bytes().to_vec())
+        .add_account(AccountMeta::new(*table, false))
+        .add_account(AccountMeta::new_readonly(*authority, true))
+}
+
+/// Append addresses to the table. The payer funds the rent of the larger
+/// account, it can be left out if the table already holds enough lamports
+pub fn extend_lookup_table(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: Option<&Pubkey>,
+    new_addresses: &[Pubkey],
+) -> Instruction {
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&(new_addresses.len() as u64).to_le_bytes());
+    new_addresses
+        .iter()
+        .for_each(|address| data.extend_from_slice(&address.0));
+
+    let mut instruction = lookup_table_instruction(data)
+        .add_account(AccountMeta::new(*table, false))
+        .add_account(AccountMeta::new_readonly(*authority, true));
+    if let Some(payer) = payer {
+        instruction = instruction
+            .add_account(AccountMeta::new(*payer, true))
+            .add_account(AccountMeta::new_readonly(
+                Pubkey::new(SYSTEM_PROGRAM_ID),
+                false,
+            ));
+    }
+
+    instruction
+}
+
+/// Start the cooldown after which the table can be closed
+pub fn deactivate_lookup_table(table: &Pubkey, authority: &Pubkey) -> Instruction {
+    lookup_table_instruction(3u32.to_le_bytes().to_vec())
+        .add_account(AccountMeta::new(*table, false))
+        .add_account(AccountMeta::new_readonly(*authority, true))
+}
+
+/// Close a deactivated table, sending its lamports to the recipient
+pub fn close_lookup_table(table: &Pubkey, authority: &Pubkey, recipient: &Pubkey) -> Instruction {
+    lookup_table_instruction(4u32.to_le_bytes().to_vec())
+        .add_account(AccountMeta::new(*table, false))
+        .add_account(AccountMeta::new_readonly(*authority, true))
+        .add_account(AccountMeta::new(*recipient, false))
+}
+
+/// The instructions creating a table and adding the addresses, grouped by the
+/// transaction they fit in. Send the groups in order, each after the previous one
+/// landed, and use the table from the slot after the last one landed
+pub fn create_lookup_table_with_addresses(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+    addresses: &[Pubkey],
+) -> AtollResult<(Pubkey, Vec<Vec<Instruction>>)> {
+    if addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(AtollError::InvalidTransaction(format!(
+            "A lookup table holds at most {LOOKUP_TABLE_MAX_ADDRESSES} addresses, got {}",
+            addresses.len()
+        )));
+    }
+
+    let (create, table) = create_lookup_table(authority, payer, recent_slot)?;
+
+    let mut transactions = vec![vec![create]];
+    for (index, batch) in addresses.chunks(LOOKUP_TABLE_EXTEND_BATCH).enumerate() {
+        let extend = extend_lookup_table(&table, authority, Some(payer), batch);
+
+        match index {
+            0 => transactions[0].push(extend),
+            _ => transactions.push(vec![extend]),
+        }
+    }
+
+    Ok((table, transactions))
+}
+
+fn lookup_table_instruction(data: Vec<u8>) -> Instruction {
+    Instruction::new(Pubkey::new(ADDRESS_LOOKUP_TABLE_PROGRAM)).change_data(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTHORITY: Pubkey = Pubkey::new([1; 32]);
+
+    /// The account data of a table as the lookup table program stores it
+    fn table_data(
+        deactivation_slot: u64,
+        authority: Option<&Pubkey>,
+        addresses: &[Pubkey],
+    ) -> Vec<u8> {
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&deactivation_slot.to_le_bytes());
+        data.extend_from_slice(&300_000_000u64.to_le_bytes());
+        data.push(2);
+        match authority {
+            Some(authority) => {
+                data.push(1);
+                data.extend_from_slice(&authority.to_bytes());
+            }
+            None => data.extend_from_slice(&[0; 33]),
+        }
+        // Padding up to the addresses
+        data.extend_from_slice(&[0; 2]);
+        addresses
+            .iter()
+            .for_each(|address| data.extend_from_slice(&address.to_bytes()));
+
+        data
+    }
+
+    #[test]
+    fn decodes_the_table_metadata_and_addresses() {
+        let addresses = [Pubkey::new([2; 32]), Pubkey::new([3; 32])];
+        let data = table_data(u64::MAX, Some(&AUTHORITY), &addresses);
+
+        assert_eq!(data.len(), LOOKUP_TABLE_META_SIZE + 64);
+        assert_eq!(
+            AddressLookupTableState::decode(&data).unwrap(),
+            AddressLookupTableState {
+                deactivation_slot: u64::MAX,
+                last_extended_slot: 300_000_000,
+                last_extended_slot_start_index: 2,
+                authority: Some(AUTHORITY),
+                addresses: addresses.to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_frozen_and_empty_tables() {
+        let state = AddressLookupTableState::decode(&table_data(1_000, None, &[])).unwrap();
+
+        assert_eq!(state.authority, None);
+        assert!(state.addresses.is_empty());
+        assert_eq!(
+            state.status(1_100),
+            LookupTableStatus::Deactivating {
+                remaining_slots: 413
+            }
+        );
+        assert_eq!(state.status(1_513), LookupTableStatus::Deactivated);
+    }
+
+    #[test]
+    fn rejects_truncated_and_uninitialized_tables() {
+        let data = table_data(u64::MAX, Some(&AUTHORITY), &[Pubkey::new([2; 32])]);
+
+        for length in (0..data.len()).filter(|length| *length != LOOKUP_TABLE_META_SIZE) {
+            assert!(
+                matches!(
+                    AddressLookupTableState::decode(&data[..length]),
+                    Err(AtollError::MalformedTransaction(_))
+                ),
+                "{length} bytes"
+            );
+        }
+
+        let mut uninitialized = data.clone();
+        uninitialized[0] = 0;
+        assert!(AddressLookupTableState::decode(&uninitialized).is_err());
+    }
+}
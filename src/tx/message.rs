@@ -0,0 +1,409 @@
+use crate::{
+    tx::{AccountMeta, CompiledInstruction, Instruction},
+    write_compact_u16, AtollError, AtollResult, Pubkey,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::{fmt, str::FromStr};
+use serde::{Deserialize, Serialize};
+use web3utilities::Utilities;
+
+/// The most accounts a message can reference, instructions index them with a `u8`
+pub const MAX_MESSAGE_ACCOUNTS: usize = 256;
+
+/// The hash of a recent block a transaction is tied to. The transaction can only
+/// land while the blockhash is recent. It is displayed and parsed as base58
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, BorshSerialize, BorshDeserialize)]
+pub struct Blockhash(pub [u8; 32]);
+
+impl Blockhash {
+    /// Parse a base58 encoded blockhash, as returned by `getLatestBlockhash`
+    pub fn from_base58(value: &str) -> AtollResult<Self> {
+        let bytes = Utilities::base58_to_bytes(value).map_err(AtollError::Utilities)?;
+
+        Ok(Blockhash(
+            Utilities::to_32byte_array(&bytes).map_err(AtollError::Utilities)?,
+        ))
+    }
+
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.0).into_string()
+    }
+}
+
+impl FromStr for Blockhash {
+    type Err = AtollError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Blockhash::from_base58(value)
+    }
+}
+
+impl fmt::Display for Blockhash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_base58())
+    }
+}
+
+impl fmt::Debug for Blockhash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Blockhash").field(&self.to_base58()).finish()
+    }
+}
+
+/// How many of the account keys sign and how many are read-only, which with
+/// the order of the keys tells the runtime how each account is used
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageHeader {
+    pub num_required_signatures: u8,
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+}
+
+/// A legacy message, the data every signer of a transaction signs. The accounts
+/// of the instructions are deduplicated into one list ordered as writable signers
+/// starting with the fee payer, read-only signers, writable and then read-only
+/// accounts, each group in the order the accounts first appear
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub header: MessageHeader,
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Blockhash,
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+impl Message {
+    /// Compile the instructions for the payer, which signs the transaction and
+    /// pays its fees. The blockhash is set with [Message::change_recent_blockhash]
+    /// or when the transaction is signed
+    pub fn new(instructions: &[Instruction], payer: &Pubkey) -> AtollResult<Self> {
+        let mut accounts = merge_accounts(instructions, payer);
+
+        if accounts.len() > MAX_MESSAGE_ACCOUNTS {
+            return Err(AtollError::InvalidTransaction(format!(
+                "The instructions use {} accounts, a message holds at most {MAX_MESSAGE_ACCOUNTS}",
+                accounts.len()
+            )));
+        }
+
+        // A stable sort keeps the payer first and the order the accounts appeared in
+        accounts.sort_by_key(|account| (!account.is_signer, !account.is_writable));
+
+        let header = compile_header(&accounts);
+        let account_keys = accounts
+            .into_iter()
+            .map(|account| account.pubkey)
+            .collect::<Vec<Pubkey>>();
+        let instructions = compile_instructions(instructions, &account_keys);
+
+        Ok(Message {
+            header,
+            account_keys,
+            recent_blockhash: Blockhash::default(),
+            instructions,
+        })
+    }
+
+    pub fn change_recent_blockhash(mut self, recent_blockhash: Blockhash) -> Self {
+        self.recent_blockhash = recent_blockhash;
+
+        self
+    }
+
+    /// The account paying the fees, the first signer
+    pub fn fee_payer(&self) -> Option<&Pubkey> {
+        self.signers().first()
+    }
+
+    /// The accounts that must sign, in the order of their signatures
+    pub fn signers(&self) -> &[Pubkey] {
+        let signers = (self.header.num_required_signatures as usize).min(self.account_keys.len());
+
+        &self.account_keys[..signers]
+    }
+
+    pub fn is_signer(&self, index: usize) -> bool {
+        index < self.header.num_required_signatures as usize
+    }
+
+    /// Whether the instructions may change the account at `index`
+    pub fn is_writable(&self, index: usize) -> bool {
+        let num_required_signatures = self.header.num_required_signatures as usize;
+
+        if index < num_required_signatures {
+            index
+                < num_required_signatures
+                    .saturating_sub(self.header.num_readonly_signed_accounts as usize)
+        } else {
+            index < self.account_keys.len()
+                && index
+                    < self
+                        .account_keys
+                        .len()
+                        .saturating_sub(self.header.num_readonly_unsigned_accounts as usize)
+        }
+    }
+
+    /// The message in the wire format, the bytes each signer signs
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = vec![
+            self.header.num_required_signatures,
+            self.header.num_readonly_signed_accounts,
+            self.header.num_readonly_unsigned_accounts,
+        ];
+
+        write_compact_u16(&mut bytes, self.account_keys.len() as u16);
+        self.account_keys
+            .iter()
+            .for_each(|key| bytes.extend_from_slice(&key.0));
+        bytes.extend_from_slice(&self.recent_blockhash.0);
+
+        serialize_instructions(&mut bytes, &self.instructions);
+
+        bytes
+    }
+}
+
+/// The payer followed by the programs and accounts of the instructions in the order
+/// they first appear, each account once with the widest signer and writable flags
+pub(crate) fn merge_accounts(instructions: &[Instruction], payer: &Pubkey) -> Vec<AccountMeta> {
+    let mut accounts = vec![AccountMeta::new(*payer, true)];
+
+    let mut merge = |account: AccountMeta| match accounts
+        .iter_mut()
+        .find(|merged| merged.pubkey == account.pubkey)
+    {
+        Some(merged) => {
+            merged.is_signer |= account.is_signer;
+            merged.is_writable |= account.is_writable;
+        }
+        None => accounts.push(account),
+    };
+
+    for instruction in instructions {
+        merge(AccountMeta::new_readonly(instruction.program_id, false));
+        instruction
+            .accounts
+            .iter()
+            .for_each(|account| merge(*account));
+    }
+
+    accounts
+}
+
+/// The header of accounts already sorted signers first and writable first within
+/// signers and non-signers
+pub(crate) fn compile_header(accounts: &[AccountMeta]) -> MessageHeader {
+    let count = |is_signer: bool, is_writable: bool| {
+        accounts
+            .iter()
+            .filter(|account| account.is_signer == is_signer && account.is_writable == is_writable)
+            .count() as u8
+    };
+
+    MessageHeader {
+        num_required_signatures: accounts.iter().filter(|account| account.is_signer).count() as u8,
+        num_readonly_signed_accounts: count(true, false),
+        num_readonly_unsigned_accounts: count(false, false),
+    }
+}
+
+/// Replace the programs and accounts of the instructions by their index in the keys
+pub(crate) fn compile_instructions(
+    instructions: &[Instruction],
+    keys: &[Pubkey],
+) -> Vec<CompiledInstruction> {
+    let index_of = |pubkey: &Pubkey| {
+        keys.iter()
+            .position(|key| key == pubkey)
+            .unwrap_or_default() as u8 // Never missing since every account was merged
+    };
+
+    instructions
+        .iter()
+        .map(|instruction| CompiledInstruction {
+            program_id_index: index_of(&instruction.program_id),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account| index_of(&account.pubkey))
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect()
+}
+
+/// Append the instructions in the wire format
+pub(crate) fn serialize_instructions(bytes: &mut Vec<u8>, instructions: &[CompiledInstruction]) {
+    write_compact_u16(bytes, instructions.len() as u16);
+    for instruction in instructions {
+        bytes.push(instruction.program_id_index);
+        write_compact_u16(bytes, instruction.accounts.len() as u16);
+        bytes.extend_from_slice(&instruction.accounts);
+        write_compact_u16(bytes, instruction.data.len() as u16);
+        bytes.extend_from_slice(&instruction.data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tx::VersionedMessage, WireReader, SYSTEM_PROGRAM_ID};
+
+    const PAYER: Pubkey = Pubkey::new([1; 32]);
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new([byte; 32])
+    }
+
+    #[test]
+    fn orders_and_deduplicates_accounts() {
+        let program = key(9);
+        let instruction = Instruction::new(program)
+            .add_account(AccountMeta::new_readonly(key(2), false))
+            .add_account(AccountMeta::new(key(3), false))
+            .add_account(AccountMeta::new_readonly(key(4), true))
+            .add_account(AccountMeta::new(key(5), true))
+            // The same account again, now writable
+            .add_account(AccountMeta::new(key(2), false))
+            .add_account(AccountMeta::new_readonly(PAYER, false));
+
+        let message = Message::new(&[instruction], &PAYER).unwrap();
+
+        assert_eq!(
+            message.account_keys,
+            vec![PAYER, key(5), key(4), key(2), key(3), program]
+        );
+        assert_eq!(
+            message.header,
+            MessageHeader {
+                num_required_signatures: 3,
+                num_readonly_signed_accounts: 1,
+                num_readonly_unsigned_accounts: 1,
+            }
+        );
+        assert_eq!(message.instructions[0].program_id_index, 5);
+        assert_eq!(message.instructions[0].accounts, vec![3, 4, 2, 1, 3, 0]);
+        assert_eq!(
+            (0..6)
+                .map(|index| (message.is_signer(index), message.is_writable(index)))
+                .collect::<Vec<_>>(),
+            vec![
+                (true, true),
+                (true, true),
+                (true, false),
+                (false, true),
+                (false, true),
+                (false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_more_accounts_than_a_message_holds() {
+        let instruction = (0..=MAX_MESSAGE_ACCOUNTS as u16).fold(
+            Instruction::new(Pubkey::new(SYSTEM_PROGRAM_ID)),
+            |instruction, index| {
+                let mut bytes = [0xff; 32];
+                bytes[..2].copy_from_slice(&index.to_le_bytes());
+
+                instruction.add_account(AccountMeta::new(Pubkey::new(bytes), false))
+            },
+        );
+
+        assert!(matches!(
+            Message::new(&[instruction], &PAYER),
+            Err(AtollError::InvalidTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn encodes_compact_u16_boundaries() {
+        for (value, encoded) in [
+            (0u16, vec![0x00]),
+            (127, vec![0x7f]),
+            (128, vec![0x80, 0x01]),
+            (16383, vec![0xff, 0x7f]),
+            (16384, vec![0x80, 0x80, 0x01]),
+            (u16::MAX, vec![0xff, 0xff, 0x03]),
+        ] {
+            let mut bytes = Vec::new();
+            write_compact_u16(&mut bytes, value);
+            assert_eq!(bytes, encoded, "{value}");
+
+            let mut reader = WireReader::new(&bytes);
+            assert_eq!(reader.read_compact_u16().unwrap(), value);
+            assert!(reader.remaining().is_empty());
+        }
+    }
+
+    #[test]
+    fn serializes_compact_u16_lengths() {
+        let program = key(0xff);
+        // The payer and 126 other accounts, which with the program make 128 keys
+        let accounts = (2..=127u8).fold(
+            Instruction::new(program).add_account(AccountMeta::new(PAYER, true)),
+            |instruction, byte| instruction.add_account(AccountMeta::new(key(byte), false)),
+        );
+        let instructions = [
+            accounts.change_data(&[7; 16384]),
+            Instruction::new(program).change_data(&[8; 16383]),
+        ];
+
+        let message = Message::new(&instructions, &PAYER).unwrap();
+        let bytes = message.serialize();
+
+        assert_eq!(message.account_keys.len(), 128);
+        // The header is followed by the number of keys
+        assert_eq!(bytes[3..5], [0x80, 0x01]);
+
+        // The number of instructions, the program and the number of accounts
+        let first = 5 + 128 * 32 + 32;
+        assert_eq!(bytes[first..first + 3], [0x02, 127, 0x7f]);
+        let first_data = first + 3 + 127;
+        assert_eq!(bytes[first_data..first_data + 3], [0x80, 0x80, 0x01]);
+
+        let second = first_data + 3 + 16384;
+        assert_eq!(bytes[second..second + 4], [127, 0x00, 0xff, 0x7f]);
+        assert_eq!(bytes.len(), second + 4 + 16383);
+
+        assert_eq!(
+            VersionedMessage::deserialize(&bytes).unwrap(),
+            VersionedMessage::Legacy(message)
+        );
+    }
+
+    #[test]
+    fn round_trips_through_the_wire_format() {
+        let message = Message::new(
+            &[
+                Instruction::new(Pubkey::new(SYSTEM_PROGRAM_ID))
+                    .add_account(AccountMeta::new(PAYER, true))
+                    .add_account(AccountMeta::new(key(2), false))
+                    .change_data(&[2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]),
+                Instruction::new(key(9))
+                    .add_account(AccountMeta::new_readonly(key(3), true))
+                    .add_account(AccountMeta::new_readonly(key(4), false)),
+            ],
+            &PAYER,
+        )
+        .unwrap()
+        .change_recent_blockhash(Blockhash([7; 32]));
+
+        let decoded = VersionedMessage::deserialize(&message.serialize()).unwrap();
+
+        assert_eq!(decoded, VersionedMessage::Legacy(message.clone()));
+        assert_eq!(decoded.serialize(), message.serialize());
+    }
+}
@@ -0,0 +1,29 @@
+mod instruction;
+pub use instruction::*;
+
+mod message;
+pub use message::*;
+
+mod transaction;
+pub use transaction::*;
+
+mod versioned;
+pub use versioned::*;
+
+mod lookup_table;
+pub use lookup_table::*;
+
+mod offline;
+pub use offline::*;
+
+mod builder;
+pub use builder::*;
+
+mod compute_budget;
+pub use compute_budget::*;
+
+mod priority_fee;
+pub use priority_fee::*;
+
+mod confirm;
+pub use confirm::*;
@@ -0,0 +1,102 @@
+use crate::{
+    tx::{Transaction, VersionedMessage},
+    verify_signature, AtollError, AtollResult, Pubkey, Signer,
+};
+use core::{fmt, str::FromStr};
+
+/// A signature made apart from its transaction, written as `<signer>=<signature>`
+/// both base58 encoded, the form `solana` CLI commands take with `--signer`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DetachedSignature {
+    pub signer: Pubkey,
+    pub signature: [u8; 64],
+}
+
+impl DetachedSignature {
+    /// Check the signature against the message
+    pub fn verify(&self, message: &VersionedMessage) -> AtollResult<()> {
+        verify_signature(&self.signer, &message.serialize(), &self.signature)
+    }
+}
+
+impl FromStr for DetachedSignature {
+    type Err = AtollError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (signer, signature) = value.trim().split_once('=').ok_or_else(|| {
+            AtollError::InvalidSignature(format!("Expected `<signer>=<signature>`, got `{value}`"))
+        })?;
+
+        let signature = bs58::decode(signature)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                AtollError::InvalidSignature(format!("Invalid base58 signature `{signature}`"))
+            })?;
+
+        Ok(DetachedSignature {
+            signer: Pubkey::from_base58(signer)?,
+            signature,
+        })
+    }
+}
+
+impl fmt::Display for DetachedSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}={}",
+            self.signer,
+            bs58::encode(self.signature).into_string()
+        )
+    }
+}
+
+impl fmt::Debug for DetachedSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DetachedSignature({self})")
+    }
+}
+
+/// Sign a message on a machine that never sees the transaction or the network,
+/// like an air-gapped one given the message from [VersionedMessage::to_base64].
+/// Fails if the signer does not sign the message
+pub async fn sign_offline(
+    message: &VersionedMessage,
+    signer: &dyn Signer,
+) -> AtollResult<DetachedSignature> {
+    let pubkey = signer.pubkey();
+    if !message.signers().contains(&pubkey) {
+        return Err(AtollError::InvalidTransaction(format!(
+            "{pubkey} is not a signer of the message"
+        )));
+    }
+
+    Ok(DetachedSignature {
+        signer: pubkey,
+        signature: signer.sign_message(&message.serialize()).await?,
+    })
+}
+
+impl Transaction {
+    /// Assemble a sendable transaction from a message and the detached signatures
+    /// of all its signers, checking each of them
+    pub fn from_detached_signatures(
+        message: VersionedMessage,
+        signatures: &[DetachedSignature],
+    ) -> AtollResult<Self> {
+        let mut transaction = Transaction::new(message);
+
+        signatures.iter().try_for_each(|detached| {
+            transaction.add_signature(&detached.signer, detached.signature)
+        })?;
+
+        match transaction.missing_signers().first() {
+            Some(missing) => Err(AtollError::InvalidTransaction(format!(
+                "The signature of {missing} is missing"
+            ))),
+            None => Ok(transaction),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    tx::{set_compute_unit_price, Instruction},
+    AtollResult, GetRecentPrioritizationFeesRequest, Pubkey, RpcRequest, Unconfigured,
+    MAX_PRIORITIZATION_FEE_ACCOUNTS,
+};
+
+/// The default percentile of the recent fees [PriorityFeeEstimator] recommends
+pub const DEFAULT_FEE_PERCENTILE: u8 = 75;
+
+/// Recommends a compute unit price from the fees recently paid by transactions
+/// writing to the same accounts, as reported by `getRecentPrioritizationFees`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFeeEstimator {
+    percentile: u8,
+    min_price: u64,
+    max_price: u64,
+}
+
+impl Default for PriorityFeeEstimator {
+    fn default() -> Self {
+        PriorityFeeEstimator::new()
+    }
+}
+
+impl PriorityFeeEstimator {
+    /// Recommend the [DEFAULT_FEE_PERCENTILE] of the recent fees without caps
+    pub fn new() -> Self {
+        PriorityFeeEstimator {
+            percentile: DEFAULT_FEE_PERCENTILE,
+            min_price: 0,
+            max_price: u64::MAX,
+        }
+    }
+
+    /// The percentile of the recent fees from `0` to `100` to recommend, higher
+    /// lands faster under contention for more lamports
+    pub fn change_percentile(mut self, percentile: u8) -> Self {
+        self.percentile = percentile.min(100);
+
+        self
+    }
+
+    /// The lowest price in micro-lamports per compute unit to recommend
+    pub fn change_min_price(mut self, min_price: u64) -> Self {
+        self.min_price = min_price;
+
+        self
+    }
+
+    /// The highest price in micro-lamports per compute unit to recommend
+    pub fn change_max_price(mut self, max_price: u64) -> Self {
+        self.max_price = max_price;
+
+        self
+    }
+
+    /// The recommended price for the fees of recent slots, the minimum price
+    /// without fees. The maximum price wins when the caps overlap
+    pub fn estimate_from_fees(&self, fees: &[u64]) -> u64 {
+        let mut fees = fees.to_vec();
+        fees.sort_unstable();
+
+        // Nearest rank, the smallest fee at or above the percentile
+        let price = match fees.len() {
+            0 => 0,
+            len => {
+                let rank = (self.percentile as usize * len).div_ceil(100);
+                fees[rank.saturating_sub(1)]
+            }
+        };
+
+        price.max(self.min_price).min(self.max_price)
+    }
+
+    /// Fetch the recent fees for the writable accounts and recommend a price in
+    /// micro-lamports per compute unit. Only the first
+    /// [MAX_PRIORITIZATION_FEE_ACCOUNTS] accounts are considered
+    pub async fn estimate(
+        &self,
+        client: &RpcRequest<Unconfigured>,
+        writable_accounts: &[Pubkey],
+    ) -> AtollResult<u64> {
+        let addresses = writable_accounts
+            .iter()
+            .take(MAX_PRIORITIZATION_FEE_ACCOUNTS)
+            .map(Pubkey::to_base58)
+            .collect::<Vec<String>>();
+        let addresses = addresses.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        let fees = client
+            .template()
+            .add_typed(GetRecentPrioritizationFeesRequest::new().change_addresses(&addresses)?)
+            .request()
+            .await?
+            .into_result()?;
+
+        Ok(self.estimate_from_fees(
+            &fees
+                .iter()
+                .map(|fee| fee.prioritization_fee)
+                .collect::<Vec<u64>>(),
+        ))
+    }
+
+    /// Recommend a price for the accounts the instructions write to and return
+    /// it as a [set_compute_unit_price] instruction
+    pub async fn estimate_instruction(
+        &self,
+        client: &RpcRequest<Unconfigured>,
+        instructions: &[Instruction],
+    ) -> AtollResult<Instruction> {
+        let mut writable_accounts = Vec::<Pubkey>::new();
+        instructions
+            .iter()
+            .flat_map(|instruction| &instruction.accounts)
+            .filter(|account| account.is_writable)
+            .for_each(|account| {
+                if !writable_accounts.contains(&account.pubkey) {
+                    writable_accounts.push(account.pubkey);
+                }
+            });
+
+        Ok(set_compute_unit_price(
+            self.estimate(client, &writable_accounts).await?,
+        ))
+    }
+}
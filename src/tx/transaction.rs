@@ -0,0 +1,383 @@
+use crate::{
+    tx::{Blockhash, VersionedMessage},
+    verify_signature, verify_transaction, write_compact_u16, AtollError, AtollResult, Pubkey,
+    Signer, WireReader,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+/// The signature of a signer that has not signed yet
+pub const PLACEHOLDER_SIGNATURE: [u8; 64] = [0u8; 64];
+
+/// The most bytes a serialized transaction takes, the IPv6 minimum MTU minus
+/// the IP and UDP headers
+pub const PACKET_DATA_SIZE: usize = 1232;
+
+/// A legacy or v0 message and a signature of each of its signers, in the wire
+/// format `sendTransaction` expects once serialized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    /// One signature per signer of the message in the same order,
+    /// [PLACEHOLDER_SIGNATURE] until signed
+    pub signatures: Vec<[u8; 64]>,
+    pub message: VersionedMessage,
+}
+
+/// A transaction decoded from the wire, where it may carry a legacy or a v0 message
+pub type VersionedTransaction = Transaction;
+
+impl Transaction {
+    /// An unsigned transaction of a [crate::tx::Message] or [crate::tx::MessageV0]
+    pub fn new(message: impl Into<VersionedMessage>) -> Self {
+        let message = message.into();
+
+        Transaction {
+            signatures: vec![
+                PLACEHOLDER_SIGNATURE;
+                message.header().num_required_signatures as usize
+            ],
+            message,
+        }
+    }
+
+    /// Set the blockhash and sign with every signer of the message. Fails if a
+    /// signer does not sign the message or a signer of the message is missing
+    pub async fn sign(
+        &mut self,
+        signers: &[&dyn Signer],
+        recent_blockhash: Blockhash,
+    ) -> AtollResult<()> {
+        if *self.message.recent_blockhash() != recent_blockhash {
+            // Signatures over the previous blockhash are no longer valid
+            match &mut self.message {
+                VersionedMessage::Legacy(message) => message.recent_blockhash = recent_blockhash,
+                VersionedMessage::V0(message) => message.recent_blockhash = recent_blockhash,
+            }
+            self.signatures =
+                vec![PLACEHOLDER_SIGNATURE; self.message.header().num_required_signatures as usize];
+        }
+
+        self.partial_sign(signers).await?;
+
+        match self.missing_signers().first() {
+            Some(missing) => Err(AtollError::InvalidTransaction(format!(
+                "The signature of {missing} is missing"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Sign the message as it is with some of its signers, leaving the other
+    /// signatures as they are so the transaction can be passed on to the
+    /// remaining signers. Fails if a signer does not sign the message or
+    /// returns a signature that does not verify
+    pub async fn partial_sign(&mut self, signers: &[&dyn Signer]) -> AtollResult<()> {
+        let message = self.message.serialize();
+
+        for signer in signers {
+            let pubkey = signer.pubkey();
+            self.signer_position(&pubkey)?;
+
+            let signature = signer.sign_message(&message).await?;
+            self.add_signature(&pubkey, signature)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add a signature made elsewhere over [VersionedMessage::serialize], after
+    /// checking it against the message
+    pub fn add_signature(&mut self, signer: &Pubkey, signature: [u8; 64]) -> AtollResult<()> {
+        let position = self.signer_position(signer)?;
+        verify_signature(signer, &self.message.serialize(), &signature)?;
+
+        let slot = self
+            .signatures
+            .get_mut(position)
+            .ok_or(AtollError::MalformedTransaction(format!(
+                "The transaction has no signature slot for signer {}",
+                position
+            )))?;
+        *slot = signature;
+
+        Ok(())
+    }
+
+    /// The signers whose signature is still the [PLACEHOLDER_SIGNATURE]
+    pub fn missing_signers(&self) -> Vec<String> {
+        self.message
+            .signers()
+            .iter()
+            .zip(&self.signatures)
+            .filter(|(_, signature)| **signature == PLACEHOLDER_SIGNATURE)
+            .map(|(signer, _)| signer.to_base58())
+            .collect()
+    }
+
+    /// Whether every signer signed, which does not check the signatures, see [Self::verify]
+    pub fn is_fully_signed(&self) -> bool {
+        self.missing_signers().is_empty()
+    }
+
+    /// The base58 encoded signature of the fee payer, which identifies the transaction
+    pub fn signature(&self) -> Option<String> {
+        self.signatures
+            .first()
+            .map(|signature| bs58::encode(signature).into_string())
+    }
+
+    /// Check every signature against the message
+    pub fn verify(&self) -> AtollResult<()> {
+        verify_transaction(&self.serialize())
+    }
+
+    /// The transaction in the wire format
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        write_compact_u16(&mut bytes, self.signatures.len() as u16);
+        self.signatures
+            .iter()
+            .for_each(|signature| bytes.extend_from_slice(signature));
+        bytes.extend_from_slice(&self.message.serialize());
+
+        bytes
+    }
+
+    /// Check that the serialized transaction fits in a packet, which the node
+    /// rejects otherwise
+    pub fn check_size(&self) -> AtollResult<()> {
+        let mut signatures = Vec::new();
+        write_compact_u16(&mut signatures, self.signatures.len() as u16);
+        let signatures = signatures.len() + self.signatures.len() * 64;
+        let message = self.message.serialize().len();
+
+        match signatures + message {
+            size if size > PACKET_DATA_SIZE => Err(AtollError::TransactionTooLarge {
+                size,
+                limit: PACKET_DATA_SIZE,
+                signatures,
+                message,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Decode a transaction in the wire format, as returned by [Self::serialize]
+    pub fn deserialize(bytes: &[u8]) -> AtollResult<Self> {
+        let mut reader = WireReader::new(bytes);
+
+        let signatures = (0..reader.read_compact_u16()?)
+            .map(|_| reader.read_array::<64>())
+            .collect::<AtollResult<Vec<[u8; 64]>>>()?;
+        let message = VersionedMessage::decode(&mut reader)?;
+
+        if !reader.remaining().is_empty() {
+            return Err(AtollError::MalformedTransaction(format!(
+                "{} unexpected bytes after the transaction",
+                reader.remaining().len()
+            )));
+        }
+
+        let num_required_signatures = message.header().num_required_signatures as usize;
+        if signatures.len() != num_required_signatures {
+            return Err(AtollError::MalformedTransaction(format!(
+                "The transaction has {} signatures but its message requires {}",
+                signatures.len(),
+                num_required_signatures
+            )));
+        }
+
+        Ok(Transaction {
+            signatures,
+            message,
+        })
+    }
+
+    pub fn from_base64(transaction: &str) -> AtollResult<Self> {
+        Self::deserialize(&BASE64.decode(transaction.trim())?)
+    }
+
+    pub fn from_base58(transaction: &str) -> AtollResult<Self> {
+        let bytes = bs58::decode(transaction.trim()).into_vec().map_err(|_| {
+            AtollError::MalformedTransaction("The transaction is not valid base58".to_owned())
+        })?;
+
+        Self::deserialize(&bytes)
+    }
+
+    /// The wire format base64 encoded, the encoding `sendTransaction` prefers
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.serialize())
+    }
+
+    fn signer_position(&self, signer: &Pubkey) -> AtollResult<usize> {
+        self.message
+            .signers()
+            .iter()
+            .position(|key| key == signer)
+            .ok_or_else(|| {
+                AtollError::InvalidTransaction(format!("{signer} is not a signer of the message"))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tx::{AccountMeta, AddressLookupTableAccount, Instruction, Message, MessageV0},
+        WireMessage, SYSTEM_PROGRAM_ID,
+    };
+
+    const FROM: Pubkey = Pubkey::new([1; 32]);
+    const TO: Pubkey = Pubkey::new([2; 32]);
+
+    /// A System program transfer of 1_000_000 lamports from [FROM] to [TO]
+    fn transfer() -> Transaction {
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+
+        let instruction = Instruction::new(Pubkey::new(SYSTEM_PROGRAM_ID))
+            .add_account(AccountMeta::new(FROM, true))
+            .add_account(AccountMeta::new(TO, false))
+            .change_data(&data);
+
+        Transaction::new(
+            Message::new(&[instruction], &FROM)
+                .unwrap()
+                .change_recent_blockhash(Blockhash([7; 32])),
+        )
+    }
+
+    #[test]
+    fn serializes_like_solana_sdk() {
+        // `Transaction::new_unsigned(Message::new_with_blockhash(&[system_instruction::transfer(
+        // &from, &to, 1_000_000)], Some(&from), &Hash::new_from_array([7; 32])))` of solana-sdk
+        let mut expected = vec![1];
+        expected.extend_from_slice(&[0; 64]);
+        expected.extend_from_slice(&[1, 0, 1, 3]);
+        expected.extend_from_slice(&[1; 32]);
+        expected.extend_from_slice(&[2; 32]);
+        expected.extend_from_slice(&[0; 32]);
+        expected.extend_from_slice(&[7; 32]);
+        expected.extend_from_slice(&[
+            1, 2, 2, 0, 1, 12, 2, 0, 0, 0, 0x40, 0x42, 0x0f, 0, 0, 0, 0, 0,
+        ]);
+
+        let transaction = transfer();
+
+        assert_eq!(transaction.serialize(), expected);
+        assert_eq!(transaction.to_base64(), BASE64.encode(&expected));
+        assert_eq!(transaction.missing_signers(), vec![FROM.to_base58()]);
+    }
+
+    #[test]
+    fn round_trips_through_the_wire_format() {
+        let mut transaction = transfer();
+        transaction.signatures[0] = [5; 64];
+
+        let bytes = transaction.serialize();
+
+        assert_eq!(Transaction::deserialize(&bytes).unwrap(), transaction);
+        assert_eq!(
+            Transaction::from_base64(&transaction.to_base64()).unwrap(),
+            transaction
+        );
+        assert_eq!(
+            Transaction::from_base58(&bs58::encode(&bytes).into_string()).unwrap(),
+            transaction
+        );
+        assert_eq!(
+            transaction.signature(),
+            Some(bs58::encode([5; 64]).into_string())
+        );
+    }
+
+    #[test]
+    fn parses_wire_bytes() {
+        // A signed v0 transaction loading one writable and one read-only account
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&[5; 64]);
+        bytes.extend_from_slice(&[0x80, 1, 0, 1, 2]);
+        bytes.extend_from_slice(&[1; 32]);
+        bytes.extend_from_slice(&[0; 32]);
+        bytes.extend_from_slice(&[7; 32]);
+        bytes.extend_from_slice(&[1, 1, 3, 0, 2, 3, 1, 9]);
+        bytes.push(1);
+        bytes.extend_from_slice(&[8; 32]);
+        bytes.extend_from_slice(&[1, 0, 1, 1]);
+
+        let transaction = Transaction::deserialize(&bytes).unwrap();
+
+        assert_eq!(transaction.signatures, vec![[5; 64]]);
+        assert_eq!(transaction.message.fee_payer(), Some(&FROM));
+        assert_eq!(
+            transaction.message.instructions()[0].accounts,
+            vec![0, 2, 3]
+        );
+        let VersionedMessage::V0(message) = &transaction.message else {
+            panic!("Expected a v0 message");
+        };
+        assert_eq!(message.loaded_accounts(), 2);
+        assert_eq!(transaction.serialize(), bytes);
+
+        let wire = WireMessage::from_transaction_bytes(&bytes).unwrap();
+        assert_eq!(wire.num_required_signatures, 1);
+        assert!(wire.is_writable(0));
+        assert!(!wire.is_writable(1));
+        assert_eq!(wire.address_table_lookups[0].writable_indexes, vec![0]);
+    }
+
+    #[test]
+    fn rejects_truncated_transactions() {
+        let legacy = transfer().serialize();
+        let v0 = Transaction::new(
+            MessageV0::new(
+                &[Instruction::new(Pubkey::new(SYSTEM_PROGRAM_ID))
+                    .add_account(AccountMeta::new(FROM, true))
+                    .add_account(AccountMeta::new(TO, false))],
+                &FROM,
+                &[AddressLookupTableAccount::new(
+                    Pubkey::new([8; 32]),
+                    vec![TO],
+                )],
+            )
+            .unwrap(),
+        )
+        .serialize();
+
+        for bytes in [legacy, v0] {
+            for length in 0..bytes.len() {
+                assert!(
+                    matches!(
+                        Transaction::deserialize(&bytes[..length]),
+                        Err(AtollError::MalformedTransaction(_))
+                    ),
+                    "{length} of {} bytes",
+                    bytes.len()
+                );
+                assert!(WireMessage::from_transaction_bytes(&bytes[..length]).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_transactions() {
+        let bytes = transfer().serialize();
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Transaction::deserialize(&trailing).is_err());
+
+        // Two signatures for a message with one signer
+        let mut signatures = vec![2];
+        signatures.extend_from_slice(&[0; 64]);
+        signatures.extend_from_slice(&bytes[1..]);
+        assert!(Transaction::deserialize(&signatures).is_err());
+
+        // A compact-u16 signature count that never ends
+        assert!(Transaction::deserialize(&[0xff, 0xff, 0xff, 0x01]).is_err());
+        assert!(Transaction::from_base58("0OIl").is_err());
+        assert!(Transaction::from_base64("not base64").is_err());
+    }
+}
@@ -0,0 +1,520 @@
+use crate::{
+    tx::{
+        compile_header, compile_instructions, merge_accounts, serialize_instructions,
+        AddressLookupTableAccount, Blockhash, CompiledInstruction, Instruction, Message,
+        MessageHeader, MAX_MESSAGE_ACCOUNTS,
+    },
+    write_compact_u16, AtollError, AtollResult, Pubkey, WireReader,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// The first byte of a versioned message, the version in the low bits. Legacy
+/// messages start with the number of signers, which never has this bit set
+pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
+
+/// The accounts a v0 message loads from one address lookup table
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAddressTableLookup {
+    pub account_key: Pubkey,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// A v0 message, which loads accounts from address lookup tables. Instructions
+/// index the static account keys, then the writable and then the read-only
+/// accounts loaded from each table in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageV0 {
+    pub header: MessageHeader,
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Blockhash,
+    pub instructions: Vec<CompiledInstruction>,
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+impl MessageV0 {
+    /// Compile the instructions for the payer, loading every account that is in one
+    /// of the tables from the first table holding it. Signers and programs are
+    /// always static accounts since the runtime does not load them from tables
+    pub fn new(
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> AtollResult<Self> {
+        let mut accounts = merge_accounts(instructions, payer);
+        let is_program = |pubkey: &Pubkey| {
+            instructions
+                .iter()
+                .any(|instruction| instruction.program_id == *pubkey)
+        };
+
+        let mut address_table_lookups = Vec::new();
+        let mut loaded_writable = Vec::new();
+        let mut loaded_readonly = Vec::new();
+        for table in lookup_tables {
+            let mut lookup = MessageAddressTableLookup {
+                account_key: table.key,
+                ..Default::default()
+            };
+
+            accounts.retain(|account| {
+                if account.is_signer || is_program(&account.pubkey) {
+                    return true;
+                }
+                let Some(index) = table
+                    .addresses
+                    .iter()
+                    .take(MAX_MESSAGE_ACCOUNTS)
+                    .position(|address| *address == account.pubkey)
+                else {
+                    return true;
+                };
+
+                if account.is_writable {
+                    lookup.writable_indexes.push(index as u8);
+                    loaded_writable.push(account.pubkey);
+                } else {
+                    lookup.readonly_indexes.push(index as u8);
+                    loaded_readonly.push(account.pubkey);
+                }
+
+                false
+            });
+
+            if !lookup.writable_indexes.is_empty() || !lookup.readonly_indexes.is_empty() {
+                address_table_lookups.push(lookup);
+            }
+        }
+
+        let total = accounts.len() + loaded_writable.len() + loaded_readonly.len();
+        if total > MAX_MESSAGE_ACCOUNTS {
+            return Err(AtollError::InvalidTransaction(format!(
+                "The instructions use {total} accounts after the lookup tables, a message holds at most {MAX_MESSAGE_ACCOUNTS}"
+            )));
+        }
+
+        // A stable sort keeps the payer first and the order the accounts appeared in
+        accounts.sort_by_key(|account| (!account.is_signer, !account.is_writable));
+
+        let header = compile_header(&accounts);
+        let account_keys = accounts
+            .into_iter()
+            .map(|account| account.pubkey)
+            .collect::<Vec<Pubkey>>();
+        let keys = account_keys
+            .iter()
+            .chain(&loaded_writable)
+            .chain(&loaded_readonly)
+            .copied()
+            .collect::<Vec<Pubkey>>();
+
+        Ok(MessageV0 {
+            header,
+            instructions: compile_instructions(instructions, &keys),
+            account_keys,
+            recent_blockhash: Blockhash::default(),
+            address_table_lookups,
+        })
+    }
+
+    pub fn change_recent_blockhash(mut self, recent_blockhash: Blockhash) -> Self {
+        self.recent_blockhash = recent_blockhash;
+
+        self
+    }
+
+    /// The number of accounts loaded from lookup tables
+    pub fn loaded_accounts(&self) -> usize {
+        self.address_table_lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+            .sum()
+    }
+
+    /// Whether the instructions may change the account at `index`, counting the
+    /// accounts loaded from tables after the static ones
+    pub fn is_writable(&self, index: usize) -> bool {
+        let num_required_signatures = self.header.num_required_signatures as usize;
+        let num_static = self.account_keys.len();
+
+        if index < num_required_signatures {
+            index
+                < num_required_signatures
+                    .saturating_sub(self.header.num_readonly_signed_accounts as usize)
+        } else if index < num_static {
+            index < num_static.saturating_sub(self.header.num_readonly_unsigned_accounts as usize)
+        } else {
+            let loaded_writable = self
+                .address_table_lookups
+                .iter()
+                .map(|lookup| lookup.writable_indexes.len())
+                .sum::<usize>();
+
+            index < num_static + loaded_writable
+        }
+    }
+
+    /// The message in the wire format, the bytes each signer signs
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = vec![
+            MESSAGE_VERSION_PREFIX,
+            self.header.num_required_signatures,
+            self.header.num_readonly_signed_accounts,
+            self.header.num_readonly_unsigned_accounts,
+        ];
+
+        write_compact_u16(&mut bytes, self.account_keys.len() as u16);
+        self.account_keys
+            .iter()
+            .for_each(|key| bytes.extend_from_slice(&key.0));
+        bytes.extend_from_slice(&self.recent_blockhash.0);
+        serialize_instructions(&mut bytes, &self.instructions);
+
+        write_compact_u16(&mut bytes, self.address_table_lookups.len() as u16);
+        for lookup in &self.address_table_lookups {
+            bytes.extend_from_slice(&lookup.account_key.0);
+            write_compact_u16(&mut bytes, lookup.writable_indexes.len() as u16);
+            bytes.extend_from_slice(&lookup.writable_indexes);
+            write_compact_u16(&mut bytes, lookup.readonly_indexes.len() as u16);
+            bytes.extend_from_slice(&lookup.readonly_indexes);
+        }
+
+        bytes
+    }
+}
+
+/// A legacy or v0 message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionedMessage {
+    Legacy(Message),
+    V0(MessageV0),
+}
+
+impl VersionedMessage {
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            VersionedMessage::Legacy(message) => &message.header,
+            VersionedMessage::V0(message) => &message.header,
+        }
+    }
+
+    /// The accounts that are not loaded from lookup tables
+    pub fn static_account_keys(&self) -> &[Pubkey] {
+        match self {
+            VersionedMessage::Legacy(message) => &message.account_keys,
+            VersionedMessage::V0(message) => &message.account_keys,
+        }
+    }
+
+    pub fn recent_blockhash(&self) -> &Blockhash {
+        match self {
+            VersionedMessage::Legacy(message) => &message.recent_blockhash,
+            VersionedMessage::V0(message) => &message.recent_blockhash,
+        }
+    }
+
+    pub fn change_recent_blockhash(mut self, recent_blockhash: Blockhash) -> Self {
+        match &mut self {
+            VersionedMessage::Legacy(message) => message.recent_blockhash = recent_blockhash,
+            VersionedMessage::V0(message) => message.recent_blockhash = recent_blockhash,
+        }
+
+        self
+    }
+
+    pub fn instructions(&self) -> &[CompiledInstruction] {
+        match self {
+            VersionedMessage::Legacy(message) => &message.instructions,
+            VersionedMessage::V0(message) => &message.instructions,
+        }
+    }
+
+    /// The accounts that must sign, in the order of their signatures
+    pub fn signers(&self) -> &[Pubkey] {
+        let keys = self.static_account_keys();
+        let signers = (self.header().num_required_signatures as usize).min(keys.len());
+
+        &keys[..signers]
+    }
+
+    /// The account paying the fees, the first signer
+    pub fn fee_payer(&self) -> Option<&Pubkey> {
+        self.signers().first()
+    }
+
+    /// The message in the wire format, the bytes each signer signs
+    pub fn serialize(&self) -> Vec<u8> {
+        match self {
+            VersionedMessage::Legacy(message) => message.serialize(),
+            VersionedMessage::V0(message) => message.serialize(),
+        }
+    }
+
+    /// Decode a message in the wire format, as returned by [Self::serialize]
+    pub fn deserialize(bytes: &[u8]) -> AtollResult<Self> {
+        let mut reader = WireReader::new(bytes);
+        let message = Self::decode(&mut reader)?;
+
+        match reader.remaining().len() {
+            0 => Ok(message),
+            trailing => Err(AtollError::MalformedTransaction(format!(
+                "{trailing} unexpected bytes after the message"
+            ))),
+        }
+    }
+
+    /// The wire format base64 encoded, how an unsigned message is handed to an
+    /// offline signer
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.serialize())
+    }
+
+    pub fn from_base64(message: &str) -> AtollResult<Self> {
+        Self::deserialize(&BASE64.decode(message.trim())?)
+    }
+
+    pub(crate) fn decode(reader: &mut WireReader) -> AtollResult<Self> {
+        let first = reader.read_u8()?;
+        let versioned = first & MESSAGE_VERSION_PREFIX != 0;
+        if versioned && first != MESSAGE_VERSION_PREFIX {
+            return Err(AtollError::MalformedTransaction(format!(
+                "Unsupported message version {}",
+                first & !MESSAGE_VERSION_PREFIX
+            )));
+        }
+
+        let header = MessageHeader {
+            num_required_signatures: match versioned {
+                true => reader.read_u8()?,
+                false => first,
+            },
+            num_readonly_signed_accounts: reader.read_u8()?,
+            num_readonly_unsigned_accounts: reader.read_u8()?,
+        };
+        let account_keys = (0..reader.read_compact_u16()?)
+            .map(|_| Ok(Pubkey::new(reader.read_array::<32>()?)))
+            .collect::<AtollResult<Vec<Pubkey>>>()?;
+        let recent_blockhash = Blockhash(reader.read_array::<32>()?);
+        let instructions = (0..reader.read_compact_u16()?)
+            .map(|_| {
+                let program_id_index = reader.read_u8()?;
+                let accounts = read_vec(reader)?;
+                let data = read_vec(reader)?;
+
+                Ok(CompiledInstruction {
+                    program_id_index,
+                    accounts,
+                    data,
+                })
+            })
+            .collect::<AtollResult<Vec<CompiledInstruction>>>()?;
+
+        if !versioned {
+            return Ok(VersionedMessage::Legacy(Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+            }));
+        }
+
+        let address_table_lookups = (0..reader.read_compact_u16()?)
+            .map(|_| {
+                Ok(MessageAddressTableLookup {
+                    account_key: Pubkey::new(reader.read_array::<32>()?),
+                    writable_indexes: read_vec(reader)?,
+                    readonly_indexes: read_vec(reader)?,
+                })
+            })
+            .collect::<AtollResult<Vec<MessageAddressTableLookup>>>()?;
+
+        Ok(VersionedMessage::V0(MessageV0 {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups,
+        }))
+    }
+}
+
+/// Read a `compact-u16` prefixed byte vector
+fn read_vec(reader: &mut WireReader) -> AtollResult<Vec<u8>> {
+    let length = reader.read_compact_u16()? as usize;
+
+    Ok(reader.read_bytes(length)?.to_vec())
+}
+
+impl From<Message> for VersionedMessage {
+    fn from(message: Message) -> Self {
+        VersionedMessage::Legacy(message)
+    }
+}
+
+impl From<MessageV0> for VersionedMessage {
+    fn from(message: MessageV0) -> Self {
+        VersionedMessage::V0(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::{AccountMeta, Transaction};
+
+    const PAYER: Pubkey = Pubkey::new([1; 32]);
+    const STATIC: Pubkey = Pubkey::new([2; 32]);
+    const LOADED_WRITABLE: Pubkey = Pubkey::new([3; 32]);
+    const LOADED_READONLY: Pubkey = Pubkey::new([4; 32]);
+    const PROGRAM: Pubkey = Pubkey::new([9; 32]);
+    const TABLE: Pubkey = Pubkey::new([8; 32]);
+
+    /// A message whose payer and program are in the table but must stay static
+    fn message() -> MessageV0 {
+        let instruction = Instruction::new(PROGRAM)
+            .add_account(AccountMeta::new(PAYER, true))
+            .add_account(AccountMeta::new_readonly(LOADED_READONLY, false))
+            .add_account(AccountMeta::new(LOADED_WRITABLE, false))
+            .add_account(AccountMeta::new(STATIC, false))
+            .change_data(&[1, 2, 3]);
+        let table = AddressLookupTableAccount::new(
+            TABLE,
+            vec![
+                PAYER,
+                PROGRAM,
+                Pubkey::new([5; 32]),
+                LOADED_READONLY,
+                LOADED_WRITABLE,
+            ],
+        );
+
+        MessageV0::new(&[instruction], &PAYER, &[table])
+            .unwrap()
+            .change_recent_blockhash(Blockhash([7; 32]))
+    }
+
+    #[test]
+    fn orders_loaded_accounts_after_static_ones() {
+        let message = message();
+
+        assert_eq!(message.account_keys, vec![PAYER, STATIC, PROGRAM]);
+        assert_eq!(
+            message.header,
+            MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            }
+        );
+        assert_eq!(
+            message.address_table_lookups,
+            vec![MessageAddressTableLookup {
+                account_key: TABLE,
+                writable_indexes: vec![4],
+                readonly_indexes: vec![3],
+            }]
+        );
+        assert_eq!(message.loaded_accounts(), 2);
+        // The loaded writable account follows the static keys, the read-only one comes last
+        assert_eq!(message.instructions[0].program_id_index, 2);
+        assert_eq!(message.instructions[0].accounts, vec![0, 4, 3, 1]);
+        assert_eq!(
+            (0..5)
+                .map(|index| message.is_writable(index))
+                .collect::<Vec<_>>(),
+            vec![true, true, false, true, false]
+        );
+    }
+
+    #[test]
+    fn loads_accounts_from_the_first_table_holding_them() {
+        let instruction = Instruction::new(PROGRAM)
+            .add_account(AccountMeta::new(LOADED_WRITABLE, false))
+            .add_account(AccountMeta::new_readonly(LOADED_READONLY, false));
+        let tables = [
+            AddressLookupTableAccount::new(TABLE, vec![LOADED_READONLY]),
+            AddressLookupTableAccount::new(
+                Pubkey::new([6; 32]),
+                vec![LOADED_READONLY, LOADED_WRITABLE],
+            ),
+        ];
+
+        let message = MessageV0::new(&[instruction], &PAYER, &tables).unwrap();
+
+        assert_eq!(
+            message.address_table_lookups,
+            vec![
+                MessageAddressTableLookup {
+                    account_key: TABLE,
+                    writable_indexes: vec![],
+                    readonly_indexes: vec![0],
+                },
+                MessageAddressTableLookup {
+                    account_key: Pubkey::new([6; 32]),
+                    writable_indexes: vec![1],
+                    readonly_indexes: vec![],
+                },
+            ]
+        );
+        // Writable accounts of every table come before the read-only ones
+        assert_eq!(message.instructions[0].accounts, vec![2, 3]);
+    }
+
+    #[test]
+    fn serializes_the_v0_wire_format() {
+        let mut expected = vec![MESSAGE_VERSION_PREFIX, 1, 0, 1, 3];
+        expected.extend_from_slice(&[1; 32]);
+        expected.extend_from_slice(&[2; 32]);
+        expected.extend_from_slice(&[9; 32]);
+        expected.extend_from_slice(&[7; 32]);
+        // One instruction with four accounts and three bytes of data
+        expected.extend_from_slice(&[1, 2, 4, 0, 4, 3, 1, 3, 1, 2, 3]);
+        // One lookup with one writable and one read-only index
+        expected.push(1);
+        expected.extend_from_slice(&[8; 32]);
+        expected.extend_from_slice(&[1, 4, 1, 3]);
+
+        let bytes = message().serialize();
+
+        assert_eq!(bytes[0], 0x80);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn round_trips_through_the_wire_format() {
+        let message = VersionedMessage::V0(message());
+
+        assert_eq!(
+            VersionedMessage::deserialize(&message.serialize()).unwrap(),
+            message
+        );
+        assert_eq!(
+            VersionedMessage::from_base64(&message.to_base64()).unwrap(),
+            message
+        );
+
+        let mut transaction = Transaction::new(message);
+        transaction.signatures[0] = [5; 64];
+
+        assert_eq!(
+            Transaction::deserialize(&transaction.serialize()).unwrap(),
+            transaction
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        let mut bytes = message().serialize();
+        bytes[0] = MESSAGE_VERSION_PREFIX | 1;
+
+        assert!(matches!(
+            VersionedMessage::deserialize(&bytes),
+            Err(AtollError::MalformedTransaction(_))
+        ));
+    }
+}
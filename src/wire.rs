@@ -0,0 +1,197 @@
+use crate::{AtollError, AtollResult, Instruction};
+
+/// A cursor over the Solana wire format used to decode transactions and messages
+#[derive(Debug)]
+pub(crate) struct WireReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> WireReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        WireReader { bytes, position: 0 }
+    }
+
+    /// The bytes that have not been read yet
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+
+    pub(crate) fn read_u8(&mut self) -> AtollResult<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub(crate) fn read_bytes(&mut self, length: usize) -> AtollResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(AtollError::MalformedTransaction(
+                "Unexpected end of transaction bytes".to_owned(),
+            ))?;
+
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    pub(crate) fn read_array<const N: usize>(&mut self) -> AtollResult<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+
+        Ok(array)
+    }
+
+    /// Read a `compact-u16` (ShortVec) length prefix
+    pub(crate) fn read_compact_u16(&mut self) -> AtollResult<u16> {
+        let mut value = 0u32;
+
+        for index in 0..3 {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7f) as u32) << (index * 7);
+
+            if byte & 0x80 == 0 {
+                return u16::try_from(value).map_err(|_| {
+                    AtollError::MalformedTransaction("compact-u16 value overflow".to_owned())
+                });
+            }
+        }
+
+        Err(AtollError::MalformedTransaction(
+            "compact-u16 value is longer than 3 bytes".to_owned(),
+        ))
+    }
+}
+
+/// Append a `compact-u16` (ShortVec) length prefix
+pub(crate) fn write_compact_u16(bytes: &mut Vec<u8>, value: u16) {
+    let mut value = value;
+
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            bytes.push(byte);
+
+            return;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// The parts of a legacy or versioned message needed to inspect a transaction
+#[derive(Debug)]
+pub(crate) struct WireMessage {
+    pub(crate) num_required_signatures: u8,
+    pub(crate) num_readonly_signed_accounts: u8,
+    pub(crate) num_readonly_unsigned_accounts: u8,
+    pub(crate) account_keys: Vec<String>,
+    pub(crate) recent_blockhash: String,
+    pub(crate) instructions: Vec<Instruction>,
+    /// The lookup tables of a v0 message, empty for legacy messages
+    pub(crate) address_table_lookups: Vec<WireLookup>,
+}
+
+/// The writable accounts a v0 message loads from an address lookup table
+#[derive(Debug)]
+pub(crate) struct WireLookup {
+    pub(crate) account_key: String,
+    pub(crate) writable_indexes: Vec<u8>,
+}
+
+impl WireMessage {
+    /// Decode the message of a wire encoded transaction, skipping the signatures
+    pub(crate) fn from_transaction_bytes(bytes: &[u8]) -> AtollResult<Self> {
+        let mut reader = WireReader::new(bytes);
+
+        let signatures = reader.read_compact_u16()? as usize;
+        reader.read_bytes(signatures * 64)?;
+
+        Self::decode(&mut reader)
+    }
+
+    /// Decode the serialized message, which is the data signed by each signer
+    pub(crate) fn from_message_bytes(bytes: &[u8]) -> AtollResult<Self> {
+        Self::decode(&mut WireReader::new(bytes))
+    }
+
+    /// Check if the static account at `index` is writable
+    pub(crate) fn is_writable(&self, index: usize) -> bool {
+        let num_required_signatures = self.num_required_signatures as usize;
+
+        if index < num_required_signatures {
+            index
+                < num_required_signatures.saturating_sub(self.num_readonly_signed_accounts as usize)
+        } else {
+            index
+                < self
+                    .account_keys
+                    .len()
+                    .saturating_sub(self.num_readonly_unsigned_accounts as usize)
+        }
+    }
+
+    fn decode(reader: &mut WireReader) -> AtollResult<Self> {
+        let mut num_required_signatures = reader.read_u8()?;
+        let versioned = num_required_signatures & 0x80 != 0;
+        if versioned {
+            num_required_signatures = reader.read_u8()?;
+        }
+        let num_readonly_signed_accounts = reader.read_u8()?;
+        let num_readonly_unsigned_accounts = reader.read_u8()?;
+
+        let account_keys = (0..reader.read_compact_u16()?)
+            .map(|_| Ok(bs58::encode(reader.read_array::<32>()?).into_string()))
+            .collect::<AtollResult<Vec<String>>>()?;
+
+        let recent_blockhash = bs58::encode(reader.read_bytes(32)?).into_string();
+
+        let instructions = (0..reader.read_compact_u16()?)
+            .map(|_| {
+                let program_id_index = reader.read_u8()?;
+                let accounts_len = reader.read_compact_u16()? as usize;
+                let accounts = reader.read_bytes(accounts_len)?.to_vec();
+                let data_len = reader.read_compact_u16()? as usize;
+                let data = bs58::encode(reader.read_bytes(data_len)?).into_string();
+
+                Ok(Instruction {
+                    program_id_index,
+                    accounts,
+                    data,
+                })
+            })
+            .collect::<AtollResult<Vec<Instruction>>>()?;
+
+        let address_table_lookups = if versioned {
+            (0..reader.read_compact_u16()?)
+                .map(|_| {
+                    let account_key = bs58::encode(reader.read_array::<32>()?).into_string();
+                    let writable_len = reader.read_compact_u16()? as usize;
+                    let writable_indexes = reader.read_bytes(writable_len)?.to_vec();
+                    let readonly_len = reader.read_compact_u16()? as usize;
+                    reader.read_bytes(readonly_len)?;
+
+                    Ok(WireLookup {
+                        account_key,
+                        writable_indexes,
+                    })
+                })
+                .collect::<AtollResult<Vec<WireLookup>>>()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(WireMessage {
+            num_required_signatures,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups,
+        })
+    }
+}